@@ -1,5 +1,8 @@
 use crate::engine::input_state::InputState;
+use crate::renderer::post_process::PostProcessStack;
 use crate::renderer::renderer::Renderer;
+#[cfg(feature = "physics")]
+use crate::physics::physics::Physics;
 use glm::Vec2;
 use sdl3::event::{Event, WindowEvent};
 use sdl3::video::Window;
@@ -10,6 +13,9 @@ pub struct Engine {
     pub(crate) aspect_ratio: f32,
     pub(crate) should_close: bool,
     pub(crate) renderer: Renderer,
+    pub(crate) post_process: PostProcessStack,
+    #[cfg(feature = "physics")]
+    pub(crate) physics: Physics,
 }
 
 impl Engine {
@@ -21,7 +27,10 @@ impl Engine {
             input: InputState::new(window_size),
             aspect_ratio,
             should_close: false,
-            renderer: Renderer::new()
+            renderer: Renderer::new(),
+            post_process: PostProcessStack::new(),
+            #[cfg(feature = "physics")]
+            physics: Physics::new(),
         }
     }
     
@@ -33,6 +42,21 @@ impl Engine {
         &mut self.renderer
     }
     
+    /// Full-screen passes applied to every frame, in order.
+    pub fn post_process(&mut self) -> &mut PostProcessStack {
+        &mut self.post_process
+    }
+    
+    #[cfg(feature = "physics")]
+    pub fn physics(&self) -> &Physics {
+        &self.physics
+    }
+    
+    #[cfg(feature = "physics")]
+    pub fn physics_mut(&mut self) -> &mut Physics {
+        &mut self.physics
+    }
+    
     pub fn aspect_ratio(&self) -> f32 {
         self.aspect_ratio
     }
@@ -7,10 +7,14 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 use image::Frame;
 use sdl3::keyboard::Keycode;
+use crate::renderer::bloom::{Bloom, BloomSettings};
 use crate::renderer::frame_buffer::Framebuffer;
+use crate::renderer::tonemap::AutoExposure;
 use crate::renderer::mesh_data::MeshData;
 use crate::renderer::texture::{TextureSpec, TextureUsage};
 use crate::{Mesh, Shader, Texture};
+#[cfg(feature = "physics")]
+use crate::physics::physics::FIXED_TIME_STEP;
 
 pub enum AppControl {
     Continue,
@@ -26,15 +30,19 @@ pub trait App {
         AppControl::Continue
     }
 
+    /// Called after every fixed physics step (`physics` feature), sync bodies into render objects here.
+    fn fixed_update(&mut self, _engine: &mut Engine, _dt: f32) {}
+
     fn update(&mut self, engine: &mut Engine, dt: Time);
     fn render(&mut self, engine: &mut Engine);
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AppConfig {
     pub width: u32,
     pub height: u32,
-    pub title: &'static str,
+    pub title: String,
     pub max_fps: Option<u32>,
 }
 
@@ -43,7 +51,7 @@ impl Default for AppConfig {
         Self {
             width: 1280,
             height: 720,
-            title: "bun app",
+            title: "bun app".to_string(),
             max_fps: Some(60),
         }
     }
@@ -72,7 +80,7 @@ pub fn run<A: App>(mut config: AppConfig, mut app: A) -> Result<(), String> {
     gl_attr.set_multisample_samples(4);
 
     let window = video_subsystem
-        .window(config.title, config.width, config.height)
+        .window(&config.title, config.width, config.height)
         .opengl()
         .position_centered()
         .resizable()
@@ -108,6 +116,9 @@ pub fn run<A: App>(mut config: AppConfig, mut app: A) -> Result<(), String> {
     framebuffer.bind();
     
     let screen_quad = Mesh::from_mesh_data(&MeshData::screen_quad());
+    let mut bloom = Bloom::new(config.width, config.height, BloomSettings::default().mip_count)?;
+    // created the first time auto exposure is turned on
+    let mut auto_exposure: Option<AutoExposure> = None;
     let screen_shader = Shader::new(&PathBuf::from("bun/src/res/shaders/screen.vert"), &PathBuf::from("bun/src/res/shaders/screen.frag"))?;
 
     let mut engine = Engine::new(window, config.width as f32 / config.height as f32);
@@ -157,18 +168,82 @@ pub fn run<A: App>(mut config: AppConfig, mut app: A) -> Result<(), String> {
         elapsed_secs += dt;
         last_frame = now;
 
+        #[cfg(feature = "physics")]
+        {
+            engine.physics.begin_frame(dt);
+            while engine.physics.try_step() {
+                app.fixed_update(&mut engine, FIXED_TIME_STEP);
+            }
+        }
+
         app.update(&mut engine, Time { dt, elapsed_secs });
         
         framebuffer.bind();
         app.render(&mut engine);
         
         
+        let hdr_texture = engine.post_process.render(framebuffer.screen_texture_id(), config.width, config.height, elapsed_secs);
+
+        let bloom_settings = *engine.renderer.bloom_settings();
+        let bloom_intensity = if bloom_settings.enabled {
+            if let Err(e) = bloom.resize(config.width, config.height, bloom_settings.mip_count) {
+                eprintln!("bloom resize failed: {}", e);
+            }
+            bloom.render(hdr_texture, &bloom_settings);
+            bloom_settings.intensity
+        } else {
+            0.0
+        };
+
+        let tonemap_settings = *engine.renderer.tonemap_settings();
+        let mut use_auto_exposure = false;
+        if let Some(settings) = &tonemap_settings.auto_exposure {
+            if auto_exposure.is_none() {
+                match AutoExposure::new() {
+                    Ok(created) => auto_exposure = Some(created),
+                    Err(e) => {
+                        eprintln!("Disabling auto exposure: {}", e);
+                        let mut disabled = tonemap_settings;
+                        disabled.auto_exposure = None;
+                        engine.renderer.set_tonemap_settings(disabled);
+                    }
+                }
+            }
+            if let Some(auto_exposure) = &auto_exposure {
+                auto_exposure.render(hdr_texture, config.width, config.height, dt, settings);
+                use_auto_exposure = true;
+            }
+        }
+
         Framebuffer::bind_default();
         unsafe {
             gl::Disable(gl::DEPTH_TEST);
             gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
         }
-        engine.renderer.render_screen_quad(&screen_quad, &screen_shader, framebuffer.screen_texture_id());
+        screen_shader.bind();
+        if let Some(loc) = screen_shader.get_uniform_location("bloom_intensity") {
+            screen_shader.set_uniform(loc, bloom_intensity);
+        }
+        if let Some(loc) = screen_shader.get_uniform_location("exposure") {
+            screen_shader.set_uniform(loc, tonemap_settings.exposure);
+        }
+        if let Some(loc) = screen_shader.get_uniform_location("auto_exposure") {
+            screen_shader.set_uniform(loc, use_auto_exposure as i32);
+        }
+        if let Some(loc) = screen_shader.get_uniform_location("tonemapper") {
+            screen_shader.set_uniform(loc, tonemap_settings.tonemapper.shader_index());
+        }
+        let debug_texture = engine.renderer.debug_texture();
+        if let Some(loc) = screen_shader.get_uniform_location("show_debug_texture") {
+            screen_shader.set_uniform(loc, debug_texture.is_some() as i32);
+        }
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE1);
+            gl::BindTexture(gl::TEXTURE_2D, bloom.texture_id());
+            gl::ActiveTexture(gl::TEXTURE2);
+            gl::BindTexture(gl::TEXTURE_2D, debug_texture.unwrap_or(0));
+        }
+        engine.renderer.render_screen_quad(&screen_quad, &screen_shader, hdr_texture);
         
         engine.window.gl_swap_window();
 
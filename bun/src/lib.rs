@@ -1,5 +1,11 @@
 pub mod renderer;
 pub mod engine;
+#[cfg(feature = "physics")]
+pub mod physics;
+#[cfg(feature = "scene")]
+pub mod scene;
+#[cfg(feature = "serde")]
+pub mod serde_glm;
 
 pub use renderer::buffer::Buffer;
 pub use renderer::camera::Camera;
@@ -12,6 +18,8 @@ pub use renderer::vertex::Vertex;
 pub use fastrand;
 pub use gl;
 pub use glm;
+#[cfg(feature = "physics")]
+pub use rapier3d;
 pub use num_traits::One;
 pub use num_traits::Zero;
 pub use sdl3::{self, event::Event, keyboard::Keycode};
@@ -0,0 +1,276 @@
+use crate::renderer::mesh_data::MeshData;
+use crate::renderer::render_object::RenderObject;
+use crate::{Transform, Vertex};
+use rapier3d::prelude::*;
+use std::sync::mpsc::{channel, Receiver};
+
+pub const FIXED_TIME_STEP: f32 = 1.0 / 60.0;
+// upper bound so a long hitch doesn't make the simulation spiral
+const MAX_STEPS_PER_FRAME: u32 = 8;
+/// Default for `Physics::set_contact_force_threshold`, in newtons. About twice the weight of a
+/// 1 kg body, so bodies resting on each other stay quiet.
+pub const DEFAULT_CONTACT_FORCE_THRESHOLD: f32 = 20.0;
+
+pub enum ColliderShape {
+    Cuboid { half_extents: glm::Vec3 },
+    Ball { radius: f32 },
+    Capsule { half_height: f32, radius: f32 },
+    TriMesh { vertices: Vec<glm::Vec3>, indices: Vec<[u32; 3]> },
+    ConvexHull { points: Vec<glm::Vec3> },
+}
+
+impl ColliderShape {
+    /// Exact triangle mesh collider. Only use it for static geometry, dynamic trimeshes are slow and hollow.
+    pub fn trimesh(mesh_data: &MeshData<Vertex>) -> Self {
+        let vertices = mesh_data.vertices().iter().map(|v| v.v).collect();
+        let indices = mesh_data
+            .indices()
+            .chunks_exact(3)
+            .map(|tri| [tri[0], tri[1], tri[2]])
+            .collect();
+        ColliderShape::TriMesh { vertices, indices }
+    }
+
+    pub fn convex_hull(mesh_data: &MeshData<Vertex>) -> Self {
+        let points = mesh_data.vertices().iter().map(|v| v.v).collect();
+        ColliderShape::ConvexHull { points }
+    }
+
+    /// Builds the rapier collider. Shapes are given in mesh space and get scaled like the render mesh.
+    fn build(&self, scale: glm::Vec3) -> Result<ColliderBuilder, String> {
+        let uniform_scale = scale.x.abs().max(scale.y.abs()).max(scale.z.abs());
+        let builder = match self {
+            ColliderShape::Cuboid { half_extents } => {
+                let h = *half_extents * scale;
+                ColliderBuilder::cuboid(h.x.abs(), h.y.abs(), h.z.abs())
+            }
+            ColliderShape::Ball { radius } => ColliderBuilder::ball(radius * uniform_scale),
+            ColliderShape::Capsule { half_height, radius } => {
+                ColliderBuilder::capsule_y(half_height * scale.y.abs(), radius * uniform_scale)
+            }
+            ColliderShape::TriMesh { vertices, indices } => {
+                let vertices = vertices.iter().map(|v| to_vector(*v * scale)).collect();
+                ColliderBuilder::trimesh(vertices, indices.clone())
+                    .map_err(|e| format!("Could not build trimesh collider: {:?}", e))?
+            }
+            ColliderShape::ConvexHull { points } => {
+                let points: Vec<Vector> = points.iter().map(|v| to_vector(*v * scale)).collect();
+                ColliderBuilder::convex_hull(&points)
+                    .ok_or_else(|| "Could not build convex hull collider".to_string())?
+            }
+        };
+        Ok(builder)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BodyKind {
+    Dynamic,
+    Fixed,
+    /// Moved by the app through `Physics::set_kinematic_target`.
+    Kinematic,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RayHit {
+    pub body: Option<RigidBodyHandle>,
+    pub collider: ColliderHandle,
+    pub distance: f32,
+    pub point: glm::Vec3,
+    pub normal: glm::Vec3,
+}
+
+pub struct Physics {
+    world: PhysicsWorld,
+    accumulator: f32,
+
+    event_collector: ChannelEventCollector,
+    collision_receiver: Receiver<CollisionEvent>,
+    contact_force_receiver: Receiver<ContactForceEvent>,
+    collision_events: Vec<CollisionEvent>,
+    contact_force_events: Vec<ContactForceEvent>,
+    contact_force_threshold: f32,
+}
+
+impl Physics {
+    pub fn new() -> Self {
+        let (collision_sender, collision_receiver) = channel();
+        let (contact_force_sender, contact_force_receiver) = channel();
+        // tearing soft bodies isn't used, dropping the receiver discards those events
+        let (soft_body_tear_sender, _) = channel();
+        let mut world = PhysicsWorld::new();
+        world.integration_parameters.dt = FIXED_TIME_STEP;
+        Self {
+            world,
+            accumulator: 0.0,
+            event_collector: ChannelEventCollector::new(collision_sender, contact_force_sender, soft_body_tear_sender),
+            collision_receiver,
+            contact_force_receiver,
+            collision_events: vec![],
+            contact_force_events: vec![],
+            contact_force_threshold: DEFAULT_CONTACT_FORCE_THRESHOLD,
+        }
+    }
+
+    pub fn gravity(&self) -> glm::Vec3 {
+        from_vector(self.world.gravity)
+    }
+
+    pub fn set_gravity(&mut self, gravity: glm::Vec3) {
+        self.world.gravity = to_vector(gravity);
+    }
+
+    /// Adds a body with a single collider, placed and scaled like `transform`.
+    pub fn add_body(&mut self, transform: &Transform, kind: BodyKind, shape: &ColliderShape) -> Result<RigidBodyHandle, String> {
+        let builder = match kind {
+            BodyKind::Dynamic => RigidBodyBuilder::dynamic(),
+            BodyKind::Fixed => RigidBodyBuilder::fixed(),
+            BodyKind::Kinematic => RigidBodyBuilder::kinematic_position_based(),
+        };
+        let body = builder.pose(to_pose(transform));
+        let collider = shape
+            .build(transform.scale())?
+            .active_events(ActiveEvents::COLLISION_EVENTS | ActiveEvents::CONTACT_FORCE_EVENTS)
+            .contact_force_event_threshold(self.contact_force_threshold);
+        let (handle, _) = self.world.insert(body, collider);
+        Ok(handle)
+    }
+
+    pub fn remove_body(&mut self, handle: RigidBodyHandle) {
+        self.world.remove_body(handle);
+    }
+
+    pub fn body(&self, handle: RigidBodyHandle) -> Option<&RigidBody> {
+        self.world.bodies.get(handle)
+    }
+
+    pub fn body_mut(&mut self, handle: RigidBodyHandle) -> Option<&mut RigidBody> {
+        self.world.bodies.get_mut(handle)
+    }
+
+    /// Direct access to the rapier world for everything not wrapped here.
+    pub fn world(&self) -> &PhysicsWorld {
+        &self.world
+    }
+
+    pub fn world_mut(&mut self) -> &mut PhysicsWorld {
+        &mut self.world
+    }
+
+    pub fn set_kinematic_target(&mut self, handle: RigidBodyHandle, transform: &Transform) {
+        if let Some(body) = self.world.bodies.get_mut(handle) {
+            body.set_next_kinematic_position(to_pose(transform));
+        }
+    }
+
+    pub fn apply_impulse(&mut self, handle: RigidBodyHandle, impulse: glm::Vec3) {
+        if let Some(body) = self.world.bodies.get_mut(handle) {
+            body.apply_impulse(to_vector(impulse), true);
+        }
+    }
+
+    /// Clears the events of the previous frame and adds `dt` to the step accumulator.
+    pub(crate) fn begin_frame(&mut self, dt: f32) {
+        self.collision_events.clear();
+        self.contact_force_events.clear();
+        self.accumulator = (self.accumulator + dt).min(FIXED_TIME_STEP * MAX_STEPS_PER_FRAME as f32);
+    }
+
+    /// Runs one fixed step if enough time has accumulated.
+    pub(crate) fn try_step(&mut self) -> bool {
+        if self.accumulator < FIXED_TIME_STEP {
+            return false;
+        }
+        self.accumulator -= FIXED_TIME_STEP;
+        self.step();
+        true
+    }
+
+    pub fn step(&mut self) {
+        self.world.step_with_events(&(), &self.event_collector);
+        self.collision_events.extend(self.collision_receiver.try_iter());
+        self.contact_force_events.extend(self.contact_force_receiver.try_iter());
+    }
+
+    /// Collision start/stop events of all steps this frame.
+    pub fn collision_events(&self) -> &[CollisionEvent] {
+        &self.collision_events
+    }
+
+    /// Contacts of all steps this frame that pushed harder than the contact force threshold.
+    pub fn contact_force_events(&self) -> &[ContactForceEvent] {
+        &self.contact_force_events
+    }
+
+    pub fn contact_force_threshold(&self) -> f32 {
+        self.contact_force_threshold
+    }
+
+    /// Smallest total force of a contact that is reported in `contact_force_events`, applies
+    /// to every collider including the existing ones.
+    pub fn set_contact_force_threshold(&mut self, threshold: f32) {
+        self.contact_force_threshold = threshold;
+        for (_, collider) in self.world.colliders.iter_mut() {
+            collider.set_contact_force_event_threshold(threshold);
+        }
+    }
+
+    /// Body owning the collider, fixed colliders without a body return `None`.
+    pub fn collider_body(&self, collider: ColliderHandle) -> Option<RigidBodyHandle> {
+        self.world.colliders.get(collider).and_then(|c| c.parent())
+    }
+
+    pub fn cast_ray(&self, origin: glm::Vec3, direction: glm::Vec3, max_distance: f32) -> Option<RayHit> {
+        self.cast_ray_filtered(origin, direction, max_distance, QueryFilter::default())
+    }
+
+    pub fn cast_ray_filtered(&self, origin: glm::Vec3, direction: glm::Vec3, max_distance: f32, filter: QueryFilter) -> Option<RayHit> {
+        let ray = Ray::new(to_vector(origin), to_vector(glm::normalize(direction)));
+        let (collider, hit) = self.world.cast_ray_and_get_normal(&ray, max_distance, true, filter)?;
+        Some(RayHit {
+            body: self.collider_body(collider),
+            collider,
+            distance: hit.time_of_impact,
+            point: from_vector(ray.point_at(hit.time_of_impact)),
+            normal: from_vector(hit.normal),
+        })
+    }
+
+    /// Copies the body pose into the transform, scale is left untouched.
+    pub fn sync_transform(&self, handle: RigidBodyHandle, transform: &mut Transform) {
+        if let Some(body) = self.world.bodies.get(handle) {
+            transform.set_pos(from_vector(body.translation()));
+            let q = body.rotation();
+            transform.set_rotation(Transform::euler_from_quaternion([q.x, q.y, q.z, q.w]));
+        }
+    }
+
+    pub fn sync_object(&self, handle: RigidBodyHandle, object: &mut RenderObject) {
+        self.sync_transform(handle, object.transform_mut());
+    }
+}
+
+impl Default for Physics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn to_vector(v: glm::Vec3) -> Vector {
+    Vector::new(v.x, v.y, v.z)
+}
+
+fn from_vector(v: Vector) -> glm::Vec3 {
+    glm::Vec3::new(v.x, v.y, v.z)
+}
+
+fn to_pose(transform: &Transform) -> Pose {
+    Pose::from_parts(to_vector(transform.pos()), euler_to_quat(transform.rotation()))
+}
+
+// `Transform` rotates as Rx * Ry * Rz
+fn euler_to_quat(rotation: glm::Vec3) -> Rotation {
+    Rotation::from_rotation_x(rotation.x)
+        * Rotation::from_rotation_y(rotation.y)
+        * Rotation::from_rotation_z(rotation.z)
+}
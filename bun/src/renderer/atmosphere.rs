@@ -0,0 +1,188 @@
+use crate::renderer::light::Light;
+use glm::{dot, normalize, Vec3};
+use std::f32::consts::PI;
+
+/// Scales the Preetham luminance (kcd/m²) down to the range of the scene lights,
+/// a clear noon zenith ends up around 1.
+pub const SKY_LUMINANCE_SCALE: f32 = 0.1;
+
+/// Analytic daylight sky (Preetham et al. 1999) and the sun lighting the scene, both
+/// driven by the same sun position. Use it as `SkySource::Atmosphere` and add
+/// [`Atmosphere::sun_light`] to the renderer every frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Atmosphere {
+    /// Haze, 2 is a very clear sky and 10 a hazy one. The model breaks down outside 1.7..10.
+    pub turbidity: f32,
+    /// Hours, the sun rises at 6, is highest at 12 and sets at 18.
+    pub time_of_day: f32,
+    /// Sun elevation at noon in radians.
+    pub noon_elevation: f32,
+    /// Radians around the up axis the sun rises at, measured from +X towards +Z.
+    /// It sets on the opposite side.
+    pub sunrise_azimuth: f32,
+    /// Intensity of the sun light straight from the zenith, the atmosphere dims it from there.
+    pub sun_intensity: f32,
+}
+
+impl Default for Atmosphere {
+    fn default() -> Self {
+        Self {
+            turbidity: 2.5,
+            time_of_day: 10.0,
+            noon_elevation: 60f32.to_radians(),
+            sunrise_azimuth: 0.0,
+            sun_intensity: 10.0,
+        }
+    }
+}
+
+/// Perez distribution coefficients and zenith values in Yxy color space, one channel per
+/// vector component (x = luminance Y, y = chromaticity x, z = chromaticity y).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PreethamCoefficients {
+    pub a: Vec3,
+    pub b: Vec3,
+    pub c: Vec3,
+    pub d: Vec3,
+    pub e: Vec3,
+    /// Zenith Yxy already divided by the distribution at the zenith, so the sky only
+    /// has to multiply it with the distribution of the view direction.
+    pub zenith: Vec3,
+}
+
+impl Atmosphere {
+    pub fn with_time_of_day(mut self, hours: f32) -> Self {
+        self.time_of_day = hours;
+        self
+    }
+
+    /// Unit vector from the ground towards the sun, below the horizon at night.
+    pub fn sun_direction(&self) -> Vec3 {
+        // the sun moves on a great circle through the sunrise and sunset points, tilted
+        // up to `noon_elevation`
+        let hour_angle = (self.time_of_day - 6.0) / 12.0 * PI;
+        let local = Vec3::new(
+            hour_angle.cos(),
+            hour_angle.sin() * self.noon_elevation.sin(),
+            hour_angle.sin() * self.noon_elevation.cos(),
+        );
+        let (sin, cos) = self.sunrise_azimuth.sin_cos();
+        normalize(Vec3::new(cos * local.x - sin * local.z, local.y, sin * local.x + cos * local.z))
+    }
+
+    /// Radians above the horizon.
+    pub fn sun_elevation(&self) -> f32 {
+        self.sun_direction().y.clamp(-1.0, 1.0).asin()
+    }
+
+    /// Transmittance of the direct sun light through the atmosphere, per RGB channel.
+    /// Reddens and darkens towards the horizon and drops to zero below it.
+    pub fn sun_transmittance(&self) -> Vec3 {
+        let elevation = self.sun_elevation();
+        // fade out over the last few degrees instead of a hard cut at the horizon
+        let visible = smoothstep(-0.05, 0.02, elevation);
+        if visible <= 0.0 {
+            return Vec3::new(0.0, 0.0, 0.0);
+        }
+
+        // relative optical air mass (Kasten and Young 1989)
+        let zenith_degrees = 90.0 - elevation.max(0.0).to_degrees();
+        let mass = 1.0 / (zenith_degrees.to_radians().cos() + 0.50572 * (96.07995 - zenith_degrees).powf(-1.6364));
+
+        // Rayleigh and Angström aerosol optical depths at 680, 550 and 440 nm
+        let beta = 0.04608 * self.turbidity - 0.04586;
+        let depth = |wavelength: f32, rayleigh: f32| rayleigh + beta * wavelength.powf(-1.3);
+        let transmittance = |depth: f32| (-mass * depth).exp() * visible;
+        Vec3::new(
+            transmittance(depth(0.68, 0.0411)),
+            transmittance(depth(0.55, 0.0975)),
+            transmittance(depth(0.44, 0.2430)),
+        )
+    }
+
+    /// Directional light of the sun for the PBR shader.
+    pub fn sun_light(&self) -> Light {
+        Light::directional(-self.sun_direction(), self.sun_transmittance(), self.sun_intensity)
+    }
+
+    /// Model coefficients for the current sun and turbidity. The sun is clamped to the
+    /// horizon, the model is undefined below it.
+    pub fn preetham(&self) -> PreethamCoefficients {
+        let t = self.turbidity;
+        let theta_s = (0.5 * PI - self.sun_elevation()).min(0.5 * PI - 0.01);
+
+        let a = Vec3::new(0.1787 * t - 1.4630, -0.0193 * t - 0.2592, -0.0167 * t - 0.2608);
+        let b = Vec3::new(-0.3554 * t + 0.4275, -0.0665 * t + 0.0008, -0.0950 * t + 0.0092);
+        let c = Vec3::new(-0.0227 * t + 5.3251, -0.0004 * t + 0.2125, -0.0079 * t + 0.2102);
+        let d = Vec3::new(0.1206 * t - 2.5771, -0.0641 * t - 0.8989, -0.0441 * t - 1.6537);
+        let e = Vec3::new(-0.0670 * t + 0.3703, -0.0033 * t + 0.0452, -0.0109 * t + 0.0529);
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta_s);
+        let zenith_luminance = ((4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192).max(0.0);
+        let cubic = |k: [f32; 4]| k[0] * theta_s.powi(3) + k[1] * theta_s.powi(2) + k[2] * theta_s + k[3];
+        let zenith_x = t * t * cubic([0.00166, -0.00375, 0.00209, 0.0])
+            + t * cubic([-0.02903, 0.06377, -0.03202, 0.00394])
+            + cubic([0.11693, -0.21196, 0.06052, 0.25886]);
+        let zenith_y = t * t * cubic([0.00275, -0.00610, 0.00317, 0.0])
+            + t * cubic([-0.04214, 0.08970, -0.04153, 0.00516])
+            + cubic([0.15346, -0.26756, 0.06670, 0.26688]);
+
+        let mut coefficients = PreethamCoefficients { a, b, c, d, e, zenith: Vec3::new(0.0, 0.0, 0.0) };
+        let at_zenith = coefficients.perez(0.0, theta_s);
+        coefficients.zenith = Vec3::new(zenith_luminance / at_zenith.x, zenith_x / at_zenith.y, zenith_y / at_zenith.z);
+        coefficients
+    }
+
+    /// Linear RGB sky radiance in `direction`, without the sun disk. Matches the sky shader.
+    pub fn sky_radiance(&self, direction: Vec3) -> Vec3 {
+        let coefficients = self.preetham();
+        let direction = normalize(direction);
+        let sun = self.sun_direction();
+        // below the horizon the sky stays at the horizon color
+        let cos_theta = direction.y.max(0.001);
+        let gamma = dot(direction, sun).clamp(-1.0, 1.0).acos();
+
+        let yxy = coefficients.perez(cos_theta.acos(), gamma) * coefficients.zenith;
+        yxy_to_rgb(Vec3::new(yxy.x.max(0.0), yxy.y, yxy.z)) * SKY_LUMINANCE_SCALE * self.daylight()
+    }
+
+    /// 1 during the day, fading to 0 at night. The sky darkens with it.
+    pub fn daylight(&self) -> f32 {
+        smoothstep(-0.1, 0.02, self.sun_elevation())
+    }
+}
+
+impl PreethamCoefficients {
+    /// Perez distribution for a view zenith angle `theta` and angle `gamma` to the sun.
+    pub fn perez(&self, theta: f32, gamma: f32) -> Vec3 {
+        let cos_theta = theta.cos().max(0.001);
+        let cos_gamma = gamma.cos();
+        let channel = |a: f32, b: f32, c: f32, d: f32, e: f32| {
+            (1.0 + a * (b / cos_theta).exp()) * (1.0 + c * (d * gamma).exp() + e * cos_gamma * cos_gamma)
+        };
+        Vec3::new(
+            channel(self.a.x, self.b.x, self.c.x, self.d.x, self.e.x),
+            channel(self.a.y, self.b.y, self.c.y, self.d.y, self.e.y),
+            channel(self.a.z, self.b.z, self.c.z, self.d.z, self.e.z),
+        )
+    }
+}
+
+fn yxy_to_rgb(yxy: Vec3) -> Vec3 {
+    let (luminance, x, y) = (yxy.x, yxy.y, yxy.z);
+    if y <= 0.0 {
+        return Vec3::new(0.0, 0.0, 0.0);
+    }
+    let big_x = x * luminance / y;
+    let big_z = (1.0 - x - y) * luminance / y;
+    Vec3::new(
+        (3.2406 * big_x - 1.5372 * luminance - 0.4986 * big_z).max(0.0),
+        (-0.9689 * big_x + 1.8758 * luminance + 0.0415 * big_z).max(0.0),
+        (0.0557 * big_x - 0.2040 * luminance + 1.0570 * big_z).max(0.0),
+    )
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
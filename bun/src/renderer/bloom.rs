@@ -0,0 +1,204 @@
+use crate::Shader;
+use gl::types::{GLint, GLsizei};
+use std::ptr::null;
+
+/// Threshold free bloom (Jimenez 2014, "Next Generation Post Processing in Call of Duty:
+/// Advanced Warfare"), set with `Renderer::set_bloom_settings`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BloomSettings {
+    pub enabled: bool,
+    /// How much of the blurred image is mixed into the frame before tonemapping.
+    pub intensity: f32,
+    /// Radius of the upsample tent filter in UV units, larger is a wider glow.
+    pub radius: f32,
+    /// Length of the downsample chain, every mip adds a wider but fainter halo.
+    pub mip_count: u32,
+}
+
+impl Default for BloomSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            intensity: 0.04,
+            radius: 0.005,
+            mip_count: 6,
+        }
+    }
+}
+
+/// Sizes of the bloom mips for a `width` x `height` frame, each half the previous one,
+/// starting at half resolution. Stops early once a side would drop below 2 pixels.
+pub fn bloom_mip_sizes(width: u32, height: u32, mip_count: u32) -> Vec<(u32, u32)> {
+    let mut sizes = vec![];
+    let (mut w, mut h) = (width / 2, height / 2);
+    while (sizes.len() as u32) < mip_count && w >= 2 && h >= 2 {
+        sizes.push((w, h));
+        w /= 2;
+        h /= 2;
+    }
+    sizes
+}
+
+struct BloomMip {
+    texture: u32,
+    width: u32,
+    height: u32,
+}
+
+/// Half float mip chain and shaders of the bloom pass.
+pub struct Bloom {
+    fbo: u32,
+    vao: u32,
+    mips: Vec<BloomMip>,
+    width: u32,
+    height: u32,
+    mip_count: u32,
+    downsample: Shader,
+    upsample: Shader,
+}
+
+impl Bloom {
+    pub fn new(width: u32, height: u32, mip_count: u32) -> Result<Self, String> {
+        let downsample = Shader::from_source(
+            include_str!("../res/shaders/fullscreen.vert").to_string(),
+            include_str!("../res/shaders/bloom_downsample.frag").to_string(),
+        )?;
+        let upsample = Shader::from_source(
+            include_str!("../res/shaders/fullscreen.vert").to_string(),
+            include_str!("../res/shaders/bloom_upsample.frag").to_string(),
+        )?;
+        let mut fbo = 0;
+        let mut vao = 0;
+        unsafe {
+            gl::GenFramebuffers(1, &mut fbo);
+            gl::GenVertexArrays(1, &mut vao);
+        }
+        let mut bloom = Self { fbo, vao, mips: vec![], width: 0, height: 0, mip_count: 0, downsample, upsample };
+        bloom.resize(width, height, mip_count)?;
+        Ok(bloom)
+    }
+
+    pub fn resize(&mut self, width: u32, height: u32, mip_count: u32) -> Result<(), String> {
+        if self.width == width && self.height == height && self.mip_count == mip_count {
+            return Ok(());
+        }
+        self.delete_mips();
+        for (w, h) in bloom_mip_sizes(width, height, mip_count) {
+            let mut texture = 0;
+            unsafe {
+                gl::GenTextures(1, &mut texture);
+                gl::BindTexture(gl::TEXTURE_2D, texture);
+                gl::TexImage2D(gl::TEXTURE_2D, 0, gl::RGB16F as GLint, w as GLsizei, h as GLsizei, 0, gl::RGB, gl::FLOAT, null());
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as GLint);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as GLint);
+            }
+            self.mips.push(BloomMip { texture, width: w, height: h });
+        }
+        if let Some(first) = self.mips.first() {
+            unsafe {
+                gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+                gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::TEXTURE_2D, first.texture, 0);
+                let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+                gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+                if status != gl::FRAMEBUFFER_COMPLETE {
+                    return Err(format!("Could not create bloom framebuffer: {:#X?}", status));
+                }
+            }
+        }
+        self.width = width;
+        self.height = height;
+        self.mip_count = mip_count;
+        Ok(())
+    }
+
+    /// Blurred bloom at half resolution, valid after `render`. 0 if the frame is too small.
+    pub fn texture_id(&self) -> u32 {
+        self.mips.first().map_or(0, |mip| mip.texture)
+    }
+
+    /// Downsamples `source` (the HDR frame) through the mip chain and accumulates it back up
+    /// into the first mip. Leaves the framebuffer unbound and restores the viewport.
+    pub fn render(&mut self, source: u32, settings: &BloomSettings) {
+        if self.mips.is_empty() {
+            return;
+        }
+        let mut previous_viewport = [0; 4];
+        unsafe {
+            gl::GetIntegerv(gl::VIEWPORT, previous_viewport.as_mut_ptr());
+            gl::Disable(gl::DEPTH_TEST);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            gl::BindVertexArray(self.vao);
+            gl::ActiveTexture(gl::TEXTURE0);
+        }
+
+        let shader = &self.downsample;
+        shader.bind();
+        let (mut source, mut source_width, mut source_height) = (source, self.width, self.height);
+        for (i, mip) in self.mips.iter().enumerate() {
+            if let Some(loc) = shader.get_uniform_location("source_texel") {
+                shader.set_uniform(loc, glm::Vec2::new(1.0 / source_width as f32, 1.0 / source_height as f32));
+            }
+            // the Karis average on the first downsample keeps single bright pixels from flickering
+            if let Some(loc) = shader.get_uniform_location("karis_average") {
+                shader.set_uniform(loc, (i == 0) as i32);
+            }
+            unsafe {
+                gl::BindTexture(gl::TEXTURE_2D, source);
+                self.draw_into(mip);
+            }
+            (source, source_width, source_height) = (mip.texture, mip.width, mip.height);
+        }
+
+        let shader = &self.upsample;
+        shader.bind();
+        if let Some(loc) = shader.get_uniform_location("filter_radius") {
+            shader.set_uniform(loc, settings.radius);
+        }
+        unsafe {
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::ONE, gl::ONE);
+            gl::BlendEquation(gl::FUNC_ADD);
+        }
+        for pair in self.mips.windows(2).rev() {
+            let (target, smaller) = (&pair[0], &pair[1]);
+            unsafe {
+                gl::BindTexture(gl::TEXTURE_2D, smaller.texture);
+                self.draw_into(target);
+            }
+        }
+
+        unsafe {
+            gl::Disable(gl::BLEND);
+            gl::BindVertexArray(0);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            gl::Enable(gl::DEPTH_TEST);
+            gl::Viewport(previous_viewport[0], previous_viewport[1], previous_viewport[2], previous_viewport[3]);
+        }
+    }
+
+    unsafe fn draw_into(&self, mip: &BloomMip) {
+        gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::TEXTURE_2D, mip.texture, 0);
+        gl::Viewport(0, 0, mip.width as GLsizei, mip.height as GLsizei);
+        gl::DrawArrays(gl::TRIANGLES, 0, 3);
+    }
+
+    fn delete_mips(&mut self) {
+        for mip in self.mips.drain(..) {
+            unsafe {
+                gl::DeleteTextures(1, &mip.texture);
+            }
+        }
+    }
+}
+
+impl Drop for Bloom {
+    fn drop(&mut self) {
+        self.delete_mips();
+        unsafe {
+            gl::DeleteFramebuffers(1, &self.fbo);
+            gl::DeleteVertexArrays(1, &self.vao);
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use glm::{dot, Mat4, Vec3, Vec4};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub fn new(min: Vec3, max: Vec3) -> Self {
+        Self { min, max }
+    }
+
+    /// Smallest box around all points, an empty iterator gives a zero sized box at the origin.
+    pub fn from_points<I: IntoIterator<Item = Vec3>>(points: I) -> Self {
+        let mut min = Vec3::new(f32::MAX, f32::MAX, f32::MAX);
+        let mut max = Vec3::new(f32::MIN, f32::MIN, f32::MIN);
+        let mut empty = true;
+        for p in points {
+            min = glm::min(min, p);
+            max = glm::max(max, p);
+            empty = false;
+        }
+        if empty {
+            let zero = Vec3::new(0.0, 0.0, 0.0);
+            return Self { min: zero, max: zero };
+        }
+        Self { min, max }
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn half_extents(&self) -> Vec3 {
+        (self.max - self.min) * 0.5
+    }
+
+    pub fn contains(&self, p: Vec3) -> bool {
+        p.x >= self.min.x && p.y >= self.min.y && p.z >= self.min.z
+            && p.x <= self.max.x && p.y <= self.max.y && p.z <= self.max.z
+    }
+
+    /// Box around the transformed box (Arvo's method), so rotations make it grow.
+    pub fn transformed(&self, m: &Mat4) -> Self {
+        let mut min = m[3].truncate(3);
+        let mut max = min;
+        for row in 0..3 {
+            for col in 0..3 {
+                let a = m[col][row] * self.min[col];
+                let b = m[col][row] * self.max[col];
+                min[row] += a.min(b);
+                max[row] += a.max(b);
+            }
+        }
+        Self { min, max }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingSphere {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    pub fn new(center: Vec3, radius: f32) -> Self {
+        Self { center, radius }
+    }
+
+    /// Sphere centered on the box of the points. Not minimal, but tight enough for culling.
+    pub fn from_points<I: IntoIterator<Item = Vec3> + Clone>(points: I) -> Self {
+        let center = Aabb::from_points(points.clone()).center();
+        let radius = points
+            .into_iter()
+            .map(|p| glm::length(p - center))
+            .fold(0.0, f32::max);
+        Self { center, radius }
+    }
+
+    /// Non-uniform scale uses the largest axis, the sphere never shrinks below the mesh.
+    pub fn transformed(&self, m: &Mat4) -> Self {
+        let c = *m * Vec4::new(self.center.x, self.center.y, self.center.z, 1.0);
+        let scale = glm::length(m[0].truncate(3))
+            .max(glm::length(m[1].truncate(3)))
+            .max(glm::length(m[2].truncate(3)));
+        Self { center: c.truncate(3), radius: self.radius * scale }
+    }
+}
+
+/// The six planes of a view frustum, normals point inwards.
+#[derive(Debug, Clone, Copy)]
+pub struct Frustum {
+    /// `xyz` is the normal, `w` the distance: `dot(n, p) + w >= 0` is inside.
+    /// Order is left, right, bottom, top, near, far.
+    pub planes: [Vec4; 6],
+}
+
+impl Frustum {
+    /// Gribb/Hartmann plane extraction from `projection * view` (or just `projection` for view space).
+    pub fn from_matrix(m: &Mat4) -> Self {
+        let row = |r: usize| Vec4::new(m[0][r], m[1][r], m[2][r], m[3][r]);
+        let (r0, r1, r2, r3) = (row(0), row(1), row(2), row(3));
+        let planes = [r3 + r0, r3 - r0, r3 + r1, r3 - r1, r3 + r2, r3 - r2].map(|p| {
+            let len = glm::length(p.truncate(3));
+            p / len
+        });
+        Self { planes }
+    }
+
+    pub fn contains_point(&self, p: Vec3) -> bool {
+        self.planes.iter().all(|plane| dot(plane.truncate(3), p) + plane.w >= 0.0)
+    }
+
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| dot(plane.truncate(3), sphere.center) + plane.w >= -sphere.radius)
+    }
+
+    /// Conservative: boxes near a frustum corner can pass although they are outside.
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|plane| {
+            // the corner furthest along the plane normal
+            let p = Vec3::new(
+                if plane.x >= 0.0 { aabb.max.x } else { aabb.min.x },
+                if plane.y >= 0.0 { aabb.max.y } else { aabb.min.y },
+                if plane.z >= 0.0 { aabb.max.z } else { aabb.min.z },
+            );
+            dot(plane.truncate(3), p) + plane.w >= 0.0
+        })
+    }
+}
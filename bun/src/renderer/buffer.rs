@@ -1,6 +1,7 @@
 use std::ptr::null;
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BufferUsage {
     StaticDraw,
     DynamicDraw,
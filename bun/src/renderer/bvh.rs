@@ -0,0 +1,139 @@
+use crate::renderer::mesh_data::MeshData;
+use crate::renderer::ray::{MeshHit, Ray};
+use crate::Vertex;
+use glm::{normalize, Vec3};
+
+const MAX_LEAF_TRIANGLES: usize = 4;
+
+struct BvhNode {
+    min: Vec3,
+    max: Vec3,
+    /// Leaves index into `Bvh::triangles`, inner nodes store their right child in `first`,
+    /// the left child always directly follows its parent.
+    first: u32,
+    count: u32,
+}
+
+/// Bounding volume hierarchy over the triangles of a mesh, used for CPU ray casts.
+pub struct Bvh {
+    nodes: Vec<BvhNode>,
+    triangles: Vec<u32>,
+}
+
+impl Bvh {
+    pub fn build(mesh_data: &MeshData<Vertex>) -> Self {
+        let vertices = mesh_data.vertices();
+        let indices = mesh_data.indices();
+        let triangle_count = indices.len() / 3;
+
+        let mut bounds = Vec::with_capacity(triangle_count);
+        for tri in indices.chunks_exact(3) {
+            let p0 = vertices[tri[0] as usize].v;
+            let p1 = vertices[tri[1] as usize].v;
+            let p2 = vertices[tri[2] as usize].v;
+            let min = glm::min(glm::min(p0, p1), p2);
+            let max = glm::max(glm::max(p0, p1), p2);
+            bounds.push((min, max, (p0 + p1 + p2) / 3.0));
+        }
+
+        let mut bvh = Self {
+            nodes: Vec::with_capacity(triangle_count.max(1) * 2),
+            triangles: (0..triangle_count as u32).collect(),
+        };
+        if triangle_count > 0 {
+            bvh.build_node(&bounds, 0, triangle_count);
+        }
+        bvh
+    }
+
+    fn build_node(&mut self, bounds: &[(Vec3, Vec3, Vec3)], start: usize, end: usize) -> usize {
+        let mut min = Vec3::new(f32::MAX, f32::MAX, f32::MAX);
+        let mut max = Vec3::new(f32::MIN, f32::MIN, f32::MIN);
+        let mut centroid_min = min;
+        let mut centroid_max = max;
+        for &tri in &self.triangles[start..end] {
+            let (tri_min, tri_max, centroid) = bounds[tri as usize];
+            min = glm::min(min, tri_min);
+            max = glm::max(max, tri_max);
+            centroid_min = glm::min(centroid_min, centroid);
+            centroid_max = glm::max(centroid_max, centroid);
+        }
+
+        let node_index = self.nodes.len();
+        self.nodes.push(BvhNode { min, max, first: start as u32, count: (end - start) as u32 });
+
+        let extent = centroid_max - centroid_min;
+        let axis = if extent.x >= extent.y && extent.x >= extent.z {
+            0
+        } else if extent.y >= extent.z {
+            1
+        } else {
+            2
+        };
+        if end - start <= MAX_LEAF_TRIANGLES || extent[axis] <= 0.0 {
+            return node_index;
+        }
+
+        // median split on the longest centroid axis
+        let mid = start + (end - start) / 2;
+        self.triangles[start..end].select_nth_unstable_by(mid - start, |a, b| {
+            bounds[*a as usize].2[axis].total_cmp(&bounds[*b as usize].2[axis])
+        });
+
+        self.build_node(bounds, start, mid);
+        let right = self.build_node(bounds, mid, end);
+        self.nodes[node_index].first = right as u32;
+        self.nodes[node_index].count = 0;
+        node_index
+    }
+
+    pub fn raycast(&self, mesh_data: &MeshData<Vertex>, ray: &Ray) -> Option<MeshHit> {
+        let vertices = mesh_data.vertices();
+        let indices = mesh_data.indices();
+        let mut closest: Option<MeshHit> = None;
+        if self.nodes.is_empty() {
+            return None;
+        }
+
+        let mut stack = vec![0usize];
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+            let entry = match ray.intersect_aabb(node.min, node.max) {
+                Some(t) => t,
+                None => continue,
+            };
+            if closest.is_some_and(|c| c.distance < entry) {
+                continue;
+            }
+
+            if node.count == 0 {
+                stack.push(node.first as usize);
+                stack.push(node_index + 1);
+                continue;
+            }
+
+            for &tri in &self.triangles[node.first as usize..(node.first + node.count) as usize] {
+                let base = tri as usize * 3;
+                let v0 = &vertices[indices[base] as usize];
+                let v1 = &vertices[indices[base + 1] as usize];
+                let v2 = &vertices[indices[base + 2] as usize];
+                let (t, u, v) = match ray.intersect_triangle(v0.v, v1.v, v2.v) {
+                    Some(hit) => hit,
+                    None => continue,
+                };
+                if closest.is_some_and(|c| c.distance <= t) {
+                    continue;
+                }
+                let w = 1.0 - u - v;
+                closest = Some(MeshHit {
+                    distance: t,
+                    point: ray.at(t),
+                    normal: normalize(v0.vn * w + v1.vn * u + v2.vn * v),
+                    barycentric: Vec3::new(w, u, v),
+                    triangle: tri as usize,
+                });
+            }
+        }
+        closest
+    }
+}
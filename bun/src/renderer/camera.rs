@@ -1,4 +1,6 @@
-use glm::{cos, cross, normalize, sin, Mat4, Vec3};
+use crate::renderer::bounds::Frustum;
+use crate::renderer::ray::Ray;
+use glm::{cos, cross, normalize, sin, Mat4, Vec2, Vec3};
 use num_traits::One;
 
 pub struct Camera {
@@ -69,6 +71,11 @@ impl Camera {
 
     pub fn projection(&self) -> Mat4 { self.proj_mat }
 
+    pub fn fov(&self) -> f32 { self.fov }
+    pub fn aspect_ratio(&self) -> f32 { self.aspect_ratio }
+    pub fn near_clip(&self) -> f32 { self.near_clip }
+    pub fn far_clip(&self) -> f32 { self.far_clip }
+
     pub fn forward(&self) -> Vec3 {
         let forward = Vec3::new(
             sin(self.rotation.y) * cos(self.rotation.x),
@@ -102,6 +109,20 @@ impl Camera {
         -self.up()
     }
 
+    /// World space ray through a point on screen, in the coordinates of `MouseState::pos()`
+    /// (-1..1, y pointing down).
+    pub fn screen_ray(&self, mouse_pos: Vec2) -> Ray {
+        let tan_half_fov = (self.fov / 2.0).tan();
+        let direction = self.forward()
+            + self.right() * (mouse_pos.x * tan_half_fov * self.aspect_ratio)
+            + self.up() * (-mouse_pos.y * tan_half_fov);
+        Ray::new(self.position, direction)
+    }
+
+    pub fn frustum(&self) -> Frustum {
+        Frustum::from_matrix(&(self.proj_mat * self.view_mat))
+    }
+
     fn rebuild_projection(&mut self) {
         self.proj_mat =
             glm::ext::perspective(self.fov, self.aspect_ratio, self.near_clip, self.far_clip);
@@ -111,3 +132,40 @@ impl Camera {
         self.view_mat = glm::ext::look_at(self.position, self.position + self.forward(), self.up());
     }
 }
+
+
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct CameraData {
+    #[serde(with = "crate::serde_glm::vec3")]
+    position: Vec3,
+    #[serde(with = "crate::serde_glm::vec3")]
+    rotation: Vec3,
+    fov: f32,
+    aspect_ratio: f32,
+    near_clip: f32,
+    far_clip: f32,
+}
+
+// the matrices are derived state and get rebuilt by `Camera::new`
+#[cfg(feature = "serde")]
+impl serde::Serialize for Camera {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        CameraData {
+            position: self.position,
+            rotation: self.rotation,
+            fov: self.fov,
+            aspect_ratio: self.aspect_ratio,
+            near_clip: self.near_clip,
+            far_clip: self.far_clip,
+        }.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Camera {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = CameraData::deserialize(deserializer)?;
+        Ok(Camera::new(data.position, data.rotation, data.fov, data.aspect_ratio, data.near_clip, data.far_clip))
+    }
+}
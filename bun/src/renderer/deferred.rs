@@ -0,0 +1,110 @@
+use crate::renderer::frame_buffer::{ColorFormat, Framebuffer};
+use crate::Shader;
+
+/// How the renderer shades opaque objects, set with `Renderer::set_render_path`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RenderPath {
+    /// Every object runs its material shader with all lights.
+    #[default]
+    Forward,
+    /// Opaque objects only write their surface into the G-buffer, then one full-screen pass
+    /// lights every pixel once. Lighting is reconstructed from the first camera of the frame.
+    /// Transparent materials, shaders without the engine surface (`Shader::has_pbr_surface`),
+    /// tessellated ones and objects of other cameras still go through the forward path.
+    Deferred,
+}
+
+/// Attachments of the G-buffer, in fragment output order of `gbuffer.frag`.
+pub const GBUFFER_LAYOUT: [ColorFormat; 4] = [
+    // rgb albedo, a material occlusion
+    ColorFormat::Rgba8,
+    // xyz world normal, w receives shadows
+    ColorFormat::Rgba16F,
+    // r roughness, g metallic
+    ColorFormat::Rg8,
+    // rgb emitted radiance
+    ColorFormat::Rgba16F,
+];
+
+/// G-buffer and shaders of the deferred path. The depth attachment is sampled to get
+/// the world position back.
+pub(crate) struct DeferredPass {
+    gbuffer: Framebuffer,
+    geometry: Shader,
+    lighting: Shader,
+    vao: u32,
+}
+
+impl DeferredPass {
+    pub(crate) fn new(width: u32, height: u32) -> Result<Self, String> {
+        let geometry = Shader::from_source(
+            include_str!("../res/shaders/gbuffer.vert").to_string(),
+            include_str!("../res/shaders/gbuffer.frag").to_string(),
+        )?;
+        let lighting = Shader::from_source(
+            include_str!("../res/shaders/fullscreen.vert").to_string(),
+            include_str!("../res/shaders/deferred_lighting.frag").to_string(),
+        )?;
+        let gbuffer = Framebuffer::with_attachments(width as usize, height as usize, &GBUFFER_LAYOUT, true)?;
+        let mut vao = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut vao);
+        }
+        Ok(Self { gbuffer, geometry, lighting, vao })
+    }
+
+    pub(crate) fn resize(&mut self, width: u32, height: u32) {
+        if self.gbuffer.width() != width as usize || self.gbuffer.height() != height as usize {
+            self.gbuffer.resize(width as usize, height as usize);
+        }
+    }
+
+    pub(crate) fn geometry_shader(&self) -> &Shader {
+        &self.geometry
+    }
+
+    pub(crate) fn lighting_shader(&self) -> &Shader {
+        &self.lighting
+    }
+
+    /// Binds and clears the G-buffer, the caller draws the opaque objects with `geometry_shader`.
+    pub(crate) fn begin_geometry(&self) {
+        self.gbuffer.bind();
+        unsafe {
+            gl::Viewport(0, 0, self.gbuffer.width() as i32, self.gbuffer.height() as i32);
+            gl::ClearColor(0.0, 0.0, 0.0, 0.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+        }
+    }
+
+    /// Draws the lighting pass with the bound `lighting_shader` into `target` and copies the
+    /// G-buffer depth over, so the sky and forward objects are still depth tested against it.
+    pub(crate) fn render_lighting(&self, target: u32) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, target);
+            for i in 0..self.gbuffer.color_attachment_count() {
+                gl::ActiveTexture(gl::TEXTURE0 + i as u32);
+                gl::BindTexture(gl::TEXTURE_2D, self.gbuffer.color_texture_id(i));
+            }
+            gl::ActiveTexture(gl::TEXTURE0 + GBUFFER_LAYOUT.len() as u32);
+            gl::BindTexture(gl::TEXTURE_2D, self.gbuffer.depth_texture_id().unwrap_or(0));
+
+            gl::Disable(gl::DEPTH_TEST);
+            gl::DepthMask(gl::FALSE);
+            gl::BindVertexArray(self.vao);
+            gl::DrawArrays(gl::TRIANGLES, 0, 3);
+            gl::BindVertexArray(0);
+            gl::DepthMask(gl::TRUE);
+            gl::Enable(gl::DEPTH_TEST);
+        }
+        self.gbuffer.blit_depth_to(target);
+    }
+}
+
+impl Drop for DeferredPass {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteVertexArrays(1, &self.vao);
+        }
+    }
+}
@@ -0,0 +1,380 @@
+use crate::Shader;
+use gl::types::{GLint, GLsizei};
+use glm::Vec3;
+use std::f32::consts::PI;
+use std::ffi::c_void;
+use std::path::Path;
+use std::ptr::null;
+
+/// Equirectangular HDR image in linear color, rows from top (+Y) to bottom (-Y).
+#[derive(Debug, Clone)]
+pub struct EnvironmentMap {
+    width: u32,
+    height: u32,
+    pixels: Vec<Vec3>,
+}
+
+impl EnvironmentMap {
+    pub fn new(width: u32, height: u32, pixels: Vec<Vec3>) -> Result<Self, String> {
+        if width == 0 || height == 0 || pixels.len() != (width * height) as usize {
+            return Err(format!("Environment map of {}x{} needs {} pixels, got {}", width, height, width * height, pixels.len()));
+        }
+        Ok(Self { width, height, pixels })
+    }
+
+    /// Loads a `.hdr` or `.exr` file. LDR formats work too but are taken as linear.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        println!("loading environment: {}", path.as_ref().to_string_lossy());
+        let image = image::open(path.as_ref())
+            .map_err(|e| format!("Could not load environment {:?}: {}", path.as_ref(), e))?
+            .to_rgb32f();
+        let (width, height) = image.dimensions();
+        let pixels = image.pixels().map(|p| Vec3::new(p[0], p[1], p[2])).collect();
+        Self::new(width, height, pixels)
+    }
+
+    pub fn width(&self) -> u32 { self.width }
+    pub fn height(&self) -> u32 { self.height }
+    pub fn pixels(&self) -> &[Vec3] { &self.pixels }
+
+    pub fn pixel(&self, x: u32, y: u32) -> Vec3 {
+        self.pixels[(y * self.width + x) as usize]
+    }
+
+    /// World direction through the center of pixel (`x`, `y`).
+    pub fn direction(&self, x: u32, y: u32) -> Vec3 {
+        let phi = (x as f32 + 0.5) / self.width as f32 * 2.0 * PI - PI;
+        let elevation = 0.5 * PI - (y as f32 + 0.5) / self.height as f32 * PI;
+        Vec3::new(elevation.cos() * phi.cos(), elevation.sin(), elevation.cos() * phi.sin())
+    }
+
+    /// Nearest pixel in `direction`, the inverse of [`EnvironmentMap::direction`].
+    pub fn sample(&self, direction: Vec3) -> Vec3 {
+        let direction = glm::normalize(direction);
+        let u = direction.z.atan2(direction.x) / (2.0 * PI) + 0.5;
+        let v = 0.5 - direction.y.clamp(-1.0, 1.0).asin() / PI;
+        let x = ((u * self.width as f32) as u32).min(self.width - 1);
+        let y = ((v * self.height as f32) as u32).min(self.height - 1);
+        self.pixel(x, y)
+    }
+}
+
+/// Diffuse irradiance as 9 spherical harmonics coefficients (bands 0 to 2), already
+/// convolved with the cosine lobe. The shader evaluates them per normal in place of
+/// an irradiance cube map.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IrradianceSh {
+    pub coefficients: [Vec3; 9],
+}
+
+// cosine lobe convolution per band (Ramamoorthi and Hanrahan)
+const SH_BAND_FACTORS: [f32; 3] = [PI, 2.0 * PI / 3.0, PI / 4.0];
+
+impl IrradianceSh {
+    pub fn from_environment(map: &EnvironmentMap) -> Self {
+        let zero = Vec3::new(0.0, 0.0, 0.0);
+        let mut radiance = [zero; 9];
+        let mut total_weight = 0.0;
+        for y in 0..map.height {
+            for x in 0..map.width {
+                let direction = map.direction(x, y);
+                // solid angle of the pixel shrinks towards the poles
+                let weight = (1.0 - direction.y * direction.y).sqrt();
+                let color = map.pixel(x, y) * weight;
+                for (c, basis) in radiance.iter_mut().zip(sh_basis(direction)) {
+                    *c = *c + color * basis;
+                }
+                total_weight += weight;
+            }
+        }
+
+        // normalizing to the full sphere hides the error of the discrete solid angles
+        let normalization = 4.0 * PI / total_weight;
+        let mut coefficients = [zero; 9];
+        for (i, c) in coefficients.iter_mut().enumerate() {
+            *c = radiance[i] * normalization * SH_BAND_FACTORS[sh_band(i)];
+        }
+        Self { coefficients }
+    }
+
+    /// Irradiance arriving at a surface facing `normal`. Divide by PI for the Lambert diffuse.
+    pub fn irradiance(&self, normal: Vec3) -> Vec3 {
+        let basis = sh_basis(glm::normalize(normal));
+        self.coefficients
+            .iter()
+            .zip(basis)
+            .fold(Vec3::new(0.0, 0.0, 0.0), |sum, (c, b)| sum + *c * b)
+    }
+}
+
+fn sh_band(index: usize) -> usize {
+    match index {
+        0 => 0,
+        1..=3 => 1,
+        _ => 2,
+    }
+}
+
+// must match `sh_irradiance` in the shaders
+fn sh_basis(d: Vec3) -> [f32; 9] {
+    [
+        0.282095,
+        0.488603 * d.y,
+        0.488603 * d.z,
+        0.488603 * d.x,
+        1.092548 * d.x * d.y,
+        1.092548 * d.y * d.z,
+        0.315392 * (3.0 * d.z * d.z - 1.0),
+        1.092548 * d.x * d.z,
+        0.546274 * (d.x * d.x - d.y * d.y),
+    ]
+}
+
+/// Resolutions and sample counts of the precomputed IBL textures.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EnvironmentSettings {
+    /// Face size of the source cube map the equirectangular image is projected into.
+    pub cube_resolution: u32,
+    /// Face size of the first mip of the GGX prefiltered cube map.
+    pub prefilter_resolution: u32,
+    /// Mips of the prefiltered cube map, the last one is for roughness 1.
+    pub prefilter_levels: u32,
+    pub brdf_lut_resolution: u32,
+    /// Importance samples per texel for the prefilter and the BRDF LUT.
+    pub sample_count: u32,
+}
+
+impl Default for EnvironmentSettings {
+    fn default() -> Self {
+        Self {
+            cube_resolution: 512,
+            prefilter_resolution: 128,
+            prefilter_levels: 5,
+            brdf_lut_resolution: 256,
+            sample_count: 512,
+        }
+    }
+}
+
+/// Roughness the prefiltered cube map stores at `level`, linear over the mips.
+pub fn prefilter_roughness(level: u32, levels: u32) -> f32 {
+    if levels <= 1 {
+        0.0
+    } else {
+        level as f32 / (levels - 1) as f32
+    }
+}
+
+/// Image based lighting baked from an [`EnvironmentMap`]: SH irradiance for the diffuse
+/// part, a GGX prefiltered cube map and the split sum BRDF LUT for the specular part.
+/// Set it with `Renderer::set_environment`.
+pub struct Environment {
+    cube_map: u32,
+    prefiltered: u32,
+    brdf_lut: u32,
+    prefilter_levels: u32,
+    irradiance: IrradianceSh,
+    intensity: f32,
+}
+
+impl Environment {
+    pub fn load<P: AsRef<Path>>(path: P, settings: EnvironmentSettings) -> Result<Self, String> {
+        Self::new(&EnvironmentMap::load(path)?, settings)
+    }
+
+    pub fn new(map: &EnvironmentMap, settings: EnvironmentSettings) -> Result<Self, String> {
+        let irradiance = IrradianceSh::from_environment(map);
+        let levels = settings.prefilter_levels.max(1);
+
+        let equirect_shader = ibl_shader(include_str!("../res/shaders/equirect_to_cube.frag"))?;
+        let prefilter_shader = ibl_shader(include_str!("../res/shaders/prefilter.frag"))?;
+        let brdf_shader = ibl_shader(include_str!("../res/shaders/brdf_lut.frag"))?;
+
+        let mut previous_viewport = [0; 4];
+        let mut previous_framebuffer = 0;
+        let mut fbo = 0;
+        let mut vao = 0;
+        let mut equirect = 0;
+        let environment;
+        unsafe {
+            gl::GetIntegerv(gl::VIEWPORT, previous_viewport.as_mut_ptr());
+            gl::GetIntegerv(gl::DRAW_FRAMEBUFFER_BINDING, &mut previous_framebuffer);
+            gl::GenFramebuffers(1, &mut fbo);
+            // the bake passes generate a fullscreen triangle from gl_VertexID
+            gl::GenVertexArrays(1, &mut vao);
+            gl::BindVertexArray(vao);
+
+            let data: Vec<f32> = map.pixels.iter().flat_map(|p| [p.x, p.y, p.z]).collect();
+            gl::GenTextures(1, &mut equirect);
+            gl::BindTexture(gl::TEXTURE_2D, equirect);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::RGB32F as GLint,
+                map.width as GLsizei,
+                map.height as GLsizei,
+                0,
+                gl::RGB,
+                gl::FLOAT,
+                data.as_ptr() as *const c_void,
+            );
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as GLint);
+
+            // source cube map, mipmapped so the prefilter can sample lower mips for wide lobes
+            let cube_levels = mip_count(settings.cube_resolution);
+            let cube_map = create_cube_map(settings.cube_resolution, cube_levels);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
+            equirect_shader.bind();
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, equirect);
+            set_int(&equirect_shader, "equirect", 0);
+            let result = render_cube_faces(&equirect_shader, cube_map, settings.cube_resolution, 0);
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, cube_map);
+            gl::GenerateMipmap(gl::TEXTURE_CUBE_MAP);
+
+            let prefiltered = create_cube_map(settings.prefilter_resolution, levels);
+            let brdf_lut = create_brdf_lut(settings.brdf_lut_resolution);
+            environment = Self { cube_map, prefiltered, brdf_lut, prefilter_levels: levels, irradiance, intensity: 1.0 };
+
+            let result = result.and_then(|_| {
+                prefilter_shader.bind();
+                gl::ActiveTexture(gl::TEXTURE0);
+                gl::BindTexture(gl::TEXTURE_CUBE_MAP, cube_map);
+                set_int(&prefilter_shader, "environment", 0);
+                set_int(&prefilter_shader, "sample_count", settings.sample_count as i32);
+                if let Some(loc) = prefilter_shader.get_uniform_location("source_resolution") {
+                    prefilter_shader.set_uniform(loc, settings.cube_resolution as f32);
+                }
+                for level in 0..levels {
+                    if let Some(loc) = prefilter_shader.get_uniform_location("roughness") {
+                        prefilter_shader.set_uniform(loc, prefilter_roughness(level, levels));
+                    }
+                    let size = (settings.prefilter_resolution >> level).max(1);
+                    render_cube_faces(&prefilter_shader, prefiltered, size, level)?;
+                }
+
+                brdf_shader.bind();
+                set_int(&brdf_shader, "sample_count", settings.sample_count as i32);
+                gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::TEXTURE_2D, brdf_lut, 0);
+                check_framebuffer()?;
+                let size = settings.brdf_lut_resolution as GLsizei;
+                gl::Viewport(0, 0, size, size);
+                gl::DrawArrays(gl::TRIANGLES, 0, 3);
+                Ok(())
+            });
+
+            gl::BindVertexArray(0);
+            gl::DeleteVertexArrays(1, &vao);
+            gl::DeleteTextures(1, &equirect);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, previous_framebuffer as u32);
+            gl::DeleteFramebuffers(1, &fbo);
+            gl::Viewport(previous_viewport[0], previous_viewport[1], previous_viewport[2], previous_viewport[3]);
+            result?;
+        }
+        Ok(environment)
+    }
+
+    /// Unfiltered environment as a mipmapped cube map.
+    pub fn cube_map_id(&self) -> u32 { self.cube_map }
+    pub fn prefiltered_id(&self) -> u32 { self.prefiltered }
+    pub fn brdf_lut_id(&self) -> u32 { self.brdf_lut }
+    pub fn prefilter_levels(&self) -> u32 { self.prefilter_levels }
+    pub fn irradiance(&self) -> &IrradianceSh { &self.irradiance }
+    pub fn intensity(&self) -> f32 { self.intensity }
+
+    /// Scales the ambient light of the environment.
+    pub fn set_intensity(&mut self, intensity: f32) {
+        self.intensity = intensity;
+    }
+}
+
+impl Drop for Environment {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteTextures(1, &self.cube_map);
+            gl::DeleteTextures(1, &self.prefiltered);
+            gl::DeleteTextures(1, &self.brdf_lut);
+        }
+    }
+}
+
+fn ibl_shader(fragment: &str) -> Result<Shader, String> {
+    Shader::from_source(include_str!("../res/shaders/fullscreen.vert").to_string(), fragment.to_string())
+}
+
+fn set_int(shader: &Shader, name: &str, value: i32) {
+    if let Some(loc) = shader.get_uniform_location(name) {
+        shader.set_uniform(loc, value);
+    }
+}
+
+fn mip_count(resolution: u32) -> u32 {
+    32 - resolution.max(1).leading_zeros()
+}
+
+unsafe fn create_cube_map(resolution: u32, levels: u32) -> u32 {
+    let mut texture = 0;
+    gl::GenTextures(1, &mut texture);
+    gl::BindTexture(gl::TEXTURE_CUBE_MAP, texture);
+    gl::TexStorage2D(gl::TEXTURE_CUBE_MAP, levels as GLsizei, gl::RGB16F, resolution as GLsizei, resolution as GLsizei);
+    let min_filter = if levels > 1 { gl::LINEAR_MIPMAP_LINEAR } else { gl::LINEAR };
+    gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_MIN_FILTER, min_filter as GLint);
+    gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+    gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as GLint);
+    gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as GLint);
+    gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_WRAP_R, gl::CLAMP_TO_EDGE as GLint);
+    gl::Enable(gl::TEXTURE_CUBE_MAP_SEAMLESS);
+    texture
+}
+
+unsafe fn create_brdf_lut(resolution: u32) -> u32 {
+    let mut texture = 0;
+    gl::GenTextures(1, &mut texture);
+    gl::BindTexture(gl::TEXTURE_2D, texture);
+    gl::TexImage2D(
+        gl::TEXTURE_2D,
+        0,
+        gl::RG16F as GLint,
+        resolution as GLsizei,
+        resolution as GLsizei,
+        0,
+        gl::RG,
+        gl::FLOAT,
+        null(),
+    );
+    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
+    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as GLint);
+    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as GLint);
+    texture
+}
+
+/// Draws the bound shader into all six faces of `level`, the shader gets the face as `face`.
+unsafe fn render_cube_faces(shader: &Shader, cube_map: u32, size: u32, level: u32) -> Result<(), String> {
+    gl::Viewport(0, 0, size as GLsizei, size as GLsizei);
+    for face in 0..6 {
+        gl::FramebufferTexture2D(
+            gl::FRAMEBUFFER,
+            gl::COLOR_ATTACHMENT0,
+            gl::TEXTURE_CUBE_MAP_POSITIVE_X + face,
+            cube_map,
+            level as GLint,
+        );
+        check_framebuffer()?;
+        set_int(shader, "face", face as i32);
+        gl::DrawArrays(gl::TRIANGLES, 0, 3);
+    }
+    Ok(())
+}
+
+unsafe fn check_framebuffer() -> Result<(), String> {
+    let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+    if status != gl::FRAMEBUFFER_COMPLETE {
+        return Err(format!("Could not create environment framebuffer: {:#X?}", status));
+    }
+    Ok(())
+}
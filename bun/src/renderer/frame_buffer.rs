@@ -1,73 +1,159 @@
 use std::ptr::null;
 use gl::types::{GLint, GLsizei};
 
+/// Format of one color attachment of a `Framebuffer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorFormat {
+    Rgba8,
+    Rg8,
+    Rgba16F,
+}
+
+impl ColorFormat {
+    /// (internal format, format, type)
+    fn to_gl(self) -> (u32, u32, u32) {
+        match self {
+            ColorFormat::Rgba8 => (gl::RGBA8, gl::RGBA, gl::UNSIGNED_BYTE),
+            ColorFormat::Rg8 => (gl::RG8, gl::RG, gl::UNSIGNED_BYTE),
+            ColorFormat::Rgba16F => (gl::RGBA16F, gl::RGBA, gl::FLOAT),
+        }
+    }
+}
+
 pub struct Framebuffer {
     id: u32,
-    screen_buffer_id: u32,
+    color_textures: Vec<(u32, ColorFormat)>,
     depth_buffer_id: u32,
+    // a depth stencil texture instead of a renderbuffer, so shaders can sample it
+    depth_texture: bool,
+    width: usize,
+    height: usize,
 }
 
 impl Framebuffer {
+    /// Single half float color attachment with a depth stencil renderbuffer, the HDR frame.
     pub fn new(width: usize, height: usize) -> Result<Self, String> {
+        Self::with_attachments(width, height, &[ColorFormat::Rgba16F], false)
+    }
+
+    /// Multiple render targets, `colors[i]` is written by fragment output location `i`.
+    /// With `depth_texture` the depth stencil attachment can be sampled with `depth_texture_id`.
+    pub fn with_attachments(width: usize, height: usize, colors: &[ColorFormat], depth_texture: bool) -> Result<Self, String> {
         let mut id = 0u32;
-        let mut screen_buffer_id = 0u32;
         let mut depth_buffer_id = 0u32;
+        let mut color_textures = vec![];
         unsafe {
             gl::GenFramebuffers(1, &mut id);
             gl::BindFramebuffer(gl::FRAMEBUFFER, id);
-            
-            gl::GenTextures(1, &mut screen_buffer_id);
-            gl::BindTexture(gl::TEXTURE_2D, screen_buffer_id);
-            gl::TexImage2D(gl::TEXTURE_2D, 0, gl::RGBA16F as GLint, width as GLsizei, height as GLsizei, 0, gl::RGBA, gl::FLOAT, null());
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as GLint);
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as GLint);
-            
-            gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::TEXTURE_2D, screen_buffer_id, 0);
-            
-            gl::GenRenderbuffers(1, &mut depth_buffer_id);
-            gl::BindRenderbuffer(gl::RENDERBUFFER, depth_buffer_id);
-            gl::RenderbufferStorage(gl::RENDERBUFFER, gl::DEPTH24_STENCIL8, width as GLsizei, height as GLsizei);
-            
-            gl::FramebufferRenderbuffer(gl::FRAMEBUFFER, gl::DEPTH_STENCIL_ATTACHMENT, gl::RENDERBUFFER, depth_buffer_id);
-            
-            // let draw_buffers = [gl::COLOR_ATTACHMENT0];
-            // gl::DrawBuffers(1, draw_buffers.as_ptr());
-            
+
+            for (i, format) in colors.iter().enumerate() {
+                let mut texture = 0u32;
+                gl::GenTextures(1, &mut texture);
+                gl::BindTexture(gl::TEXTURE_2D, texture);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as GLint);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as GLint);
+                gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0 + i as u32, gl::TEXTURE_2D, texture, 0);
+                color_textures.push((texture, *format));
+            }
+
+            if depth_texture {
+                gl::GenTextures(1, &mut depth_buffer_id);
+                gl::BindTexture(gl::TEXTURE_2D, depth_buffer_id);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as GLint);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as GLint);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as GLint);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as GLint);
+                gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::DEPTH_STENCIL_ATTACHMENT, gl::TEXTURE_2D, depth_buffer_id, 0);
+            } else {
+                gl::GenRenderbuffers(1, &mut depth_buffer_id);
+                gl::BindRenderbuffer(gl::RENDERBUFFER, depth_buffer_id);
+                gl::FramebufferRenderbuffer(gl::FRAMEBUFFER, gl::DEPTH_STENCIL_ATTACHMENT, gl::RENDERBUFFER, depth_buffer_id);
+            }
+
+            let draw_buffers: Vec<u32> = (0..colors.len() as u32).map(|i| gl::COLOR_ATTACHMENT0 + i).collect();
+            gl::DrawBuffers(draw_buffers.len() as GLsizei, draw_buffers.as_ptr());
+        }
+
+        let mut framebuffer = Self {
+            id,
+            color_textures,
+            depth_buffer_id,
+            depth_texture,
+            width: 0,
+            height: 0,
+        };
+        framebuffer.resize(width, height);
+
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, id);
             let framebuffer_status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
             if framebuffer_status != gl::FRAMEBUFFER_COMPLETE {
                 return Err(format!("Could not create framebuffer: {:#X?}", framebuffer_status));
             }
-            
-            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
         }
-        
-        Ok(Self {
-            id,
-            screen_buffer_id,
-            depth_buffer_id
-        })
+
+        Ok(framebuffer)
     }
-    
+
     pub fn resize(&mut self, new_width: usize, new_height: usize) {
+        let (w, h) = (new_width as GLsizei, new_height as GLsizei);
         unsafe {
-            gl::BindTexture(gl::TEXTURE_2D, self.screen_buffer_id);
-            gl::TexImage2D(gl::TEXTURE_2D, 0, gl::RGBA16F as GLint, new_width as GLsizei, new_height as GLsizei, 0, gl::RGBA, gl::FLOAT, null());
-            
-            gl::BindRenderbuffer(gl::RENDERBUFFER, self.depth_buffer_id);
-            gl::RenderbufferStorage(gl::RENDERBUFFER, gl::DEPTH24_STENCIL8, new_width as GLsizei, new_height as GLsizei);
+            for (texture, format) in &self.color_textures {
+                let (internal_format, format, ty) = format.to_gl();
+                gl::BindTexture(gl::TEXTURE_2D, *texture);
+                gl::TexImage2D(gl::TEXTURE_2D, 0, internal_format as GLint, w, h, 0, format, ty, null());
+            }
+
+            if self.depth_texture {
+                gl::BindTexture(gl::TEXTURE_2D, self.depth_buffer_id);
+                gl::TexImage2D(gl::TEXTURE_2D, 0, gl::DEPTH24_STENCIL8 as GLint, w, h, 0, gl::DEPTH_STENCIL, gl::UNSIGNED_INT_24_8, null());
+            } else {
+                gl::BindRenderbuffer(gl::RENDERBUFFER, self.depth_buffer_id);
+                gl::RenderbufferStorage(gl::RENDERBUFFER, gl::DEPTH24_STENCIL8, w, h);
+            }
         }
+        self.width = new_width;
+        self.height = new_height;
     }
-    
+
     pub fn bind(&self) {
         unsafe {
             gl::BindFramebuffer(gl::FRAMEBUFFER, self.id);
         }
     }
-    
-    pub fn screen_texture_id(&self) -> u32 { self.screen_buffer_id }
-    
+
+    pub fn id(&self) -> u32 { self.id }
+
+    pub fn width(&self) -> usize { self.width }
+
+    pub fn height(&self) -> usize { self.height }
+
+    pub fn screen_texture_id(&self) -> u32 { self.color_texture_id(0) }
+
+    pub fn color_texture_id(&self, index: usize) -> u32 { self.color_textures[index].0 }
+
+    pub fn color_attachment_count(&self) -> usize { self.color_textures.len() }
+
+    /// `None` if the depth stencil attachment is a renderbuffer.
+    pub fn depth_texture_id(&self) -> Option<u32> {
+        self.depth_texture.then_some(self.depth_buffer_id)
+    }
+
+    /// Copies the depth and stencil into the framebuffer `target` (0 is the default one),
+    /// which has to be the same size.
+    pub fn blit_depth_to(&self, target: u32) {
+        let (w, h) = (self.width as GLint, self.height as GLint);
+        unsafe {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.id);
+            gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, target);
+            gl::BlitFramebuffer(0, 0, w, h, 0, 0, w, h, gl::DEPTH_BUFFER_BIT | gl::STENCIL_BUFFER_BIT, gl::NEAREST);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, target);
+        }
+    }
+
     pub fn bind_default() {
         unsafe {
             gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
@@ -78,6 +164,14 @@ impl Framebuffer {
 impl Drop for Framebuffer {
     fn drop(&mut self) {
         unsafe {
+            for (texture, _) in &self.color_textures {
+                gl::DeleteTextures(1, texture);
+            }
+            if self.depth_texture {
+                gl::DeleteTextures(1, &self.depth_buffer_id);
+            } else {
+                gl::DeleteRenderbuffers(1, &self.depth_buffer_id);
+            }
             gl::DeleteFramebuffers(1, &self.id);
         }
     }
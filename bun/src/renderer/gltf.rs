@@ -0,0 +1,635 @@
+use crate::renderer::material::{BlendMode, Channel, Material, MaterialProperty, NormalMap, PackedMap};
+use crate::renderer::mesh::calculate_tangents;
+use crate::renderer::mesh_data::MeshData;
+use crate::renderer::render_object::RenderObject;
+use crate::renderer::texture::{TextureSpec, TextureUsage};
+use crate::{Camera, Mesh, Shader, Texture, Transform, Vertex};
+use ::gltf::mesh::Mode;
+use glm::{Mat4, Vec2, Vec3, Vec4};
+use image::{DynamicImage, ImageBuffer};
+use num_traits::{One, Zero};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Far plane of cameras that the file leaves infinite.
+const INFINITE_FAR_CLIP: f32 = 1000.0;
+
+/// Everything read from a `.gltf` or `.glb` file that doesn't need a GL context. Indices into
+/// the vectors are the indices of the file.
+pub struct GltfData {
+    pub meshes: Vec<GltfMeshData>,
+    pub materials: Vec<GltfMaterialData>,
+    pub textures: Vec<GltfTextureData>,
+    pub nodes: Vec<GltfNode>,
+    /// One per node with a camera.
+    pub cameras: Vec<GltfCamera>,
+    /// Top level nodes of the default scene, or of the first one.
+    pub roots: Vec<usize>,
+}
+
+pub struct GltfMeshData {
+    pub name: Option<String>,
+    pub primitives: Vec<GltfPrimitiveData>,
+}
+
+pub struct GltfPrimitiveData {
+    pub data: MeshData<Vertex>,
+    /// `None` is glTF's default material.
+    pub material: Option<usize>,
+}
+
+pub struct GltfTextureData {
+    pub name: Option<String>,
+    /// First row at the top, like the file stores it.
+    pub image: image::RgbaImage,
+    pub sampler: GltfSampler,
+}
+
+/// Sampler of a texture as GL enums.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GltfSampler {
+    pub min_filter: u32,
+    pub mag_filter: u32,
+    pub wrap_s: u32,
+    pub wrap_t: u32,
+}
+
+impl GltfSampler {
+    pub fn spec(&self, usage: TextureUsage) -> TextureSpec {
+        TextureSpec {
+            usage,
+            min_filter: self.min_filter,
+            mag_filter: self.mag_filter,
+            wrap_s: self.wrap_s,
+            wrap_t: self.wrap_t,
+            generate_mipmaps: true,
+        }
+    }
+}
+
+/// pbrMetallicRoughness material. Textures are indices into `GltfData::textures`. As in
+/// glTF, the factors multiply their textures, `GltfModel` bakes them in with `apply_factor`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GltfMaterialData {
+    pub name: Option<String>,
+    pub base_color: Vec4,
+    pub base_color_texture: Option<usize>,
+    pub metallic: f32,
+    pub roughness: f32,
+    /// Roughness in green, metallic in blue.
+    pub metallic_roughness_texture: Option<usize>,
+    pub normal_texture: Option<usize>,
+    pub normal_scale: f32,
+    /// Occlusion in red, often the same texture as `metallic_roughness_texture`.
+    pub occlusion_texture: Option<usize>,
+    pub occlusion_strength: f32,
+    pub emissive: Vec3,
+    pub emissive_texture: Option<usize>,
+    pub emissive_strength: f32,
+    pub blend_mode: BlendMode,
+}
+
+impl Default for GltfMaterialData {
+    /// The material of primitives that don't name one.
+    fn default() -> Self {
+        Self {
+            name: None,
+            base_color: Vec4::one(),
+            base_color_texture: None,
+            metallic: 1.0,
+            roughness: 1.0,
+            metallic_roughness_texture: None,
+            normal_texture: None,
+            normal_scale: 1.0,
+            occlusion_texture: None,
+            occlusion_strength: 1.0,
+            emissive: Vec3::zero(),
+            emissive_texture: None,
+            emissive_strength: 1.0,
+            blend_mode: BlendMode::Opaque,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GltfNode {
+    pub name: Option<String>,
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+    /// Relative to the parent.
+    pub transform: Transform,
+    /// Relative to the file, the parent transforms applied.
+    pub world: Mat4,
+    pub mesh: Option<usize>,
+    pub camera: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GltfProjection {
+    Perspective {
+        /// Vertical field of view in radians.
+        yfov: f32,
+        aspect_ratio: Option<f32>,
+        near: f32,
+        /// `None` is an infinite projection.
+        far: Option<f32>,
+    },
+    Orthographic {
+        xmag: f32,
+        ymag: f32,
+        near: f32,
+        far: f32,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GltfCamera {
+    pub name: Option<String>,
+    pub node: usize,
+    pub position: Vec3,
+    /// Pitch and yaw like `Camera::rotation`, the roll of the node is dropped.
+    pub rotation: Vec3,
+    pub projection: GltfProjection,
+}
+
+impl GltfCamera {
+    /// `None` for orthographic cameras, `Camera` is perspective only. The window decides the
+    /// aspect ratio, not the file.
+    pub fn to_camera(&self, aspect_ratio: f32) -> Option<Camera> {
+        match self.projection {
+            GltfProjection::Perspective { yfov, near, far, .. } => Some(Camera::new(
+                self.position,
+                self.rotation,
+                yfov,
+                aspect_ratio,
+                near,
+                far.unwrap_or(INFINITE_FAR_CLIP),
+            )),
+            GltfProjection::Orthographic { .. } => None,
+        }
+    }
+}
+
+impl GltfData {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        println!("loading gltf: {}", path.as_ref().to_string_lossy());
+        let (document, buffers, images) =
+            ::gltf::import(path.as_ref()).map_err(|e| format!("Could not load glTF {:?}: {}", path.as_ref(), e))?;
+
+        let mut meshes = vec![];
+        for mesh in document.meshes() {
+            let mut primitives = vec![];
+            for primitive in mesh.primitives() {
+                let name = mesh.name().unwrap_or("unnamed");
+                let Some(data) = read_primitive(&primitive, &buffers, name)? else {
+                    continue;
+                };
+                primitives.push(GltfPrimitiveData {
+                    data,
+                    material: primitive.material().index(),
+                });
+            }
+            meshes.push(GltfMeshData {
+                name: mesh.name().map(String::from),
+                primitives,
+            });
+        }
+
+        let materials = document.materials().map(|m| read_material(&m)).collect();
+
+        let images = images.into_iter().map(rgba_image).collect::<Result<Vec<_>, _>>()?;
+        let textures = document
+            .textures()
+            .map(|texture| {
+                let sampler = texture.sampler();
+                GltfTextureData {
+                    name: texture.name().map(String::from),
+                    image: images[texture.source().index()].clone(),
+                    sampler: GltfSampler {
+                        min_filter: sampler.min_filter().map_or(gl::LINEAR_MIPMAP_LINEAR, |f| f.as_gl_enum()),
+                        mag_filter: sampler.mag_filter().map_or(gl::LINEAR, |f| f.as_gl_enum()),
+                        wrap_s: sampler.wrap_s().as_gl_enum(),
+                        wrap_t: sampler.wrap_t().as_gl_enum(),
+                    },
+                }
+            })
+            .collect();
+
+        let mut nodes: Vec<GltfNode> = document
+            .nodes()
+            .map(|node| {
+                let (translation, rotation, scale) = node.transform().decomposed();
+                GltfNode {
+                    name: node.name().map(String::from),
+                    parent: None,
+                    children: node.children().map(|c| c.index()).collect(),
+                    transform: Transform::new(
+                        Vec3::new(translation[0], translation[1], translation[2]),
+                        Vec3::new(scale[0], scale[1], scale[2]),
+                        Transform::euler_from_quaternion(rotation),
+                    ),
+                    world: mat4(node.transform().matrix()),
+                    mesh: node.mesh().map(|m| m.index()),
+                    camera: node.camera().map(|c| c.index()),
+                }
+            })
+            .collect();
+        for i in 0..nodes.len() {
+            for child in nodes[i].children.clone() {
+                nodes[child].parent = Some(i);
+            }
+        }
+        // parents first, so every parent's world matrix is done before its children
+        let mut stack: Vec<usize> = (0..nodes.len()).filter(|&i| nodes[i].parent.is_none()).collect();
+        while let Some(i) = stack.pop() {
+            for child in nodes[i].children.clone() {
+                nodes[child].world = nodes[i].world * nodes[child].world;
+                stack.push(child);
+            }
+        }
+
+        let cameras = document
+            .nodes()
+            .filter_map(|node| {
+                let camera = node.camera()?;
+                let world = &nodes[node.index()].world;
+                // glTF cameras look down -Z
+                let forward = glm::normalize(-Vec3::new(world[2].x, world[2].y, world[2].z));
+                let projection = match camera.projection() {
+                    ::gltf::camera::Projection::Perspective(p) => GltfProjection::Perspective {
+                        yfov: p.yfov(),
+                        aspect_ratio: p.aspect_ratio(),
+                        near: p.znear(),
+                        far: p.zfar(),
+                    },
+                    ::gltf::camera::Projection::Orthographic(o) => GltfProjection::Orthographic {
+                        xmag: o.xmag(),
+                        ymag: o.ymag(),
+                        near: o.znear(),
+                        far: o.zfar(),
+                    },
+                };
+                Some(GltfCamera {
+                    name: camera.name().or(node.name()).map(String::from),
+                    node: node.index(),
+                    position: Vec3::new(world[3].x, world[3].y, world[3].z),
+                    rotation: Vec3::new(forward.y.clamp(-1.0, 1.0).asin(), forward.x.atan2(forward.z), 0.0),
+                    projection,
+                })
+            })
+            .collect();
+
+        let roots = match document.default_scene().or_else(|| document.scenes().next()) {
+            Some(scene) => scene.nodes().map(|n| n.index()).collect(),
+            None => (0..nodes.len()).filter(|&i| nodes[i].parent.is_none()).collect(),
+        };
+
+        Ok(Self {
+            meshes,
+            materials,
+            textures,
+            nodes,
+            cameras,
+            roots,
+        })
+    }
+}
+
+/// `None` for points and lines, which the renderer can't draw.
+fn read_primitive(
+    primitive: &::gltf::Primitive,
+    buffers: &[::gltf::buffer::Data],
+    mesh_name: &str,
+) -> Result<Option<MeshData<Vertex>>, String> {
+    let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+    let positions: Vec<Vec3> = reader
+        .read_positions()
+        .ok_or_else(|| format!("Mesh '{}' has a primitive without positions", mesh_name))?
+        .map(|p| Vec3::new(p[0], p[1], p[2]))
+        .collect();
+    let count = positions.len() as u32;
+    let indices: Vec<u32> = match reader.read_indices() {
+        Some(indices) => indices.into_u32().collect(),
+        None => (0..count).collect(),
+    };
+    if let Some(&index) = indices.iter().find(|&&i| i >= count) {
+        return Err(format!("Mesh '{}' has index {} but {} vertices", mesh_name, index, count));
+    }
+    let mut indices = match primitive.mode() {
+        Mode::Triangles => indices,
+        Mode::TriangleStrip => (2..indices.len())
+            .flat_map(|i| {
+                // every other triangle is flipped to keep the winding
+                let (a, b) = if i % 2 == 0 { (i - 2, i - 1) } else { (i - 1, i - 2) };
+                [indices[a], indices[b], indices[i]]
+            })
+            .collect(),
+        Mode::TriangleFan => (2..indices.len()).flat_map(|i| [indices[0], indices[i - 1], indices[i]]).collect(),
+        mode => {
+            eprintln!("Skipping {:?} primitive of mesh '{}'", mode, mesh_name);
+            return Ok(None);
+        }
+    };
+    indices.truncate(indices.len() / 3 * 3);
+
+    // glTF's v runs down the image, ours up
+    let uvs: Vec<Vec2> = match reader.read_tex_coords(0) {
+        Some(uvs) => uvs.into_f32().map(|uv| Vec2::new(uv[0], 1.0 - uv[1])).collect(),
+        None => vec![Vec2::zero(); positions.len()],
+    };
+    let normals: Option<Vec<Vec3>> = reader.read_normals().map(|n| n.map(|n| Vec3::new(n[0], n[1], n[2])).collect());
+    let tangents: Option<Vec<Vec4>> = reader.read_tangents().map(|t| t.map(|t| Vec4::new(t[0], t[1], t[2], t[3])).collect());
+
+    let (mut vertices, mut indices) = match normals {
+        Some(normals) => {
+            let vertices = (0..positions.len())
+                .map(|i| {
+                    let tangent = tangents.as_ref().map_or(Vec4::zero(), |t| t[i]);
+                    Vertex::new(positions[i], normals[i], uvs[i], tangent)
+                })
+                .collect();
+            (vertices, indices)
+        }
+        // the spec asks for flat normals, so every triangle gets its own vertices
+        None => {
+            let mut vertices = Vec::with_capacity(indices.len());
+            for triangle in indices.chunks_exact(3) {
+                let [a, b, c] = [0, 1, 2].map(|i| triangle[i] as usize);
+                let normal = glm::cross(positions[b] - positions[a], positions[c] - positions[a]);
+                let normal = if glm::length(normal) > 0.0 { glm::normalize(normal) } else { Vec3::new(0.0, 1.0, 0.0) };
+                for i in [a, b, c] {
+                    vertices.push(Vertex::new(positions[i], normal, uvs[i], Vec4::zero()));
+                }
+            }
+            let indices = (0..vertices.len() as u32).collect();
+            (vertices, indices)
+        }
+    };
+    if tangents.is_none() || vertices.len() != positions.len() {
+        calculate_tangents(&mut vertices, &mut indices);
+    }
+    // no uvs or degenerate ones leave no direction, any tangent in the surface will do
+    for vertex in &mut vertices {
+        let tangent = vertex.tangent.truncate(3);
+        if !(tangent.x.is_finite() && tangent.y.is_finite() && tangent.z.is_finite()) || glm::length(tangent) < 1e-6 {
+            let n = vertex.vn;
+            let axis = if n.x.abs() < 0.9 { Vec3::new(1.0, 0.0, 0.0) } else { Vec3::new(0.0, 1.0, 0.0) };
+            let tangent = glm::normalize(axis - n * glm::dot(n, axis));
+            vertex.tangent = Vec4::new(tangent.x, tangent.y, tangent.z, 1.0);
+        }
+    }
+
+    Ok(Some(MeshData::new(vertices, indices)))
+}
+
+fn read_material(material: &::gltf::Material) -> GltfMaterialData {
+    let pbr = material.pbr_metallic_roughness();
+    let [r, g, b, a] = pbr.base_color_factor();
+    let [er, eg, eb] = material.emissive_factor();
+    GltfMaterialData {
+        name: material.name().map(String::from),
+        base_color: Vec4::new(r, g, b, a),
+        base_color_texture: pbr.base_color_texture().map(|t| t.texture().index()),
+        metallic: pbr.metallic_factor(),
+        roughness: pbr.roughness_factor(),
+        metallic_roughness_texture: pbr.metallic_roughness_texture().map(|t| t.texture().index()),
+        normal_texture: material.normal_texture().map(|t| t.texture().index()),
+        normal_scale: material.normal_texture().map_or(1.0, |t| t.scale()),
+        occlusion_texture: material.occlusion_texture().map(|t| t.texture().index()),
+        occlusion_strength: material.occlusion_texture().map_or(1.0, |t| t.strength()),
+        emissive: Vec3::new(er, eg, eb),
+        emissive_texture: material.emissive_texture().map(|t| t.texture().index()),
+        emissive_strength: material.emissive_strength().unwrap_or(1.0),
+        blend_mode: match material.alpha_mode() {
+            ::gltf::material::AlphaMode::Opaque => BlendMode::Opaque,
+            ::gltf::material::AlphaMode::Mask => BlendMode::AlphaCutout {
+                threshold: material.alpha_cutoff().unwrap_or(0.5),
+            },
+            ::gltf::material::AlphaMode::Blend => BlendMode::AlphaBlend,
+        },
+    }
+}
+
+fn rgba_image(data: ::gltf::image::Data) -> Result<image::RgbaImage, String> {
+    use ::gltf::image::Format;
+    let (width, height) = (data.width, data.height);
+    // the importer hands out wider samples as native endian bytes
+    let words = |bytes: &[u8]| -> Vec<u16> { bytes.chunks_exact(2).map(|b| u16::from_ne_bytes([b[0], b[1]])).collect() };
+    let floats = |bytes: &[u8]| -> Vec<f32> {
+        bytes.chunks_exact(4).map(|b| f32::from_ne_bytes([b[0], b[1], b[2], b[3]])).collect()
+    };
+    let image = match data.format {
+        Format::R8 => ImageBuffer::from_raw(width, height, data.pixels).map(DynamicImage::ImageLuma8),
+        Format::R8G8 => ImageBuffer::from_raw(width, height, data.pixels).map(DynamicImage::ImageLumaA8),
+        Format::R8G8B8 => ImageBuffer::from_raw(width, height, data.pixels).map(DynamicImage::ImageRgb8),
+        Format::R8G8B8A8 => ImageBuffer::from_raw(width, height, data.pixels).map(DynamicImage::ImageRgba8),
+        Format::R16 => ImageBuffer::from_raw(width, height, words(&data.pixels)).map(DynamicImage::ImageLuma16),
+        Format::R16G16 => ImageBuffer::from_raw(width, height, words(&data.pixels)).map(DynamicImage::ImageLumaA16),
+        Format::R16G16B16 => ImageBuffer::from_raw(width, height, words(&data.pixels)).map(DynamicImage::ImageRgb16),
+        Format::R16G16B16A16 => ImageBuffer::from_raw(width, height, words(&data.pixels)).map(DynamicImage::ImageRgba16),
+        Format::R32G32B32FLOAT => ImageBuffer::from_raw(width, height, floats(&data.pixels)).map(DynamicImage::ImageRgb32F),
+        Format::R32G32B32A32FLOAT => {
+            ImageBuffer::from_raw(width, height, floats(&data.pixels)).map(DynamicImage::ImageRgba32F)
+        }
+    };
+    image
+        .map(|image| image.to_rgba8())
+        .ok_or_else(|| format!("glTF image of {}x{} has the wrong amount of pixel data", width, height))
+}
+
+fn mat4(m: [[f32; 4]; 4]) -> Mat4 {
+    let column = |c: [f32; 4]| Vec4::new(c[0], c[1], c[2], c[3]);
+    Mat4::new(column(m[0]), column(m[1]), column(m[2]), column(m[3]))
+}
+
+pub struct GltfMesh {
+    pub name: Option<String>,
+    pub primitives: Vec<GltfPrimitive>,
+}
+
+pub struct GltfPrimitive {
+    pub mesh: Arc<Mesh<Vertex>>,
+    pub material: Arc<Material>,
+}
+
+/// A glTF file on the GPU, the materials all use one shader.
+pub struct GltfModel {
+    pub meshes: Vec<GltfMesh>,
+    pub materials: Vec<Arc<Material>>,
+    pub nodes: Vec<GltfNode>,
+    pub cameras: Vec<GltfCamera>,
+    pub roots: Vec<usize>,
+}
+
+impl GltfModel {
+    pub fn load<P: AsRef<Path>>(path: P, shader: Arc<Shader>) -> Result<Self, String> {
+        Self::from_data(GltfData::load(path)?, shader)
+    }
+
+    // meshes and materials are Arcs to be shared with render objects, all of them on the gl thread
+    #[allow(clippy::arc_with_non_send_sync)]
+    pub fn from_data(data: GltfData, shader: Arc<Shader>) -> Result<Self, String> {
+        // one texture per usage and factor, albedo and emissive are sRGB and the rest linear
+        let mut textures: HashMap<(usize, TextureUsage, [u32; 4]), Arc<Texture>> = HashMap::new();
+        let mut texture = |index: usize, usage: TextureUsage, factor: Vec4| -> Result<Arc<Texture>, String> {
+            let key = (index, usage, [factor.x, factor.y, factor.z, factor.w].map(f32::to_bits));
+            if let Some(texture) = textures.get(&key) {
+                return Ok(texture.clone());
+            }
+            let source = data.textures.get(index).ok_or_else(|| format!("glTF references unknown texture {}", index))?;
+            let srgb = matches!(usage, TextureUsage::Albedo | TextureUsage::Emissive);
+            let image = DynamicImage::ImageRgba8(apply_factor(&source.image, factor, srgb));
+            let texture = Arc::new(Texture::from_image(&image, source.sampler.spec(usage))?);
+            textures.insert(key, texture.clone());
+            Ok(texture)
+        };
+
+        let mut materials = vec![];
+        for material in &data.materials {
+            materials.push(Arc::new(build_material(material, &shader, &mut texture)?));
+        }
+        let mut default_material = None;
+
+        let mut meshes = vec![];
+        for mesh in &data.meshes {
+            let mut primitives = vec![];
+            for primitive in &mesh.primitives {
+                let material = match primitive.material {
+                    Some(index) => materials
+                        .get(index)
+                        .cloned()
+                        .ok_or_else(|| format!("glTF references unknown material {}", index))?,
+                    None => match &default_material {
+                        Some(material) => Arc::clone(material),
+                        None => {
+                            let material = Arc::new(build_material(&GltfMaterialData::default(), &shader, &mut texture)?);
+                            default_material = Some(material.clone());
+                            material
+                        }
+                    },
+                };
+                primitives.push(GltfPrimitive {
+                    mesh: Arc::new(Mesh::from_mesh_data(&primitive.data)),
+                    material,
+                });
+            }
+            meshes.push(GltfMesh {
+                name: mesh.name.clone(),
+                primitives,
+            });
+        }
+
+        Ok(Self {
+            meshes,
+            materials,
+            nodes: data.nodes,
+            cameras: data.cameras,
+            roots: data.roots,
+        })
+    }
+
+    /// One object per primitive of every node with a mesh, placed by the world matrix of its
+    /// node times `model`.
+    pub fn render_objects(&self, model: &Mat4) -> Vec<RenderObject> {
+        let mut objects = vec![];
+        for node in &self.nodes {
+            let Some(mesh) = node.mesh.and_then(|m| self.meshes.get(m)) else {
+                continue;
+            };
+            let transform = Transform::from_matrix(&(*model * node.world));
+            for primitive in &mesh.primitives {
+                objects.push(RenderObject::new(transform.clone(), primitive.mesh.clone(), primitive.material.clone()));
+            }
+        }
+        objects
+    }
+}
+
+fn build_material(
+    desc: &GltfMaterialData,
+    shader: &Arc<Shader>,
+    texture: &mut impl FnMut(usize, TextureUsage, Vec4) -> Result<Arc<Texture>, String>,
+) -> Result<Material, String> {
+    // the base color alpha is `Material::alpha`, the shader multiplies it with the texture
+    let base_color = Vec4::new(desc.base_color.x, desc.base_color.y, desc.base_color.z, 1.0);
+    let albedo = match desc.base_color_texture {
+        Some(index) => MaterialProperty::Texture(texture(index, TextureUsage::Albedo, base_color)?),
+        None => MaterialProperty::Color(base_color.truncate(3)),
+    };
+    let normal = match desc.normal_texture {
+        Some(index) => NormalMap::Texture {
+            texture: texture(index, TextureUsage::Normal, Vec4::one())?,
+            scale: desc.normal_scale,
+        },
+        None => NormalMap::None,
+    };
+    let shares_occlusion = desc.occlusion_texture.is_some() && desc.occlusion_texture == desc.metallic_roughness_texture;
+    let packed = match desc.metallic_roughness_texture {
+        // occlusion in red has no factor
+        Some(index) => Some(PackedMap {
+            texture: texture(index, TextureUsage::Data, Vec4::new(1.0, desc.roughness, desc.metallic, 1.0))?,
+            roughness: Some(Channel::G),
+            metallic: Some(Channel::B),
+            occlusion: shares_occlusion.then_some(Channel::R),
+        }),
+        None => None,
+    };
+    let occlusion = match desc.occlusion_texture {
+        Some(index) if !shares_occlusion => Some(texture(index, TextureUsage::Data, Vec4::one())?),
+        _ => None,
+    };
+    let emissive = match desc.emissive_texture {
+        Some(index) => {
+            let factor = Vec4::new(desc.emissive.x, desc.emissive.y, desc.emissive.z, 1.0);
+            MaterialProperty::Texture(texture(index, TextureUsage::Emissive, factor)?)
+        }
+        None => MaterialProperty::Color(desc.emissive),
+    };
+
+    Ok(Material {
+        shader: shader.clone(),
+        albedo,
+        metallic: MaterialProperty::Value(desc.metallic),
+        roughness: MaterialProperty::Value(desc.roughness),
+        normal,
+        emissive,
+        emissive_strength: desc.emissive_strength,
+        occlusion,
+        occlusion_strength: desc.occlusion_strength,
+        packed,
+        blend_mode: desc.blend_mode,
+        alpha: desc.base_color.w,
+        ..Default::default()
+    })
+}
+
+/// `image` with every channel multiplied by `factor`, the way glTF factors scale their textures.
+/// The color of sRGB images is scaled in linear space, alpha is always linear.
+pub fn apply_factor(image: &image::RgbaImage, factor: Vec4, srgb: bool) -> image::RgbaImage {
+    let mut image = image.clone();
+    if factor == Vec4::one() {
+        return image;
+    }
+    let factor = [factor.x, factor.y, factor.z, factor.w];
+    for pixel in image.pixels_mut() {
+        for (channel, value) in pixel.0.iter_mut().enumerate() {
+            let v = *value as f32 / 255.0;
+            let v = if srgb && channel < 3 {
+                linear_to_srgb(srgb_to_linear(v) * factor[channel])
+            } else {
+                v * factor[channel]
+            };
+            *value = (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+        }
+    }
+    image
+}
+
+fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+}
+
+fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 { c * 12.92 } else { 1.055 * c.powf(1.0 / 2.4) - 0.055 }
+}
@@ -0,0 +1,126 @@
+use glm::{normalize, Vec3};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LightKind {
+    /// Infinitely far away, like the sun. `direction` points from the light into the scene.
+    Directional { direction: Vec3 },
+    /// Fades out to zero at `range`.
+    Point { position: Vec3, range: f32 },
+    /// Cone half angles in radians, full intensity inside `inner_angle`, zero outside `outer_angle`.
+    Spot {
+        position: Vec3,
+        direction: Vec3,
+        range: f32,
+        inner_angle: f32,
+        outer_angle: f32,
+    },
+}
+
+/// Cube map shadow of a point or spot light. Only the most important lights within
+/// `Renderer::point_shadow_budget` actually get one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PointShadowSettings {
+    /// Width and height of every cube face.
+    pub resolution: u32,
+    pub near: f32,
+    /// Casters further away than this don't cast shadows.
+    pub far: f32,
+    /// World space distance subtracted before the depth compare.
+    pub bias: f32,
+}
+
+impl PointShadowSettings {
+    pub fn new(resolution: u32, near: f32, far: f32) -> Self {
+        Self { resolution, near, far, bias: 0.05 }
+    }
+}
+
+/// A dynamic light, submitted to the renderer every frame with `Renderer::add_light`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Light {
+    pub kind: LightKind,
+    pub color: Vec3,
+    pub intensity: f32,
+    /// Ignored for directional lights, they use the cascades of `Renderer::shadow_settings`.
+    pub shadow: Option<PointShadowSettings>,
+}
+
+impl Light {
+    pub fn directional(direction: Vec3, color: Vec3, intensity: f32) -> Self {
+        Self { kind: LightKind::Directional { direction: normalize(direction) }, color, intensity, shadow: None }
+    }
+
+    pub fn point(position: Vec3, color: Vec3, intensity: f32, range: f32) -> Self {
+        Self { kind: LightKind::Point { position, range }, color, intensity, shadow: None }
+    }
+
+    pub fn spot(
+        position: Vec3,
+        direction: Vec3,
+        color: Vec3,
+        intensity: f32,
+        range: f32,
+        inner_angle: f32,
+        outer_angle: f32,
+    ) -> Self {
+        Self {
+            kind: LightKind::Spot {
+                position,
+                direction: normalize(direction),
+                range,
+                inner_angle,
+                outer_angle,
+            },
+            color,
+            intensity,
+            shadow: None,
+        }
+    }
+
+    pub fn with_shadow(mut self, shadow: PointShadowSettings) -> Self {
+        self.shadow = Some(shadow);
+        self
+    }
+
+    pub fn position(&self) -> Option<Vec3> {
+        match self.kind {
+            LightKind::Directional { .. } => None,
+            LightKind::Point { position, .. } | LightKind::Spot { position, .. } => Some(position),
+        }
+    }
+
+    pub(crate) fn to_gpu(self) -> GpuLight {
+        let zero = Vec3::new(0.0, 0.0, 0.0);
+        let (light_type, position, direction, range, cone) = match self.kind {
+            LightKind::Directional { direction } => (LIGHT_DIRECTIONAL, zero, direction, 0.0, [0.0, 0.0]),
+            LightKind::Point { position, range } => (LIGHT_POINT, position, zero, range, [0.0, 0.0]),
+            LightKind::Spot { position, direction, range, inner_angle, outer_angle } => {
+                // the shader compares cosines, inner has to stay inside outer for the smoothstep
+                let outer = outer_angle.max(inner_angle + 1e-4);
+                (LIGHT_SPOT, position, direction, range, [inner_angle.cos(), outer.cos()])
+            }
+        };
+        GpuLight {
+            position_type: [position.x, position.y, position.z, light_type as f32],
+            direction_range: [direction.x, direction.y, direction.z, range],
+            color_intensity: [self.color.x, self.color.y, self.color.z, self.intensity],
+            cone: [cone[0], cone[1], 0.0, 0.0],
+        }
+    }
+}
+
+// must match the defines in the shaders
+const LIGHT_DIRECTIONAL: i32 = 0;
+const LIGHT_POINT: i32 = 1;
+const LIGHT_SPOT: i32 = 2;
+
+/// std430 layout of one element of the `LightBuffer` SSBO.
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(C)]
+pub(crate) struct GpuLight {
+    position_type: [f32; 4],
+    direction_range: [f32; 4],
+    color_intensity: [f32; 4],
+    /// cos of the inner and outer cone angle
+    cone: [f32; 4],
+}
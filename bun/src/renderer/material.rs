@@ -1,8 +1,16 @@
 use std::ffi::CString;
+use crate::renderer::renderer::{SPLAT_ALBEDO_UNIT, SPLAT_NORMAL_UNIT};
+use crate::renderer::texture::TextureArray;
+#[cfg(feature = "serde")]
+use crate::renderer::texture::TextureSpec;
 use crate::{Shader, Texture};
 use glm::{Vec2, Vec3, Vec4};
 use num_traits::{One, Zero};
 use std::sync::Arc;
+#[cfg(feature = "serde")]
+use std::collections::HashMap;
+#[cfg(feature = "serde")]
+use std::path::PathBuf;
 use crate::renderer::buffer::UBO;
 
 pub enum MaterialProperty {
@@ -19,21 +27,194 @@ pub enum NormalMap {
     }
 }
 
+/// Most layers a `SplatMap` blends, one per weight channel.
+pub const MAX_SPLAT_LAYERS: usize = 4;
+
+/// Blends texture layers by the channels of a weight texture, for terrain. Replaces the albedo,
+/// normal map and roughness of the material. The weights span the whole uv range of the mesh,
+/// the layers repeat `tiling` times across it.
+pub struct SplatMap {
+    /// Channel i is the weight of layer i, the weights are normalized in the shader.
+    pub weights: Arc<Texture>,
+    pub albedo: Arc<TextureArray>,
+    /// Same layer order as `albedo`.
+    pub normal: Option<Arc<TextureArray>>,
+    pub tiling: [f32; MAX_SPLAT_LAYERS],
+    pub roughness: [f32; MAX_SPLAT_LAYERS],
+}
+
+/// How a `HeightMap` shapes the surface.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HeightMode {
+    /// Parallax occlusion mapping, the surface is carved up to `scale` texture widths into
+    /// the mesh and the geometry itself doesn't move.
+    #[default]
+    Parallax,
+    /// Tessellates the mesh and moves the vertices up to `scale` world units along their
+    /// normal. Needs a material shader with tessellation stages, see `Shader::with_tessellation`.
+    /// Shadow maps and the SSAO prepass still see the undisplaced mesh.
+    Displacement {
+        /// Targeted screen space length of a tessellated edge, in pixels.
+        edge_length: f32,
+        /// Upper bound of the tessellation level per edge, at most 64.
+        max_level: f32,
+    },
+}
+
+/// Height field of a material, white is high.
+pub struct HeightMap {
+    pub texture: Arc<Texture>,
+    pub scale: f32,
+    pub mode: HeightMode,
+    /// Parallax ray march steps, `min_layers` looking straight at the surface up to `max_layers`
+    /// at grazing angles.
+    pub min_layers: u32,
+    pub max_layers: u32,
+    /// The parallax height field shadows lights, forward path only.
+    pub self_shadowing: bool,
+}
+
+impl HeightMap {
+    pub fn new(texture: Arc<Texture>) -> Self {
+        Self {
+            texture,
+            scale: 0.05,
+            mode: HeightMode::Parallax,
+            min_layers: 8,
+            max_layers: 32,
+            self_shadowing: true,
+        }
+    }
+}
+
+/// Color channel of a texture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Channel {
+    R,
+    G,
+    B,
+    A,
+}
+
+impl Channel {
+    pub fn index(self) -> i32 {
+        match self {
+            Channel::R => 0,
+            Channel::G => 1,
+            Channel::B => 2,
+            Channel::A => 3,
+        }
+    }
+}
+
+/// Several scalar maps in the channels of one texture. Every map it provides replaces the
+/// separate property or texture of the material.
+pub struct PackedMap {
+    pub texture: Arc<Texture>,
+    pub roughness: Option<Channel>,
+    pub metallic: Option<Channel>,
+    pub occlusion: Option<Channel>,
+}
+
+impl PackedMap {
+    /// glTF convention, occlusion in red, roughness in green and metallic in blue.
+    pub fn orm(texture: Arc<Texture>) -> Self {
+        Self {
+            texture,
+            roughness: Some(Channel::G),
+            metallic: Some(Channel::B),
+            occlusion: Some(Channel::R),
+        }
+    }
+}
+
+/// How a material's color combines with what is already in the frame.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BlendMode {
+    #[default]
+    Opaque,
+    /// Opaque, fragments with alpha below `threshold` are discarded. Foliage, fences.
+    AlphaCutout { threshold: f32 },
+    /// Classic `src * a + dst * (1 - a)`, sorted back to front.
+    AlphaBlend,
+    /// Added on top of the frame, alpha scales the contribution. Order independent.
+    Additive,
+    /// The shader output is already multiplied by alpha, `src + dst * (1 - a)`.
+    Premultiplied,
+}
+
+impl BlendMode {
+    /// Blended modes are drawn after all opaque objects without writing depth.
+    pub fn is_transparent(self) -> bool {
+        matches!(self, BlendMode::AlphaBlend | BlendMode::Additive | BlendMode::Premultiplied)
+    }
+
+    pub fn writes_depth(self) -> bool {
+        !self.is_transparent()
+    }
+
+    /// Source and destination blend factors, `None` if blending is off.
+    pub fn blend_func(self) -> Option<(u32, u32)> {
+        match self {
+            BlendMode::Opaque | BlendMode::AlphaCutout { .. } => None,
+            BlendMode::AlphaBlend => Some((gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA)),
+            BlendMode::Additive => Some((gl::SRC_ALPHA, gl::ONE)),
+            BlendMode::Premultiplied => Some((gl::ONE, gl::ONE_MINUS_SRC_ALPHA)),
+        }
+    }
+
+    /// `alpha_mode` in the shaders.
+    fn shader_index(self) -> i32 {
+        match self {
+            BlendMode::Opaque => 0,
+            BlendMode::AlphaCutout { .. } => 1,
+            BlendMode::AlphaBlend => 2,
+            BlendMode::Additive => 3,
+            BlendMode::Premultiplied => 4,
+        }
+    }
+
+    fn cutoff(self) -> f32 {
+        match self {
+            BlendMode::AlphaCutout { threshold } => threshold,
+            _ => 0.0,
+        }
+    }
+}
+
 pub struct Material {
     pub shader: Arc<Shader>,
     pub albedo: MaterialProperty,
     pub metallic: MaterialProperty,
     pub roughness: MaterialProperty,
     pub normal: NormalMap,
+    /// Emitted light, a color or a texture (`TextureSpec::emissive`).
     pub emissive: MaterialProperty,
+    /// Scale of the emission, above 1 it is bright enough to bloom.
+    pub emissive_strength: f32,
+    /// Ambient occlusion from the red channel, only darkens ambient and image based light.
+    pub occlusion: Option<Arc<Texture>>,
+    /// 0 ignores the occlusion map, 1 applies it fully.
+    pub occlusion_strength: f32,
+    pub packed: Option<PackedMap>,
+    pub height: Option<HeightMap>,
+    pub splat: Option<SplatMap>,
     
     pub texture_scale: Vec2,
+    pub blend_mode: BlendMode,
+    /// Multiplied with the alpha of the albedo texture, or the alpha of an albedo color.
+    pub alpha: f32,
     
     pub ubo: UBO,
 }
 
 impl Material {
-    pub(crate) fn apply(&self) {
+    /// Binds textures and uniforms for `shader`, usually `self.shader`. The G-buffer pass
+    /// binds materials for its own program.
+    pub(crate) fn apply(&self, shader: &Shader) {
         
         let ubo_data = MaterialUBO::from_material(self);
         
@@ -45,37 +226,81 @@ impl Material {
             texture.bind(1).unwrap();
         }
         
-        if let MaterialProperty::Texture(texture) = &self.roughness {
+        if let Some(texture) = self.roughness_texture() {
             texture.bind(2).unwrap();
         }
         
-        if let MaterialProperty::Texture(texture) = &self.metallic {
+        if let Some(texture) = self.metallic_texture() {
             texture.bind(3).unwrap();
         }
         
+        if let MaterialProperty::Texture(texture) = &self.emissive {
+            texture.bind(4).unwrap();
+        }
+        
+        if let Some(texture) = self.occlusion_texture() {
+            texture.bind(5).unwrap();
+        }
+        
+        if let Some(height) = &self.height {
+            height.texture.bind(6).unwrap();
+        }
+        
+        if let Some(splat) = &self.splat {
+            splat.weights.bind(7).unwrap();
+            splat.albedo.bind(SPLAT_ALBEDO_UNIT).unwrap();
+            if let Some(normal) = &splat.normal {
+                normal.bind(SPLAT_NORMAL_UNIT).unwrap();
+            }
+        }
+        
         self.ubo.bind();
         unsafe {
             let location = CString::new("MaterialUBO").unwrap();
-            let block_index = gl::GetUniformBlockIndex(self.shader.id(), location.as_ptr());
+            let block_index = gl::GetUniformBlockIndex(shader.id(), location.as_ptr());
             if block_index != gl::INVALID_INDEX {
-                gl::UniformBlockBinding(self.shader.id(), block_index, 2);
+                gl::UniformBlockBinding(shader.id(), block_index, 2);
                 gl::BindBufferBase(gl::UNIFORM_BUFFER, 2, self.ubo.id());
                 self.ubo.buffer_data(&[ubo_data]);
             }
         }
         
-        if let Some(loc) = self.shader.get_uniform_location("specular_intensity") {
-            self.shader.set_uniform(loc, 1.0);
+        if let Some(loc) = shader.get_uniform_location("specular_intensity") {
+            shader.set_uniform(loc, 1.0);
         }
-        if let Some(loc) = self.shader.get_uniform_location("tint") {
-            self.shader.set_uniform(loc, Vec4::one());
+        if let Some(loc) = shader.get_uniform_location("tint") {
+            shader.set_uniform(loc, Vec4::one());
         }
-        if let Some(loc) = self.shader.get_uniform_location("texture_scale") {
-            self.shader.set_uniform(loc, self.texture_scale);
+        if let Some(loc) = shader.get_uniform_location("texture_scale") {
+            shader.set_uniform(loc, self.texture_scale);
         }
     }
 }
 
+impl Material {
+    fn packed_texture(&self, channel: impl Fn(&PackedMap) -> Option<Channel>) -> Option<&Arc<Texture>> {
+        self.packed.as_ref().filter(|packed| channel(packed).is_some()).map(|packed| &packed.texture)
+    }
+
+    fn roughness_texture(&self) -> Option<&Arc<Texture>> {
+        self.packed_texture(|p| p.roughness).or(match &self.roughness {
+            MaterialProperty::Texture(texture) => Some(texture),
+            _ => None,
+        })
+    }
+
+    fn metallic_texture(&self) -> Option<&Arc<Texture>> {
+        self.packed_texture(|p| p.metallic).or(match &self.metallic {
+            MaterialProperty::Texture(texture) => Some(texture),
+            _ => None,
+        })
+    }
+
+    fn occlusion_texture(&self) -> Option<&Arc<Texture>> {
+        self.packed_texture(|p| p.occlusion).or(self.occlusion.as_ref())
+    }
+}
+
 impl Default for Material {
     fn default() -> Self {
         let ubo = UBO::new();
@@ -88,14 +313,23 @@ impl Default for Material {
             roughness: MaterialProperty::Value(1.0),
             normal: NormalMap::None,
             emissive: MaterialProperty::Color(Vec3::zero()),
+            emissive_strength: 1.0,
+            occlusion: None,
+            occlusion_strength: 1.0,
+            packed: None,
+            height: None,
+            splat: None,
             
             texture_scale: Vec2::one(),
+            blend_mode: BlendMode::Opaque,
+            alpha: 1.0,
             
             ubo
         }
     }
 }
 
+/// Matches the `MaterialUBO` block of the engine include `bun/material.glsl`.
 #[repr(C, align(16))]
 pub struct MaterialUBO {
     albedo_color: Vec4,
@@ -110,30 +344,114 @@ pub struct MaterialUBO {
     emissive_has_texture: i32,
     metallic_has_texture: i32,
     roughness_has_texture: i32,
+    
+    alpha_mode: i32,
+    alpha_cutoff: f32,
+    
+    occlusion_has_texture: i32,
+    // channel the scalar maps are read from, 0 for separate textures
+    roughness_channel: i32,
+    metallic_channel: i32,
+    occlusion_channel: i32,
+    occlusion_strength: f32,
+    
+    height_has_texture: i32,
+    height_scale: f32,
+    height_min_layers: i32,
+    height_max_layers: i32,
+    height_self_shadowing: i32,
+    
+    displacement_has_texture: i32,
+    displacement_scale: f32,
+    tessellation_edge_length: f32,
+    tessellation_max_level: f32,
+    
+    splat_tiling: Vec4,
+    splat_roughness: Vec4,
+    splat_has_texture: i32,
+    splat_layer_count: i32,
+    splat_normal_has_texture: i32,
 }
 
 impl MaterialUBO {
     fn from_material(material: &Material) -> Self {
-        let (albedo_color, albedo_has_texture) = get_material_property_vec4(&material.albedo);
-        let (roughness_value, roughness_has_texture) = get_material_property_f32(&material.roughness);
-        let (metallic_value, metallic_has_texture) = get_material_property_f32(&material.metallic);
+        let (mut albedo_color, albedo_has_texture) = get_material_property_vec4(&material.albedo);
+        albedo_color.w = material.alpha;
+        let packed_channel = |channel: fn(&PackedMap) -> Option<Channel>| material.packed.as_ref().and_then(channel);
+        let (roughness_value, roughness_has_texture, roughness_channel) = match packed_channel(|p| p.roughness) {
+            Some(channel) => (0.0, 1, channel.index()),
+            None => {
+                let (value, has_texture) = get_material_property_f32(&material.roughness);
+                (value, has_texture, 0)
+            }
+        };
+        let (metallic_value, metallic_has_texture, metallic_channel) = match packed_channel(|p| p.metallic) {
+            Some(channel) => (0.0, 1, channel.index()),
+            None => {
+                let (value, has_texture) = get_material_property_f32(&material.metallic);
+                (value, has_texture, 0)
+            }
+        };
+        let occlusion_channel = packed_channel(|p| p.occlusion).map_or(0, Channel::index);
+        // the strength rides along in alpha
+        let (mut emissive_color, emissive_has_texture) = match &material.emissive {
+            MaterialProperty::Value(v) => (Vec4::new(*v, *v, *v, 1.0), 0),
+            property => get_material_property_vec4(property),
+        };
+        emissive_color.w = material.emissive_strength;
         let (normal_has_texture, normal_scale_value) = match &material.normal {
             NormalMap::None => (0, 1.0f32),
             NormalMap::Texture { scale, .. } => (1, *scale)
         };
+        let splat = material.splat.as_ref();
+        let (height, displacement) = match &material.height {
+            Some(height) => match height.mode {
+                HeightMode::Parallax => (Some(height), None),
+                HeightMode::Displacement { edge_length, max_level } => (None, Some((height.scale, edge_length, max_level))),
+            },
+            None => (None, None),
+        };
         
         Self {
             albedo_color,
-            emissive_color: Vec4::zero(),
+            emissive_color,
             metallic_value,
             roughness_value,
             normal_scale_value,
             
             albedo_has_texture,
             normal_has_texture,
-            emissive_has_texture: 0,
+            emissive_has_texture,
             metallic_has_texture,
             roughness_has_texture,
+            
+            alpha_mode: material.blend_mode.shader_index(),
+            alpha_cutoff: material.blend_mode.cutoff(),
+            
+            occlusion_has_texture: material.occlusion_texture().is_some() as i32,
+            roughness_channel,
+            metallic_channel,
+            occlusion_channel,
+            occlusion_strength: material.occlusion_strength,
+            
+            height_has_texture: height.is_some() as i32,
+            height_scale: height.map_or(0.0, |h| h.scale),
+            height_min_layers: height.map_or(0, |h| h.min_layers.max(1) as i32),
+            height_max_layers: height.map_or(0, |h| h.max_layers.max(h.min_layers).max(1) as i32),
+            height_self_shadowing: height.is_some_and(|h| h.self_shadowing) as i32,
+            
+            displacement_has_texture: displacement.is_some() as i32,
+            displacement_scale: displacement.map_or(0.0, |(scale, ..)| scale),
+            tessellation_edge_length: displacement.map_or(1.0, |(_, edge_length, _)| edge_length.max(1.0)),
+            tessellation_max_level: displacement.map_or(1.0, |(.., max_level)| max_level.clamp(1.0, 64.0)),
+            
+            splat_tiling: splat.map_or(Vec4::one(), |s| Vec4::new(s.tiling[0], s.tiling[1], s.tiling[2], s.tiling[3])),
+            splat_roughness: splat.map_or(Vec4::one(), |s| {
+                Vec4::new(s.roughness[0], s.roughness[1], s.roughness[2], s.roughness[3])
+            }),
+            splat_has_texture: splat.is_some() as i32,
+            splat_layer_count: splat.map_or(0, |s| s.albedo.layers().min(MAX_SPLAT_LAYERS) as i32),
+            splat_normal_has_texture: splat.is_some_and(|s| s.normal.is_some()) as i32,
         }
     }
 }
@@ -161,4 +479,523 @@ fn get_material_property_f32(property: &MaterialProperty) -> (f32, i32) {
         },
         _ => panic!("f32 should never have a color")
     }
-}
\ No newline at end of file
+}
+
+// ----------- serde -----------
+// GPU resources can't be written out directly, textures and shaders are stored as
+// references to the files they were loaded from and get reloaded by `MaterialData::build`.
+// Scene files use the same types and refer to their texture and shader tables by name.
+
+/// An image file, or a texture a scene declares by name. Names are written as a plain
+/// string, e.g. `Texture("brick")`.
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextureRef {
+    File {
+        path: PathBuf,
+        spec: TextureSpec,
+    },
+    Named(String),
+}
+
+/// Shader files, or a shader a scene declares by name.
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShaderRef {
+    Files {
+        vertex: PathBuf,
+        fragment: PathBuf,
+        /// Tessellation control and evaluation stages.
+        tessellation: Option<(PathBuf, PathBuf)>,
+    },
+    Named(String),
+}
+
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum MaterialPropertyData {
+    Value(f32),
+    Color(#[serde(with = "crate::serde_glm::vec3")] Vec3),
+    Texture(TextureRef),
+}
+
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct NormalMapData {
+    pub texture: TextureRef,
+    #[serde(default = "one")]
+    pub scale: f32,
+}
+
+/// Scalar maps read from channels of one texture, e.g. glTF's occlusion R, roughness G, metallic B.
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PackedMapData {
+    pub texture: TextureRef,
+    #[serde(default)]
+    pub roughness: Option<Channel>,
+    #[serde(default)]
+    pub metallic: Option<Channel>,
+    #[serde(default)]
+    pub occlusion: Option<Channel>,
+}
+
+/// Blends up to four layers by the RGBA channels of the `weights` texture.
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SplatMapData {
+    pub weights: TextureRef,
+    pub layers: Vec<SplatLayerData>,
+    /// Every layer image is resized to this many pixels along each side.
+    #[serde(default = "default_splat_size")]
+    pub size: u32,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SplatLayerData {
+    pub albedo: PathBuf,
+    /// Either every layer has a normal map or none does.
+    #[serde(default)]
+    pub normal: Option<PathBuf>,
+    /// Repeats of the layer across the material uv.
+    #[serde(default = "one")]
+    pub tiling: f32,
+    #[serde(default = "one")]
+    pub roughness: f32,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct HeightMapData {
+    pub texture: TextureRef,
+    #[serde(default = "default_height_scale")]
+    pub scale: f32,
+    #[serde(default)]
+    pub mode: HeightMode,
+    #[serde(default = "default_height_min_layers")]
+    pub min_layers: u32,
+    #[serde(default = "default_height_max_layers")]
+    pub max_layers: u32,
+    #[serde(default = "enabled")]
+    pub self_shadowing: bool,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct MaterialData {
+    /// `None` uses the built-in default shader.
+    #[serde(default)]
+    pub shader: Option<ShaderRef>,
+    pub albedo: MaterialPropertyData,
+    pub metallic: MaterialPropertyData,
+    pub roughness: MaterialPropertyData,
+    #[serde(default)]
+    pub normal: Option<NormalMapData>,
+    #[serde(default = "no_emission")]
+    pub emissive: MaterialPropertyData,
+    #[serde(default = "one")]
+    pub emissive_strength: f32,
+    #[serde(default)]
+    pub occlusion: Option<TextureRef>,
+    #[serde(default = "one")]
+    pub occlusion_strength: f32,
+    #[serde(default)]
+    pub packed: Option<PackedMapData>,
+    #[serde(default)]
+    pub height: Option<HeightMapData>,
+    #[serde(default)]
+    pub splat: Option<SplatMapData>,
+    #[serde(with = "crate::serde_glm::vec2", default = "unit_scale")]
+    pub texture_scale: Vec2,
+    #[serde(default)]
+    pub blend_mode: BlendMode,
+    #[serde(default = "one")]
+    pub alpha: f32,
+}
+
+#[cfg(feature = "serde")]
+fn one() -> f32 { 1.0 }
+#[cfg(feature = "serde")]
+fn enabled() -> bool { true }
+#[cfg(feature = "serde")]
+fn unit_scale() -> Vec2 { Vec2::new(1.0, 1.0) }
+#[cfg(feature = "serde")]
+fn no_emission() -> MaterialPropertyData { MaterialPropertyData::Color(Vec3::zero()) }
+#[cfg(feature = "serde")]
+fn default_height_scale() -> f32 { 0.05 }
+#[cfg(feature = "serde")]
+fn default_height_min_layers() -> u32 { 8 }
+#[cfg(feature = "serde")]
+fn default_height_max_layers() -> u32 { 32 }
+#[cfg(feature = "serde")]
+fn default_splat_size() -> u32 { 1024 }
+
+/// Either a plain string naming a scene asset or the struct `T`.
+#[cfg(feature = "serde")]
+enum NameOr<T> {
+    Name(String),
+    Value(T),
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>> serde::Deserialize<'de> for NameOr<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor<T>(std::marker::PhantomData<T>);
+
+        impl<'de, T: serde::Deserialize<'de>> serde::de::Visitor<'de> for Visitor<T> {
+            type Value = NameOr<T>;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a name or a struct")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, name: &str) -> Result<Self::Value, E> {
+                Ok(NameOr::Name(name.to_string()))
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+                T::deserialize(serde::de::value::MapAccessDeserializer::new(map)).map(NameOr::Value)
+            }
+        }
+
+        deserializer.deserialize_any(Visitor(std::marker::PhantomData))
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct TextureFile {
+    path: PathBuf,
+    spec: TextureSpec,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct ShaderFiles {
+    vertex: PathBuf,
+    fragment: PathBuf,
+    #[serde(default)]
+    tessellation: Option<(PathBuf, PathBuf)>,
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for TextureRef {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            TextureRef::File { path, spec } => TextureFile { path: path.clone(), spec: *spec }.serialize(serializer),
+            TextureRef::Named(name) => serializer.serialize_str(name),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for TextureRef {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match NameOr::<TextureFile>::deserialize(deserializer)? {
+            NameOr::Name(name) => TextureRef::Named(name),
+            NameOr::Value(TextureFile { path, spec }) => TextureRef::File { path, spec },
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for ShaderRef {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            ShaderRef::Files { vertex, fragment, tessellation } => ShaderFiles {
+                vertex: vertex.clone(),
+                fragment: fragment.clone(),
+                tessellation: tessellation.clone(),
+            }
+            .serialize(serializer),
+            ShaderRef::Named(name) => serializer.serialize_str(name),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for ShaderRef {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match NameOr::<ShaderFiles>::deserialize(deserializer)? {
+            NameOr::Name(name) => ShaderRef::Named(name),
+            NameOr::Value(ShaderFiles { vertex, fragment, tessellation }) => {
+                ShaderRef::Files { vertex, fragment, tessellation }
+            }
+        })
+    }
+}
+
+/// Scene assets that `TextureRef::Named` and `ShaderRef::Named` resolve to.
+#[cfg(feature = "serde")]
+pub type NamedShaders = HashMap<String, Arc<Shader>>;
+#[cfg(feature = "serde")]
+pub type NamedTextures = HashMap<String, Arc<Texture>>;
+
+#[cfg(feature = "serde")]
+impl TextureRef {
+    fn from_texture(texture: &Texture) -> Result<Self, String> {
+        let path = texture
+            .path()
+            .ok_or_else(|| "Texture was not loaded from a file".to_string())?;
+        Ok(Self::File { path: path.to_path_buf(), spec: texture.spec() })
+    }
+
+    pub fn load(&self, textures: &NamedTextures) -> Result<Arc<Texture>, String> {
+        match self {
+            TextureRef::File { path, spec } => Ok(Arc::new(Texture::new(path, *spec)?)),
+            TextureRef::Named(name) => textures.get(name).cloned().ok_or_else(|| format!("unknown texture '{}'", name)),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl ShaderRef {
+    // like `Material::default`, the shader sits in an Arc that never leaves the gl thread
+    #[allow(clippy::arc_with_non_send_sync)]
+    pub fn load(&self, shaders: &NamedShaders) -> Result<Arc<Shader>, String> {
+        match self {
+            ShaderRef::Files { vertex, fragment, tessellation: Some((control, evaluation)) } => {
+                Ok(Arc::new(Shader::with_tessellation(vertex, control, evaluation, fragment)?))
+            }
+            ShaderRef::Files { vertex, fragment, tessellation: None } => Ok(Arc::new(Shader::new(vertex, fragment)?)),
+            ShaderRef::Named(name) => shaders.get(name).cloned().ok_or_else(|| format!("unknown shader '{}'", name)),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl MaterialProperty {
+    pub fn to_data(&self) -> Result<MaterialPropertyData, String> {
+        Ok(match self {
+            MaterialProperty::Value(v) => MaterialPropertyData::Value(*v),
+            MaterialProperty::Color(c) => MaterialPropertyData::Color(*c),
+            MaterialProperty::Texture(t) => MaterialPropertyData::Texture(TextureRef::from_texture(t)?),
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl MaterialPropertyData {
+    pub fn build(&self, textures: &NamedTextures) -> Result<MaterialProperty, String> {
+        Ok(match self {
+            MaterialPropertyData::Value(v) => MaterialProperty::Value(*v),
+            MaterialPropertyData::Color(c) => MaterialProperty::Color(*c),
+            MaterialPropertyData::Texture(t) => MaterialProperty::Texture(t.load(textures)?),
+        })
+    }
+
+    fn expect_color(&self, slot: &str) -> Result<(), String> {
+        match self {
+            MaterialPropertyData::Value(_) => Err(format!("{} must be Color or Texture", slot)),
+            _ => Ok(()),
+        }
+    }
+
+    fn expect_scalar(&self, slot: &str) -> Result<(), String> {
+        match self {
+            MaterialPropertyData::Color(_) => Err(format!("{} must be Value or Texture", slot)),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl SplatMapData {
+    fn check(&self) -> Result<(), String> {
+        if self.layers.is_empty() || self.layers.len() > MAX_SPLAT_LAYERS {
+            return Err(format!("needs 1 to {} splat layers, got {}", MAX_SPLAT_LAYERS, self.layers.len()));
+        }
+        let normals = self.layers.iter().filter(|l| l.normal.is_some()).count();
+        if normals != 0 && normals != self.layers.len() {
+            return Err("needs a normal map on every splat layer or none".to_string());
+        }
+        Ok(())
+    }
+
+    // like `Material::default`, the arrays sit in Arcs that never leave the gl thread
+    #[allow(clippy::arc_with_non_send_sync)]
+    fn build(&self, textures: &NamedTextures) -> Result<SplatMap, String> {
+        let albedo: Vec<&PathBuf> = self.layers.iter().map(|l| &l.albedo).collect();
+        let normal: Vec<&PathBuf> = self.layers.iter().filter_map(|l| l.normal.as_ref()).collect();
+        let mut tiling = [1.0; MAX_SPLAT_LAYERS];
+        let mut roughness = [1.0; MAX_SPLAT_LAYERS];
+        for (i, layer) in self.layers.iter().enumerate() {
+            tiling[i] = layer.tiling;
+            roughness[i] = layer.roughness;
+        }
+        Ok(SplatMap {
+            weights: self.weights.load(textures)?,
+            albedo: Arc::new(TextureArray::from_paths(&albedo, TextureSpec::albedo(), self.size)?),
+            normal: match normal.is_empty() {
+                true => None,
+                false => Some(Arc::new(TextureArray::from_paths(&normal, TextureSpec::normal(), self.size)?)),
+            },
+            tiling,
+            roughness,
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Material {
+    pub fn to_data(&self) -> Result<MaterialData, String> {
+        let shader = match (self.shader.vertex_path(), self.shader.fragment_path()) {
+            (Some(vertex), Some(fragment)) => Some(ShaderRef::Files {
+                vertex: vertex.clone(),
+                fragment: fragment.clone(),
+                tessellation: self
+                    .shader
+                    .tess_control_path()
+                    .cloned()
+                    .zip(self.shader.tess_evaluation_path().cloned()),
+            }),
+            _ => None,
+        };
+        let normal = match &self.normal {
+            NormalMap::None => None,
+            NormalMap::Texture { texture, scale } => Some(NormalMapData {
+                texture: TextureRef::from_texture(texture)?,
+                scale: *scale,
+            }),
+        };
+        Ok(MaterialData {
+            shader,
+            albedo: self.albedo.to_data()?,
+            metallic: self.metallic.to_data()?,
+            roughness: self.roughness.to_data()?,
+            normal,
+            emissive: self.emissive.to_data()?,
+            emissive_strength: self.emissive_strength,
+            occlusion: self.occlusion.as_deref().map(TextureRef::from_texture).transpose()?,
+            occlusion_strength: self.occlusion_strength,
+            packed: match &self.packed {
+                Some(packed) => Some(PackedMapData {
+                    texture: TextureRef::from_texture(&packed.texture)?,
+                    roughness: packed.roughness,
+                    metallic: packed.metallic,
+                    occlusion: packed.occlusion,
+                }),
+                None => None,
+            },
+            height: match &self.height {
+                Some(height) => Some(HeightMapData {
+                    texture: TextureRef::from_texture(&height.texture)?,
+                    scale: height.scale,
+                    mode: height.mode,
+                    min_layers: height.min_layers,
+                    max_layers: height.max_layers,
+                    self_shadowing: height.self_shadowing,
+                }),
+                None => None,
+            },
+            splat: match &self.splat {
+                Some(splat) => Some(SplatMapData {
+                    weights: TextureRef::from_texture(&splat.weights)?,
+                    layers: splat
+                        .albedo
+                        .paths()
+                        .iter()
+                        .enumerate()
+                        .map(|(i, albedo)| SplatLayerData {
+                            albedo: albedo.clone(),
+                            normal: splat.normal.as_ref().map(|normal| normal.paths()[i].clone()),
+                            tiling: splat.tiling[i],
+                            roughness: splat.roughness[i],
+                        })
+                        .collect(),
+                    size: splat.albedo.size() as u32,
+                }),
+                None => None,
+            },
+            texture_scale: self.texture_scale,
+            blend_mode: self.blend_mode,
+            alpha: self.alpha,
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl MaterialData {
+    /// Loads the referenced shader and textures. Needs a current GL context. Named references
+    /// only resolve inside a scene, see `build_with`.
+    pub fn build(&self) -> Result<Material, String> {
+        self.build_with(&HashMap::new(), &HashMap::new()).map_err(|e| format!("Material: {}", e))
+    }
+
+    /// Like `build`, named shaders and textures are taken from the given tables. Property kinds
+    /// and splat layers are checked before anything is loaded.
+    // like `Material::default`, the shader sits in an Arc that never leaves the gl thread
+    #[allow(clippy::arc_with_non_send_sync)]
+    pub fn build_with(&self, shaders: &NamedShaders, textures: &NamedTextures) -> Result<Material, String> {
+        self.albedo.expect_color("albedo")?;
+        self.metallic.expect_scalar("metallic")?;
+        self.roughness.expect_scalar("roughness")?;
+        self.emissive.expect_color("emissive")?;
+        if let Some(splat) = &self.splat {
+            splat.check()?;
+        }
+
+        let shader = match &self.shader {
+            Some(shader) => shader.load(shaders)?,
+            None => Arc::new(Shader::default()),
+        };
+        let normal = match &self.normal {
+            None => NormalMap::None,
+            Some(normal) => NormalMap::Texture {
+                texture: normal.texture.load(textures)?,
+                scale: normal.scale,
+            },
+        };
+        Ok(Material {
+            shader,
+            albedo: self.albedo.build(textures)?,
+            metallic: self.metallic.build(textures)?,
+            roughness: self.roughness.build(textures)?,
+            normal,
+            emissive: self.emissive.build(textures)?,
+            emissive_strength: self.emissive_strength,
+            occlusion: self.occlusion.as_ref().map(|t| t.load(textures)).transpose()?,
+            occlusion_strength: self.occlusion_strength,
+            packed: match &self.packed {
+                Some(packed) => Some(PackedMap {
+                    texture: packed.texture.load(textures)?,
+                    roughness: packed.roughness,
+                    metallic: packed.metallic,
+                    occlusion: packed.occlusion,
+                }),
+                None => None,
+            },
+            height: match &self.height {
+                Some(height) => Some(HeightMap {
+                    texture: height.texture.load(textures)?,
+                    scale: height.scale,
+                    mode: height.mode,
+                    min_layers: height.min_layers,
+                    max_layers: height.max_layers,
+                    self_shadowing: height.self_shadowing,
+                }),
+                None => None,
+            },
+            splat: self.splat.as_ref().map(|splat| splat.build(textures)).transpose()?,
+            texture_scale: self.texture_scale,
+            blend_mode: self.blend_mode,
+            alpha: self.alpha,
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for MaterialProperty {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_data().map_err(serde::ser::Error::custom)?.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Material {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_data().map_err(serde::ser::Error::custom)?.serialize(serializer)
+    }
+}
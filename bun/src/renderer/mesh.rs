@@ -1,8 +1,12 @@
-use crate::renderer::buffer::{EBO, VBO};
+use crate::renderer::bounds::{Aabb, BoundingSphere};
+use crate::renderer::buffer::{BufferUsage, EBO, VBO};
+use crate::renderer::bvh::Bvh;
+use crate::renderer::ray::{MeshHit, Ray};
 use crate::renderer::mesh_data::MeshData;
-use crate::renderer::vertex::{Vertex, VertexLayout};
+use crate::renderer::vertex::{InstanceData, Vertex, VertexLayout};
 use crate::renderer::vertex_array::VAO;
-use glm::{dot, normalize, IVec3, Vec2, Vec3};
+use glm::{cross, dot, normalize, IVec3, Vec2, Vec3, Vec4};
+use std::cell::OnceCell;
 use std::collections::HashMap;
 use std::marker::PhantomData;
 use std::path::Path;
@@ -16,6 +20,13 @@ pub struct Mesh<V: VertexLayout> {
     pub vbo: VBO,
     pub vao: VAO,
     pub indices_len: usize,
+    aabb: Aabb,
+    bounding_sphere: BoundingSphere,
+    // created on the first instanced draw
+    instance_vbo: OnceCell<VBO>,
+    // CPU copy for picking and collision, the BVH is built on the first ray cast
+    data: MeshData<V>,
+    bvh: OnceCell<Bvh>,
     _marker: PhantomData<V>
 }
 
@@ -35,11 +46,18 @@ impl<V: VertexLayout> Mesh<V> {
         
         V::setup_attributes(&vao);
 
+        let positions = vertices.iter().map(V::position);
+
         Self {
             ebo,
             vbo,
             vao,
             indices_len: indices.len(),
+            aabb: Aabb::from_points(positions.clone()),
+            bounding_sphere: BoundingSphere::from_points(positions),
+            instance_vbo: OnceCell::new(),
+            data: MeshData::new(vertices.to_vec(), indices.to_vec()),
+            bvh: OnceCell::new(),
             _marker: PhantomData
         }
     }
@@ -48,15 +66,80 @@ impl<V: VertexLayout> Mesh<V> {
         Mesh::new(mesh_data.vertices(), mesh_data.indices())
     }
     
+    pub fn data(&self) -> &MeshData<V> {
+        &self.data
+    }
+    
+    /// Mesh space bounds, see `RenderObject::world_aabb` for the transformed ones.
+    pub fn aabb(&self) -> &Aabb {
+        &self.aabb
+    }
+    
+    pub fn bounding_sphere(&self) -> &BoundingSphere {
+        &self.bounding_sphere
+    }
+    
     pub fn render(&self) {
+        self.draw(gl::TRIANGLES);
+    }
+    
+    /// Draws every triangle as a 3 vertex patch, for programs with tessellation stages.
+    pub fn render_patches(&self) {
+        unsafe {
+            gl::PatchParameteri(gl::PATCH_VERTICES, 3);
+        }
+        self.draw(gl::PATCHES);
+    }
+    
+    /// Draws the mesh once per instance with a single `DrawElementsInstanced`.
+    pub fn render_instanced(&self, instances: &[InstanceData]) {
+        self.draw_instanced(gl::TRIANGLES, instances);
+    }
+    
+    /// `render_instanced` with 3 vertex patches.
+    pub fn render_instanced_patches(&self, instances: &[InstanceData]) {
+        unsafe {
+            gl::PatchParameteri(gl::PATCH_VERTICES, 3);
+        }
+        self.draw_instanced(gl::PATCHES, instances);
+    }
+    
+    fn draw(&self, mode: u32) {
         self.vao.bind();
         let count = self.indices_len.try_into().expect("Too many indices");
         unsafe {
             gl::DrawElements(
-                gl::TRIANGLES,
+                mode,
+                count,
+                gl::UNSIGNED_INT,
+                null(),
+            );
+        }
+    }
+    
+    fn draw_instanced(&self, mode: u32, instances: &[InstanceData]) {
+        if instances.is_empty() {
+            return;
+        }
+        self.vao.bind();
+        let instance_vbo = self.instance_vbo.get_or_init(|| {
+            let vbo = VBO::new();
+            vbo.bind();
+            InstanceData::setup_attributes(&self.vao, V::ATTRIBUTE_COUNT);
+            vbo
+        });
+        instance_vbo.bind();
+        instance_vbo.buffer_data_with_usage(instances, BufferUsage::StreamDraw);
+        
+        let count = self.indices_len.try_into().expect("Too many indices");
+        let instance_count = instances.len().try_into().expect("Too many instances");
+        unsafe {
+            gl::DrawElementsInstanced(
+                mode,
                 count,
                 gl::UNSIGNED_INT,
                 null(),
+                instance_count,
             );
         }
     }
@@ -71,6 +154,12 @@ impl Mesh<Vertex> {
         Ok(Mesh::new(&vertices, &indices))
     }
     
+    /// Ray cast in mesh space against the retained CPU data.
+    pub fn raycast(&self, ray: &Ray) -> Option<MeshHit> {
+        self.bvh
+            .get_or_init(|| Bvh::build(&self.data))
+            .raycast(&self.data, ray)
+    }
 }
 
 fn load_from_obj<P>(path: P) -> Result<(Vec<Vertex>, Vec<u32>), String>
@@ -138,7 +227,7 @@ where
                             v: positions[v_idx as usize],
                             vn: normals[vn_idx as usize],
                             vt: uvs[vt_idx as usize],
-                            tangent: Vec3::zero(),
+                            tangent: Vec4::zero(),
                         };
                         
                         let new_index = faces.len() as u32;
@@ -171,7 +260,7 @@ where
                             v: positions[v_idx as usize],
                             vn: normals[vn_idx as usize],
                             vt: uvs[vt_idx as usize],
-                            tangent: Vec3::zero(),
+                            tangent: Vec4::zero(),
                         };
                         
                         let new_index = faces.len() as u32;
@@ -205,7 +294,9 @@ where
     Ok((faces, indices))
 }
 
-pub fn calculate_tangents(faces: &mut Vec<Vertex>, indices: &mut Vec<u32>) {
+pub fn calculate_tangents(faces: &mut [Vertex], indices: &mut Vec<u32>) {
+    let mut tangents = vec![Vec3::zero(); faces.len()];
+    let mut bitangents = vec![Vec3::zero(); faces.len()];
     for tri in indices.chunks_exact(3) {
         let i0 = tri[0] as usize;
         let i1 = tri[1] as usize;
@@ -236,17 +327,22 @@ pub fn calculate_tangents(faces: &mut Vec<Vertex>, indices: &mut Vec<u32>) {
         
         let r = 1.0 / denom;
         let tangent = (dp1 * duv2.y - dp2 * duv1.y) * r;
+        let bitangent = (dp2 * duv1.x - dp1 * duv2.x) * r;
         
-        faces[i0].tangent = faces[i0].tangent + tangent;
-        faces[i1].tangent = faces[i1].tangent + tangent;
-        faces[i2].tangent = faces[i2].tangent + tangent;
+        for i in [i0, i1, i2] {
+            tangents[i] = tangents[i] + tangent;
+            bitangents[i] = bitangents[i] + bitangent;
+        }
     }
     
-    for v in faces {
+    for (i, v) in faces.iter_mut().enumerate() {
         let n = normalize(v.vn);
         
         // Gram–Schmidt orthogonalization
-        v.tangent = normalize(v.tangent - n * dot(n, v.tangent));
+        let t = normalize(tangents[i] - n * dot(n, tangents[i]));
+        // mirrored uvs run the bitangent against cross(n, t)
+        let w = if dot(cross(n, t), bitangents[i]) < 0.0 { -1.0 } else { 1.0 };
+        v.tangent = Vec4::new(t.x, t.y, t.z, w);
     }
 }
 
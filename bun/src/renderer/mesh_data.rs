@@ -1,6 +1,6 @@
 use crate::renderer::vertex::{ScreenVertex, VertexLayout};
 use crate::{Mesh, Vertex};
-use glm::{dot, normalize, Vec2, Vec3};
+use glm::{dot, normalize, Vec2, Vec3, Vec4};
 use std::marker::PhantomData;
 use num_traits::Zero;
 use crate::renderer::mesh::calculate_tangents;
@@ -40,25 +40,25 @@ impl MeshData<Vertex> {
                 v: Vec3::new(-0.5, 0.0, 0.5),
                 vn: Vec3::new(0.0, 1.0, 0.0),
                 vt: Vec2::new(0.0, 1.0),
-                tangent: Vec3::zero(),
+                tangent: Vec4::zero(),
             },
             Vertex {
                 v: Vec3::new(-0.5, 0.0, -0.5),
                 vn: Vec3::new(0.0, 1.0, 0.0),
                 vt: Vec2::new(0.0, 1.0),
-                tangent: Vec3::zero(),
+                tangent: Vec4::zero(),
             },
             Vertex {
                 v: Vec3::new(0.5, 0.0, -0.5),
                 vn: Vec3::new(0.0, 1.0, 0.0),
                 vt: Vec2::new(1.0, 0.0),
-                tangent: Vec3::zero(),
+                tangent: Vec4::zero(),
             },
             Vertex {
                 v: Vec3::new(0.5, 0.0, 0.5),
                 vn: Vec3::new(0.0, 1.0, 0.0),
                 vt: Vec2::new(1.0, 1.0),
-                tangent: Vec3::zero(),
+                tangent: Vec4::zero(),
             },
         ];
         
@@ -85,7 +85,7 @@ impl MeshData<Vertex> {
                 
                 let u = 0.0 + x_advance;
                 let v = 1.0 - z_advance;
-                vertices.push(Vertex::new(Vec3::new(x, y, z), normal, Vec2::new(u, v), Vec3::zero()));
+                vertices.push(Vertex::new(Vec3::new(x, y, z), normal, Vec2::new(u, v), Vec4::zero()));
             }
         }
         
@@ -114,150 +114,150 @@ impl MeshData<Vertex> {
                 v: Vec3::new(-0.5, -0.5, 0.5),
                 vn: Vec3::new(0.0, 0.0, 1.0),
                 vt: Vec2::new(0.0, 0.0),
-                tangent: Vec3::zero(),
+                tangent: Vec4::zero(),
             },
             Vertex {
                 v: Vec3::new(0.5, -0.5, 0.5),
                 vn: Vec3::new(0.0, 0.0, 1.0),
                 vt: Vec2::new(1.0, 0.0),
-                tangent: Vec3::zero(),
+                tangent: Vec4::zero(),
             },
             Vertex {
                 v: Vec3::new(0.5, 0.5, 0.5),
                 vn: Vec3::new(0.0, 0.0, 1.0),
                 vt: Vec2::new(1.0, 1.0),
-                tangent: Vec3::zero(),
+                tangent: Vec4::zero(),
             },
             Vertex {
                 v: Vec3::new(-0.5, 0.5, 0.5),
                 vn: Vec3::new(0.0, 0.0, 1.0),
                 vt: Vec2::new(0.0, 1.0),
-                tangent: Vec3::zero(),
+                tangent: Vec4::zero(),
             },
             // Back face (-Z) - Green
             Vertex {
                 v: Vec3::new(0.5, -0.5, -0.5),
                 vn: Vec3::new(0.0, 0.0, -1.0),
                 vt: Vec2::new(0.0, 0.0),
-                tangent: Vec3::zero(),
+                tangent: Vec4::zero(),
             },
             Vertex {
                 v: Vec3::new(-0.5, -0.5, -0.5),
                 vn: Vec3::new(0.0, 0.0, -1.0),
                 vt: Vec2::new(1.0, 0.0),
-                tangent: Vec3::zero(),
+                tangent: Vec4::zero(),
             },
             Vertex {
                 v: Vec3::new(-0.5, 0.5, -0.5),
                 vn: Vec3::new(0.0, 0.0, -1.0),
                 vt: Vec2::new(1.0, 1.0),
-                tangent: Vec3::zero(),
+                tangent: Vec4::zero(),
             },
             Vertex {
                 v: Vec3::new(0.5, 0.5, -0.5),
                 vn: Vec3::new(0.0, 0.0, -1.0),
                 vt: Vec2::new(0.0, 1.0),
-                tangent: Vec3::zero(),
+                tangent: Vec4::zero(),
             },
             // Top face (+Y) - Blue
             Vertex {
                 v: Vec3::new(-0.5, 0.5, 0.5),
                 vn: Vec3::new(0.0, 1.0, 0.0),
                 vt: Vec2::new(0.0, 0.0),
-                tangent: Vec3::zero(),
+                tangent: Vec4::zero(),
             },
             Vertex {
                 v: Vec3::new(0.5, 0.5, 0.5),
                 vn: Vec3::new(0.0, 1.0, 0.0),
                 vt: Vec2::new(1.0, 0.0),
-                tangent: Vec3::zero(),
+                tangent: Vec4::zero(),
             },
             Vertex {
                 v: Vec3::new(0.5, 0.5, -0.5),
                 vn: Vec3::new(0.0, 1.0, 0.0),
                 vt: Vec2::new(1.0, 1.0),
-                tangent: Vec3::zero(),
+                tangent: Vec4::zero(),
             },
             Vertex {
                 v: Vec3::new(-0.5, 0.5, -0.5),
                 vn: Vec3::new(0.0, 1.0, 0.0),
                 vt: Vec2::new(0.0, 1.0),
-                tangent: Vec3::zero(),
+                tangent: Vec4::zero(),
             },
             // Bottom face (-Y) - Yellow
             Vertex {
                 v: Vec3::new(-0.5, -0.5, -0.5),
                 vn: Vec3::new(0.0, -1.0, 0.0),
                 vt: Vec2::new(0.0, 0.0),
-                tangent: Vec3::zero(),
+                tangent: Vec4::zero(),
             },
             Vertex {
                 v: Vec3::new(0.5, -0.5, -0.5),
                 vn: Vec3::new(0.0, -1.0, 0.0),
                 vt: Vec2::new(1.0, 0.0),
-                tangent: Vec3::zero(),
+                tangent: Vec4::zero(),
             },
             Vertex {
                 v: Vec3::new(0.5, -0.5, 0.5),
                 vn: Vec3::new(0.0, -1.0, 0.0),
                 vt: Vec2::new(1.0, 1.0),
-                tangent: Vec3::zero(),
+                tangent: Vec4::zero(),
             },
             Vertex {
                 v: Vec3::new(-0.5, -0.5, 0.5),
                 vn: Vec3::new(0.0, -1.0, 0.0),
                 vt: Vec2::new(0.0, 1.0),
-                tangent: Vec3::zero(),
+                tangent: Vec4::zero(),
             },
             // Right face (+X) - Magenta
             Vertex {
                 v: Vec3::new(0.5, -0.5, 0.5),
                 vn: Vec3::new(1.0, 0.0, 0.0),
                 vt: Vec2::new(0.0, 0.0),
-                tangent: Vec3::zero(),
+                tangent: Vec4::zero(),
             },
             Vertex {
                 v: Vec3::new(0.5, -0.5, -0.5),
                 vn: Vec3::new(1.0, 0.0, 0.0),
                 vt: Vec2::new(1.0, 0.0),
-                tangent: Vec3::zero(),
+                tangent: Vec4::zero(),
             },
             Vertex {
                 v: Vec3::new(0.5, 0.5, -0.5),
                 vn: Vec3::new(1.0, 0.0, 0.0),
                 vt: Vec2::new(1.0, 1.0),
-                tangent: Vec3::zero(),
+                tangent: Vec4::zero(),
             },
             Vertex {
                 v: Vec3::new(0.5, 0.5, 0.5),
                 vn: Vec3::new(1.0, 0.0, 0.0),
                 vt: Vec2::new(0.0, 1.0),
-                tangent: Vec3::zero(),
+                tangent: Vec4::zero(),
             },
             // Left face (-X) - Cyan
             Vertex {
                 v: Vec3::new(-0.5, -0.5, -0.5),
                 vn: Vec3::new(-1.0, 0.0, 0.0),
                 vt: Vec2::new(0.0, 0.0),
-                tangent: Vec3::zero(),
+                tangent: Vec4::zero(),
             },
             Vertex {
                 v: Vec3::new(-0.5, -0.5, 0.5),
                 vn: Vec3::new(-1.0, 0.0, 0.0),
                 vt: Vec2::new(1.0, 0.0),
-                tangent: Vec3::zero(),
+                tangent: Vec4::zero(),
             },
             Vertex {
                 v: Vec3::new(-0.5, 0.5, 0.5),
                 vn: Vec3::new(-1.0, 0.0, 0.0),
                 vt: Vec2::new(1.0, 1.0),
-                tangent: Vec3::zero(),
+                tangent: Vec4::zero(),
             },
             Vertex {
                 v: Vec3::new(-0.5, 0.5, -0.5),
                 vn: Vec3::new(-1.0, 0.0, 0.0),
                 vt: Vec2::new(0.0, 1.0),
-                tangent: Vec3::zero(),
+                tangent: Vec4::zero(),
             },
         ];
         
@@ -1,13 +1,29 @@
+pub mod atmosphere;
+pub mod bloom;
+pub mod bounds;
 pub mod buffer;
+pub mod bvh;
 pub mod camera;
+pub mod deferred;
+pub mod environment;
 pub mod frame_buffer;
+#[cfg(feature = "gltf")]
+pub mod gltf;
+pub mod light;
 pub mod material;
 pub mod mesh;
 pub mod mesh_data;
+pub mod post_process;
+pub mod ray;
 pub mod render_object;
 pub mod renderer;
 pub mod shader;
+pub mod shadow;
+pub mod sky;
+pub mod ssao;
+pub mod terrain;
 pub mod texture;
+pub mod tonemap;
 pub mod transform;
 pub mod vertex;
 pub mod vertex_array;
@@ -0,0 +1,306 @@
+use crate::Shader;
+use gl::types::{GLint, GLsizei};
+use glm::{Vec2, Vec3, Vec4};
+use std::ptr::null;
+
+/// Value of a pass parameter, set as the uniform of the same name before the pass runs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PostProcessParam {
+    Float(f32),
+    Int(i32),
+    Vec2(Vec2),
+    Vec3(Vec3),
+    Vec4(Vec4),
+}
+
+impl From<f32> for PostProcessParam {
+    fn from(value: f32) -> Self {
+        PostProcessParam::Float(value)
+    }
+}
+
+impl From<i32> for PostProcessParam {
+    fn from(value: i32) -> Self {
+        PostProcessParam::Int(value)
+    }
+}
+
+impl From<bool> for PostProcessParam {
+    fn from(value: bool) -> Self {
+        PostProcessParam::Int(value as i32)
+    }
+}
+
+impl From<Vec2> for PostProcessParam {
+    fn from(value: Vec2) -> Self {
+        PostProcessParam::Vec2(value)
+    }
+}
+
+impl From<Vec3> for PostProcessParam {
+    fn from(value: Vec3) -> Self {
+        PostProcessParam::Vec3(value)
+    }
+}
+
+impl From<Vec4> for PostProcessParam {
+    fn from(value: Vec4) -> Self {
+        PostProcessParam::Vec4(value)
+    }
+}
+
+/// One full-screen effect of a `PostProcessStack`.
+///
+/// The shader gets the previous pass (or the HDR frame) as `source` on texture unit 0, and
+/// `texel_size` and `time` as uniforms. `fullscreen.vert` provides `frag_uv`.
+pub struct PostProcessPass {
+    name: String,
+    shader: Shader,
+    params: Vec<(String, PostProcessParam)>,
+    enabled: bool,
+}
+
+impl PostProcessPass {
+    pub fn new(name: impl Into<String>, shader: Shader) -> Self {
+        Self { name: name.into(), shader, params: vec![], enabled: true }
+    }
+
+    /// Pairs the fragment shader with the engine's fullscreen triangle vertex shader.
+    pub fn from_fragment_source(name: impl Into<String>, fragment_src: String) -> Result<Self, String> {
+        let shader = Shader::from_source(include_str!("../res/shaders/fullscreen.vert").to_string(), fragment_src)?;
+        Ok(Self::new(name, shader))
+    }
+
+    pub fn with_param(mut self, name: &str, value: impl Into<PostProcessParam>) -> Self {
+        self.set_param(name, value);
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn shader(&self) -> &Shader {
+        &self.shader
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn param(&self, name: &str) -> Option<PostProcessParam> {
+        self.params.iter().find(|(n, _)| n == name).map(|(_, value)| *value)
+    }
+
+    pub fn params(&self) -> &[(String, PostProcessParam)] {
+        &self.params
+    }
+
+    pub fn set_param(&mut self, name: &str, value: impl Into<PostProcessParam>) {
+        let value = value.into();
+        match self.params.iter_mut().find(|(n, _)| n == name) {
+            Some((_, param)) => *param = value,
+            None => self.params.push((name.to_string(), value)),
+        }
+    }
+
+    fn apply_params(&self) {
+        let shader = &self.shader;
+        for (name, value) in &self.params {
+            let Some(loc) = shader.get_uniform_location(name) else {
+                continue;
+            };
+            match *value {
+                PostProcessParam::Float(v) => shader.set_uniform(loc, v),
+                PostProcessParam::Int(v) => shader.set_uniform(loc, v),
+                PostProcessParam::Vec2(v) => shader.set_uniform(loc, v),
+                PostProcessParam::Vec3(v) => shader.set_uniform(loc, v),
+                PostProcessParam::Vec4(v) => shader.set_uniform(loc, v),
+            }
+        }
+    }
+}
+
+/// Ordered full-screen passes run on the HDR frame before bloom and tonemapping, reachable
+/// with `Engine::post_process`. Passes render alternately into two half float targets.
+pub struct PostProcessStack {
+    passes: Vec<PostProcessPass>,
+    // created with the first frame that has an enabled pass
+    targets: Option<PingPongTargets>,
+}
+
+impl PostProcessStack {
+    pub fn new() -> Self {
+        Self { passes: vec![], targets: None }
+    }
+
+    /// Appends the pass, it runs after all passes added before.
+    pub fn push(&mut self, pass: PostProcessPass) {
+        self.passes.push(pass);
+    }
+
+    /// Inserts the pass at `index`, clamped to the end of the stack.
+    pub fn insert(&mut self, index: usize, pass: PostProcessPass) {
+        self.passes.insert(index.min(self.passes.len()), pass);
+    }
+
+    /// Removes the first pass called `name`.
+    pub fn remove(&mut self, name: &str) -> Option<PostProcessPass> {
+        let index = self.passes.iter().position(|p| p.name == name)?;
+        Some(self.passes.remove(index))
+    }
+
+    pub fn passes(&self) -> &[PostProcessPass] {
+        &self.passes
+    }
+
+    pub fn pass(&self, name: &str) -> Option<&PostProcessPass> {
+        self.passes.iter().find(|p| p.name == name)
+    }
+
+    pub fn pass_mut(&mut self, name: &str) -> Option<&mut PostProcessPass> {
+        self.passes.iter_mut().find(|p| p.name == name)
+    }
+
+    /// Returns false if there is no pass called `name`.
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> bool {
+        match self.pass_mut(name) {
+            Some(pass) => {
+                pass.set_enabled(enabled);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Runs the enabled passes on `source` and returns the texture holding the result,
+    /// `source` itself if nothing ran. Leaves the framebuffer unbound and restores the viewport.
+    pub(crate) fn render(&mut self, source: u32, width: u32, height: u32, time: f32) -> u32 {
+        if !self.passes.iter().any(|p| p.enabled) || width == 0 || height == 0 {
+            return source;
+        }
+        let targets = match self.targets.take() {
+            Some(mut targets) => targets.resize(width, height).map(|_| targets),
+            None => PingPongTargets::new(width, height),
+        };
+        let targets = match targets {
+            Ok(targets) => self.targets.insert(targets),
+            Err(e) => {
+                eprintln!("Skipping post processing: {}", e);
+                return source;
+            }
+        };
+
+        let mut previous_viewport = [0; 4];
+        unsafe {
+            gl::GetIntegerv(gl::VIEWPORT, previous_viewport.as_mut_ptr());
+            gl::Disable(gl::DEPTH_TEST);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, targets.fbo);
+            gl::BindVertexArray(targets.vao);
+            gl::Viewport(0, 0, width as GLsizei, height as GLsizei);
+            gl::ActiveTexture(gl::TEXTURE0);
+        }
+
+        let texel_size = Vec2::new(1.0 / width as f32, 1.0 / height as f32);
+        let mut input = source;
+        let mut target = 0;
+        for pass in self.passes.iter().filter(|p| p.enabled) {
+            let shader = &pass.shader;
+            shader.bind();
+            if let Some(loc) = shader.get_uniform_location("texel_size") {
+                shader.set_uniform(loc, texel_size);
+            }
+            if let Some(loc) = shader.get_uniform_location("time") {
+                shader.set_uniform(loc, time);
+            }
+            pass.apply_params();
+            unsafe {
+                gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::TEXTURE_2D, targets.textures[target], 0);
+                gl::BindTexture(gl::TEXTURE_2D, input);
+                gl::DrawArrays(gl::TRIANGLES, 0, 3);
+            }
+            input = targets.textures[target];
+            target = 1 - target;
+        }
+
+        unsafe {
+            gl::BindVertexArray(0);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            gl::Enable(gl::DEPTH_TEST);
+            gl::Viewport(previous_viewport[0], previous_viewport[1], previous_viewport[2], previous_viewport[3]);
+        }
+        input
+    }
+}
+
+impl Default for PostProcessStack {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct PingPongTargets {
+    fbo: u32,
+    vao: u32,
+    textures: [u32; 2],
+    width: u32,
+    height: u32,
+}
+
+impl PingPongTargets {
+    fn new(width: u32, height: u32) -> Result<Self, String> {
+        let mut fbo = 0;
+        let mut vao = 0;
+        let mut textures = [0; 2];
+        unsafe {
+            gl::GenFramebuffers(1, &mut fbo);
+            gl::GenVertexArrays(1, &mut vao);
+            gl::GenTextures(2, textures.as_mut_ptr());
+        }
+        let mut targets = Self { fbo, vao, textures, width: 0, height: 0 };
+        targets.resize(width, height)?;
+        Ok(targets)
+    }
+
+    fn resize(&mut self, width: u32, height: u32) -> Result<(), String> {
+        if self.width == width && self.height == height {
+            return Ok(());
+        }
+        for texture in self.textures {
+            unsafe {
+                gl::BindTexture(gl::TEXTURE_2D, texture);
+                gl::TexImage2D(gl::TEXTURE_2D, 0, gl::RGBA16F as GLint, width as GLsizei, height as GLsizei, 0, gl::RGBA, gl::FLOAT, null());
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as GLint);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as GLint);
+            }
+        }
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::TEXTURE_2D, self.textures[0], 0);
+            let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            if status != gl::FRAMEBUFFER_COMPLETE {
+                return Err(format!("Could not create post process framebuffer: {:#X?}", status));
+            }
+        }
+        self.width = width;
+        self.height = height;
+        Ok(())
+    }
+}
+
+impl Drop for PingPongTargets {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteTextures(2, self.textures.as_ptr());
+            gl::DeleteFramebuffers(1, &self.fbo);
+            gl::DeleteVertexArrays(1, &self.vao);
+        }
+    }
+}
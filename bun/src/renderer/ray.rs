@@ -0,0 +1,139 @@
+use crate::renderer::render_object::RenderObject;
+use glm::{cross, dot, normalize, GenSquareMat, Mat3, Vec3, Vec4};
+
+#[derive(Debug, Clone, Copy)]
+pub struct Ray {
+    pub origin: Vec3,
+    pub direction: Vec3,
+}
+
+impl Ray {
+    pub fn new(origin: Vec3, direction: Vec3) -> Self {
+        Self { origin, direction: normalize(direction) }
+    }
+
+    pub fn at(&self, t: f32) -> Vec3 {
+        self.origin + self.direction * t
+    }
+
+    /// Möller–Trumbore. Returns the distance along the ray and the barycentrics of `p1` and `p2`.
+    /// Both triangle sides are hit, the renderer doesn't expose per-material culling.
+    pub fn intersect_triangle(&self, p0: Vec3, p1: Vec3, p2: Vec3) -> Option<(f32, f32, f32)> {
+        const EPSILON: f32 = 1e-7;
+        let e1 = p1 - p0;
+        let e2 = p2 - p0;
+        let p = cross(self.direction, e2);
+        let det = dot(e1, p);
+        if det.abs() < EPSILON {
+            return None;
+        }
+        let inv_det = 1.0 / det;
+        let s = self.origin - p0;
+        let u = dot(s, p) * inv_det;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let q = cross(s, e1);
+        let v = dot(self.direction, q) * inv_det;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+        let t = dot(e2, q) * inv_det;
+        if t < 0.0 {
+            return None;
+        }
+        Some((t, u, v))
+    }
+
+    /// Slab test, returns the entry distance (0 if the origin is inside).
+    pub fn intersect_aabb(&self, min: Vec3, max: Vec3) -> Option<f32> {
+        let mut t_min = 0.0f32;
+        let mut t_max = f32::INFINITY;
+        for axis in 0..3 {
+            let inv_d = 1.0 / self.direction[axis];
+            let mut t0 = (min[axis] - self.origin[axis]) * inv_d;
+            let mut t1 = (max[axis] - self.origin[axis]) * inv_d;
+            if inv_d < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            // NaN (origin on a slab with a parallel ray) keeps the old bound
+            t_min = t_min.max(t0);
+            t_max = t_max.min(t1);
+            if t_max < t_min {
+                return None;
+            }
+        }
+        Some(t_min)
+    }
+}
+
+/// Hit on a mesh, everything is in the space of the mesh vertices.
+#[derive(Debug, Clone, Copy)]
+pub struct MeshHit {
+    pub distance: f32,
+    pub point: Vec3,
+    /// Interpolated vertex normal.
+    pub normal: Vec3,
+    /// Weights of the three triangle corners.
+    pub barycentric: Vec3,
+    pub triangle: usize,
+}
+
+/// Hit on a `RenderObject`, point and normal are in world space.
+#[derive(Debug, Clone, Copy)]
+pub struct PickHit {
+    /// Index into the objects passed to `pick`.
+    pub index: usize,
+    pub distance: f32,
+    pub point: Vec3,
+    pub normal: Vec3,
+    pub barycentric: Vec3,
+    pub triangle: usize,
+}
+
+/// Finds the closest object hit by a world space ray.
+pub fn pick<'a, I>(objects: I, ray: &Ray) -> Option<PickHit>
+where
+    I: IntoIterator<Item = &'a RenderObject>,
+{
+    let mut closest: Option<PickHit> = None;
+    for (index, object) in objects.into_iter().enumerate() {
+        let model = object.transform().model_matrix();
+        let inv_model = match model.inverse() {
+            Some(m) => m,
+            None => continue,
+        };
+
+        let local_origin = inv_model * Vec4::new(ray.origin.x, ray.origin.y, ray.origin.z, 1.0);
+        let local_dir = inv_model * Vec4::new(ray.direction.x, ray.direction.y, ray.direction.z, 0.0);
+        let local_ray = Ray::new(local_origin.truncate(3), local_dir.truncate(3));
+
+        let hit = match object.mesh().raycast(&local_ray) {
+            Some(hit) => hit,
+            None => continue,
+        };
+
+        // the local ray is renormalized, so the distance has to be measured again in world space
+        let point = (model * Vec4::new(hit.point.x, hit.point.y, hit.point.z, 1.0)).truncate(3);
+        let distance = glm::length(point - ray.origin);
+        if closest.is_some_and(|c| c.distance <= distance) {
+            continue;
+        }
+
+        let model3 = Mat3::new(model[0].truncate(3), model[1].truncate(3), model[2].truncate(3));
+        let normal_mat = match model3.inverse() {
+            Some(m) => glm::transpose(&m),
+            None => model3,
+        };
+
+        closest = Some(PickHit {
+            index,
+            distance,
+            point,
+            normal: normalize(normal_mat * hit.normal),
+            barycentric: hit.barycentric,
+            triangle: hit.triangle,
+        });
+    }
+    closest
+}
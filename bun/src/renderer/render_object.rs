@@ -1,4 +1,5 @@
 use std::cell::RefCell;
+use crate::renderer::bounds::{Aabb, BoundingSphere};
 use crate::renderer::material::Material;
 use crate::renderer::transform::Transform;
 use crate::{Mesh, Vertex};
@@ -7,7 +8,9 @@ use std::sync::Arc;
 pub struct RenderObject {
     transform: Transform,
     mesh: Arc<Mesh<Vertex>>,
-    material: Arc<Material>
+    material: Arc<Material>,
+    cast_shadows: bool,
+    receive_shadows: bool,
 }
 
 impl RenderObject {
@@ -15,7 +18,9 @@ impl RenderObject {
         Self {
             transform,
             mesh,
-            material
+            material,
+            cast_shadows: true,
+            receive_shadows: true,
         }
     }
     
@@ -34,4 +39,36 @@ impl RenderObject {
     pub fn material(&self) -> &Material {
         self.material.as_ref()
     }
+    
+    pub fn cast_shadows(&self) -> bool {
+        self.cast_shadows
+    }
+    
+    pub fn set_cast_shadows(&mut self, cast_shadows: bool) {
+        self.cast_shadows = cast_shadows;
+    }
+    
+    pub fn receive_shadows(&self) -> bool {
+        self.receive_shadows
+    }
+    
+    pub fn set_receive_shadows(&mut self, receive_shadows: bool) {
+        self.receive_shadows = receive_shadows;
+    }
+    
+    pub(crate) fn shared_mesh(&self) -> &Arc<Mesh<Vertex>> {
+        &self.mesh
+    }
+    
+    pub(crate) fn shared_material(&self) -> &Arc<Material> {
+        &self.material
+    }
+    
+    pub fn world_aabb(&self) -> Aabb {
+        self.mesh.aabb().transformed(&self.transform.model_matrix())
+    }
+    
+    pub fn world_bounding_sphere(&self) -> BoundingSphere {
+        self.mesh.bounding_sphere().transformed(&self.transform.model_matrix())
+    }
 }
\ No newline at end of file
@@ -1,22 +1,325 @@
 use std::sync::Arc;
+use crate::renderer::buffer::{BufferUsage, SSBO};
+use crate::renderer::environment::Environment;
+use crate::renderer::light::{GpuLight, Light, LightKind};
+use crate::renderer::material::{BlendMode, Material};
 use crate::renderer::render_object::RenderObject;
-use crate::renderer::transform::Transform;
-use crate::{Camera, Mesh, Shader};
-use glm::Vec4;
+use crate::renderer::bloom::BloomSettings;
+use crate::renderer::deferred::{DeferredPass, RenderPath};
+use crate::renderer::tonemap::TonemapSettings;
+use crate::renderer::bounds::BoundingSphere;
+use crate::renderer::sky::{Sky, SkyPass};
+use crate::renderer::ssao::{Ssao, SsaoSettings};
+use crate::renderer::light::PointShadowSettings;
+use crate::renderer::shadow::{
+    compute_cascades, cube_face_view_projs, select_point_shadows, Cascade, CubeShadowMap, ShadowMap, ShadowSettings,
+    MAX_CASCADES, MAX_POINT_SHADOWS,
+};
+use crate::{Camera, Mesh, Shader, Vertex};
+use glm::{dot, GenSquareMat, Mat4, Vec2, Vec3, Vec4};
+use num_traits::One;
 use crate::renderer::mesh_data::MeshData;
-use crate::renderer::vertex::ScreenVertex;
+use crate::renderer::vertex::{InstanceData, ScreenVertex};
+
+/// Object counts of one frame.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RenderStats {
+    pub drawn: u32,
+    /// Objects skipped because their bounds are outside the camera frustum.
+    pub culled: u32,
+    /// GL state changes while flushing the queue, lower is better.
+    pub shader_binds: u32,
+    pub material_binds: u32,
+    pub draw_calls: u32,
+    /// Objects drawn as part of an instanced draw call.
+    pub instanced: u32,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+struct CameraState {
+    projection: Mat4,
+    view: Mat4,
+    position: Vec3,
+}
+
+/// One recorded `render` call, everything needed to draw it later.
+#[derive(Clone)]
+struct DrawCommand {
+    mesh: Arc<Mesh<Vertex>>,
+    material: Arc<Material>,
+    model: Mat4,
+    camera: usize,
+    /// View space distance along the camera forward axis.
+    depth: f32,
+    /// World space bounds, used to skip casters outside a light's range.
+    bounds: BoundingSphere,
+    cast_shadows: bool,
+    receive_shadows: bool,
+}
+
+struct ShadowCaster {
+    mesh: Arc<Mesh<Vertex>>,
+    model: Mat4,
+    bounds: BoundingSphere,
+}
+
+impl DrawCommand {
+    fn shader_id(&self) -> u32 {
+        self.material.shader.id()
+    }
+
+    fn material_key(&self) -> usize {
+        Arc::as_ptr(&self.material) as usize
+    }
+
+    fn mesh_key(&self) -> usize {
+        Arc::as_ptr(&self.mesh) as usize
+    }
+
+    fn can_batch_with(&self, other: &DrawCommand) -> bool {
+        self.mesh_key() == other.mesh_key()
+            && self.material_key() == other.material_key()
+            && self.camera == other.camera
+            && self.receive_shadows == other.receive_shadows
+    }
+}
+
+/// Binding point of the `LightBuffer` SSBO in the shaders.
+const LIGHT_BUFFER_BINDING: u32 = 3;
+/// Texture unit of the cascaded shadow map, units below are used by materials.
+const SHADOW_MAP_UNIT: u32 = 8;
+/// First texture unit of the point light cube maps, one per slot up to `MAX_POINT_SHADOWS`.
+const POINT_SHADOW_MAP_UNIT: u32 = 9;
+/// Texture unit of the prefiltered environment cube map.
+const ENVIRONMENT_UNIT: u32 = 13;
+/// Texture unit of the split-sum BRDF lookup table.
+const BRDF_LUT_UNIT: u32 = 14;
+/// Texture unit of the blurred ambient occlusion.
+const SSAO_UNIT: u32 = 15;
+/// Texture unit of the splat layer albedo array, bound by the material.
+pub(crate) const SPLAT_ALBEDO_UNIT: u32 = 16;
+/// Texture unit of the splat layer normal array, bound by the material.
+pub(crate) const SPLAT_NORMAL_UNIT: u32 = 17;
+
+/// Runs of at least this many identical mesh + material draws become one instanced draw.
+const MIN_INSTANCE_BATCH: usize = 2;
 
 pub struct Renderer {
-    current_shader: Option<u32>
+    current_shader: Option<u32>,
+    render_path: RenderPath,
+    // G-buffer, created when the deferred path is selected
+    deferred: Option<DeferredPass>,
+    frustum_culling: bool,
+    instancing: bool,
+    // counted during the current frame, copied to `stats` by `end_frame`
+    frame_stats: RenderStats,
+    stats: RenderStats,
+
+    cameras: Vec<CameraState>,
+    opaque: Vec<DrawCommand>,
+    transparent: Vec<DrawCommand>,
+    // scratch buffer for the instanced draws of `flush`
+    instances: Vec<InstanceData>,
+
+    lights: Vec<Light>,
+    light_ssbo: SSBO,
+
+    shadows_enabled: bool,
+    shadow_settings: ShadowSettings,
+    // created on the first frame with a shadow casting light
+    shadow_map: Option<ShadowMap>,
+    cascades: Vec<Cascade>,
+    // index into `lights` of the directional light the cascades belong to
+    shadow_light: Option<usize>,
+    // outside the camera frustum, but they might still throw a shadow into it
+    shadow_only: Vec<DrawCommand>,
+
+    point_shadow_budget: usize,
+    point_shadow_shader: Option<Shader>,
+    // one per slot, reused between frames
+    point_shadow_maps: Vec<CubeShadowMap>,
+    // light index and settings of every cube map rendered this frame, by slot
+    point_shadows: Vec<(usize, PointShadowSettings)>,
+
+    // image based ambient light, the shaders fall back to a flat sky without one
+    environment: Option<Arc<Environment>>,
+
+    sky: Option<Sky>,
+    // created with the first sky
+    sky_pass: Option<SkyPass>,
+
+    ssao_settings: SsaoSettings,
+    // created with the first frame that has SSAO enabled
+    ssao: Option<Ssao>,
+    // whether the AO texture belongs to this frame
+    ssao_rendered: bool,
+
+    // applied by the engine after `end_frame`, on the HDR frame
+    bloom_settings: BloomSettings,
+    tonemap_settings: TonemapSettings,
 }
 
 impl Renderer {
     pub fn new() -> Self {
         Self {
-            current_shader: None
+            current_shader: None,
+            render_path: RenderPath::Forward,
+            deferred: None,
+            frustum_culling: true,
+            instancing: true,
+            frame_stats: RenderStats::default(),
+            stats: RenderStats::default(),
+            cameras: vec![],
+            opaque: vec![],
+            transparent: vec![],
+            instances: vec![],
+            lights: vec![],
+            light_ssbo: SSBO::new(),
+            shadows_enabled: true,
+            shadow_settings: ShadowSettings::default(),
+            shadow_map: None,
+            cascades: vec![],
+            shadow_light: None,
+            shadow_only: vec![],
+            point_shadow_budget: 2,
+            point_shadow_shader: None,
+            point_shadow_maps: vec![],
+            point_shadows: vec![],
+            environment: None,
+            sky: None,
+            sky_pass: None,
+            ssao_settings: SsaoSettings::default(),
+            ssao: None,
+            ssao_rendered: false,
+            bloom_settings: BloomSettings::default(),
+            tonemap_settings: TonemapSettings::default(),
         }
     }
-    
+
+    pub fn render_path(&self) -> RenderPath {
+        self.render_path
+    }
+
+    /// Switching to `RenderPath::Deferred` creates the G-buffer, if that fails the render path
+    /// stays as it was and the error is returned.
+    pub fn set_render_path(&mut self, path: RenderPath) -> Result<(), String> {
+        if path == RenderPath::Deferred && self.deferred.is_none() {
+            // sized to the viewport with the first deferred frame
+            self.deferred = Some(DeferredPass::new(1, 1)?);
+        }
+        self.render_path = path;
+        Ok(())
+    }
+
+    pub fn frustum_culling(&self) -> bool {
+        self.frustum_culling
+    }
+
+    pub fn set_frustum_culling(&mut self, enabled: bool) {
+        self.frustum_culling = enabled;
+    }
+
+    pub fn instancing(&self) -> bool {
+        self.instancing
+    }
+
+    /// Whether repeated opaque mesh + material pairs are merged into instanced draws.
+    pub fn set_instancing(&mut self, enabled: bool) {
+        self.instancing = enabled;
+    }
+
+    pub fn shadows_enabled(&self) -> bool {
+        self.shadows_enabled
+    }
+
+    /// Cascaded shadows from the first directional light.
+    pub fn set_shadows_enabled(&mut self, enabled: bool) {
+        self.shadows_enabled = enabled;
+    }
+
+    pub fn shadow_settings(&self) -> &ShadowSettings {
+        &self.shadow_settings
+    }
+
+    pub fn set_shadow_settings(&mut self, settings: ShadowSettings) {
+        self.shadow_settings = settings;
+    }
+
+    pub fn point_shadow_budget(&self) -> usize {
+        self.point_shadow_budget
+    }
+
+    /// How many point and spot lights get a cube shadow map each frame, at most `MAX_POINT_SHADOWS`.
+    pub fn set_point_shadow_budget(&mut self, budget: usize) {
+        self.point_shadow_budget = budget.min(MAX_POINT_SHADOWS);
+    }
+
+    pub fn environment(&self) -> Option<&Arc<Environment>> {
+        self.environment.as_ref()
+    }
+
+    /// Ambient lighting for all following frames, `None` goes back to the flat fallback.
+    pub fn set_environment(&mut self, environment: Option<Arc<Environment>>) {
+        self.environment = environment;
+    }
+
+    pub fn sky(&self) -> Option<&Sky> {
+        self.sky.as_ref()
+    }
+
+    pub fn sky_mut(&mut self) -> Option<&mut Sky> {
+        self.sky.as_mut()
+    }
+
+    /// Background for all following frames, drawn from the first camera of the frame.
+    /// `None` leaves the clear color of `begin_frame`.
+    pub fn set_sky(&mut self, sky: Option<Sky>) {
+        self.sky = sky;
+    }
+
+    pub fn ssao_settings(&self) -> &SsaoSettings {
+        &self.ssao_settings
+    }
+
+    pub fn set_ssao_settings(&mut self, settings: SsaoSettings) {
+        self.ssao_settings = settings;
+    }
+
+    /// Texture the engine shows instead of the frame, the AO buffer with `SsaoSettings::debug_view`.
+    pub fn debug_texture(&self) -> Option<u32> {
+        match &self.ssao {
+            Some(ssao) if self.ssao_settings.debug_view && self.ssao_rendered => Some(ssao.texture_id()),
+            _ => None,
+        }
+    }
+
+    pub fn bloom_settings(&self) -> &BloomSettings {
+        &self.bloom_settings
+    }
+
+    pub fn set_bloom_settings(&mut self, settings: BloomSettings) {
+        self.bloom_settings = settings;
+    }
+
+    pub fn tonemap_settings(&self) -> &TonemapSettings {
+        &self.tonemap_settings
+    }
+
+    /// Exposure, eye adaptation and tonemapper used by the engine to resolve the HDR frame.
+    pub fn set_tonemap_settings(&mut self, settings: TonemapSettings) {
+        self.tonemap_settings = settings;
+    }
+
+    /// Cascades used in the last frame, empty if nothing cast shadows.
+    pub fn cascades(&self) -> &[Cascade] {
+        &self.cascades
+    }
+
+    /// Stats of the last frame finished with `end_frame`.
+    pub fn stats(&self) -> RenderStats {
+        self.stats
+    }
+
     pub fn begin_frame(&mut self, clear_color: Vec4) {
         unsafe {
             gl::Enable(gl::DEPTH_TEST);
@@ -24,60 +327,688 @@ impl Renderer {
             gl::ClearColor(clear_color.x, clear_color.y, clear_color.z, clear_color.w);
             gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
         }
+        self.frame_stats = RenderStats::default();
+        self.cameras.clear();
+        self.opaque.clear();
+        self.transparent.clear();
+        self.shadow_only.clear();
+        self.lights.clear();
+    }
+
+    /// Adds a light for the current frame, lights have to be added again every frame.
+    pub fn add_light(&mut self, light: Light) {
+        self.lights.push(light);
     }
-    
+
+    pub fn lights(&self) -> &[Light] {
+        &self.lights
+    }
+
+    /// Queues the object, nothing is drawn before `end_frame`.
     pub fn render(
         &mut self,
         object: &RenderObject,
         camera: &Camera
     ) {
-        let material = object.material();
-        
-        let shader_id = material.shader.id();
-        if self.current_shader != Some(shader_id) {
-            material.shader.bind();
-            self.current_shader = Some(material.shader.id());
-        }
-        
-        self.set_camera_uniforms(camera, material.shader.as_ref());
-        self.set_model_uniforms(&object.transform(), material.shader.as_ref());
-        
-        
-        material.apply();
-        
-        object.mesh().render();
-    }
-    
+        self.submit(
+            object.shared_mesh(),
+            object.shared_material(),
+            object.transform().model_matrix(),
+            camera,
+            (object.cast_shadows(), object.receive_shadows()),
+        );
+    }
+
+    /// Queues one draw per model matrix. They end up in a single instanced draw if
+    /// instancing is enabled and the material is opaque.
+    pub fn render_instanced(
+        &mut self,
+        mesh: &Arc<Mesh<Vertex>>,
+        material: &Arc<Material>,
+        models: &[Mat4],
+        camera: &Camera
+    ) {
+        for model in models {
+            self.submit(mesh, material, *model, camera, (true, true));
+        }
+    }
+
+    /// `shadows` is (cast, receive).
+    pub(crate) fn submit(&mut self, mesh: &Arc<Mesh<Vertex>>, material: &Arc<Material>, model: Mat4, camera: &Camera, shadows: (bool, bool)) {
+        let sphere = mesh.bounding_sphere().transformed(&model);
+        let visible = !self.frustum_culling || {
+            let frustum = camera.frustum();
+            // the sphere test is cheaper and rejects most objects, the box is tighter for long meshes
+            frustum.intersects_sphere(&sphere) && frustum.intersects_aabb(&mesh.aabb().transformed(&model))
+        };
+        if visible {
+            self.frame_stats.drawn += 1;
+        } else {
+            self.frame_stats.culled += 1;
+            if !shadows.0 || material.blend_mode.is_transparent() {
+                return;
+            }
+        }
+
+        let camera_state = CameraState {
+            projection: camera.projection(),
+            view: camera.view(),
+            position: camera.position(),
+        };
+        if self.cameras.last() != Some(&camera_state) {
+            self.cameras.push(camera_state);
+        }
+
+        let command = DrawCommand {
+            mesh: mesh.clone(),
+            material: material.clone(),
+            model,
+            camera: self.cameras.len() - 1,
+            depth: dot(sphere.center - camera.position(), camera.forward()),
+            bounds: sphere,
+            cast_shadows: shadows.0,
+            receive_shadows: shadows.1,
+        };
+
+        if !visible {
+            self.shadow_only.push(command);
+        } else if command.material.blend_mode.is_transparent() {
+            self.transparent.push(command);
+        } else {
+            self.opaque.push(command);
+        }
+    }
+
     pub fn render_screen_quad(&mut self, screen_quad: &Mesh<ScreenVertex>, screen_shader: &Shader, screen_buffer_texture_id: u32) {
         screen_shader.bind();
         self.current_shader = Some(screen_shader.id());
-        
+
         unsafe {
             gl::ActiveTexture(gl::TEXTURE0);
             gl::BindTexture(gl::TEXTURE_2D, screen_buffer_texture_id);
         }
         screen_quad.render();
     }
-    
+
+    /// Sorts and draws everything queued since `begin_frame`.
     pub fn end_frame(&mut self) {
-    
+        self.upload_lights();
+        self.render_shadow_maps();
+        self.bind_environment();
+        // the light and shadow uniforms have to be set again on every program used this frame
+        self.current_shader = None;
+
+        // opaque by state to minimize binds, then by mesh so instances end up next to each other,
+        // front to back inside a mesh for early depth rejects
+        self.opaque.sort_by(|a, b| {
+            a.shader_id()
+                .cmp(&b.shader_id())
+                .then(a.material_key().cmp(&b.material_key()))
+                .then(a.mesh_key().cmp(&b.mesh_key()))
+                .then(a.depth.total_cmp(&b.depth))
+        });
+        // blended back to front, the order matters for everything but additive
+        self.transparent.sort_by(|a, b| b.depth.total_cmp(&a.depth));
+
+        self.render_ssao();
+
+        let opaque = std::mem::take(&mut self.opaque);
+        match self.render_path {
+            RenderPath::Forward => self.flush(&opaque, self.instancing, None),
+            RenderPath::Deferred => self.render_deferred(&opaque),
+        }
+        // after the opaque pass so only uncovered pixels run the sky shader
+        self.render_sky();
+
+        if !self.transparent.is_empty() {
+            let transparent = std::mem::take(&mut self.transparent);
+            self.flush(&transparent, false, None);
+            Self::set_blend_state(BlendMode::Opaque);
+            self.transparent = transparent;
+        }
+        self.opaque = opaque;
+
+        self.stats = self.frame_stats;
     }
-    
-    fn set_camera_uniforms(&self, camera: &Camera, shader: &Shader) {
+
+    /// Draws the commands with their material shaders, or all with `shader_override`.
+    fn flush(&mut self, commands: &[DrawCommand], instancing: bool, shader_override: Option<&Shader>) {
+        let mut current_camera = None;
+        let mut current_material = None;
+        let mut current_blend_mode = None;
+
+        let mut i = 0;
+        while i < commands.len() {
+            let command = &commands[i];
+            let batch_len = if instancing {
+                commands[i..].iter().take_while(|c| c.can_batch_with(command)).count()
+            } else {
+                1
+            };
+            let batch = &commands[i..i + batch_len];
+            i += batch_len;
+
+            let material = command.material.as_ref();
+            let shader = shader_override.unwrap_or(material.shader.as_ref());
+
+            // uniforms are per program, a shader switch invalidates everything bound before
+            if self.current_shader != Some(shader.id()) {
+                shader.bind();
+                self.current_shader = Some(shader.id());
+                self.frame_stats.shader_binds += 1;
+                self.set_light_uniforms(shader);
+                self.set_shadow_uniforms(shader);
+                self.set_environment_uniforms(shader);
+                self.set_ssao_uniforms(shader);
+                current_camera = None;
+                current_material = None;
+            }
+
+            if current_camera != Some(command.camera) {
+                self.set_camera_uniforms(&self.cameras[command.camera], shader);
+                current_camera = Some(command.camera);
+            }
+
+            if current_blend_mode != Some(material.blend_mode) {
+                Self::set_blend_state(material.blend_mode);
+                current_blend_mode = Some(material.blend_mode);
+            }
+
+            if current_material != Some(command.material_key()) {
+                material.apply(shader);
+                current_material = Some(command.material_key());
+                self.frame_stats.material_binds += 1;
+            }
+
+            if let Some(loc) = shader.get_uniform_location("receive_shadows") {
+                shader.set_uniform(loc, command.receive_shadows as i32);
+            }
+
+            self.instances.clear();
+            self.instances.extend(batch.iter().map(|c| InstanceData::new(c.model)));
+            self.draw_instances(shader, &command.mesh);
+        }
+    }
+
+    /// Writes the opaque objects into the G-buffer and lights them with one full-screen pass
+    /// into the current framebuffer.
+    fn render_deferred(&mut self, opaque: &[DrawCommand]) {
+        let mut previous_viewport = [0; 4];
+        let mut previous_framebuffer = 0;
+        unsafe {
+            gl::GetIntegerv(gl::VIEWPORT, previous_viewport.as_mut_ptr());
+            gl::GetIntegerv(gl::DRAW_FRAMEBUFFER_BINDING, &mut previous_framebuffer);
+        }
+        let (width, height) = (previous_viewport[2].max(1) as u32, previous_viewport[3].max(1) as u32);
+        let Some(mut deferred) = self.deferred.take() else {
+            // `set_render_path` creates it before the path can be deferred
+            self.flush(opaque, self.instancing, None);
+            return;
+        };
+        deferred.resize(width, height);
+
+        // the G-buffer program stands in for the material shader, so only shaders with the
+        // engine surface and without tessellation stages can use it. The lighting pass
+        // reconstructs positions with the first camera, objects of other cameras stay forward too.
+        let (opaque, forward): (Vec<DrawCommand>, Vec<DrawCommand>) = opaque.iter().cloned().partition(|c| {
+            let shader = &c.material.shader;
+            shader.has_pbr_surface() && !shader.is_tessellated() && c.camera == 0
+        });
+
+        deferred.begin_geometry();
+        self.flush(&opaque, self.instancing, Some(deferred.geometry_shader()));
+
+        unsafe {
+            gl::Viewport(previous_viewport[0], previous_viewport[1], previous_viewport[2], previous_viewport[3]);
+        }
+        if let Some(camera) = self.cameras.first().copied() {
+            let shader = deferred.lighting_shader();
+            shader.bind();
+            self.current_shader = Some(shader.id());
+            self.frame_stats.shader_binds += 1;
+            self.set_light_uniforms(shader);
+            self.set_shadow_uniforms(shader);
+            self.set_environment_uniforms(shader);
+            self.set_ssao_uniforms(shader);
+            self.set_camera_uniforms(&camera, shader);
+            if let Some(loc) = shader.get_uniform_location("inverse_view_proj") {
+                shader.set_uniform(loc, (camera.projection * camera.view).inverse().unwrap_or(Mat4::one()));
+            }
+            deferred.render_lighting(previous_framebuffer as u32);
+            self.frame_stats.draw_calls += 1;
+        }
+        if !forward.is_empty() {
+            self.flush(&forward, self.instancing, None);
+        }
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, previous_framebuffer as u32);
+        }
+
+        self.deferred = Some(deferred);
+    }
+
+    /// Draws `self.instances`, one instanced draw for more than one instance.
+    fn draw_instances(&mut self, shader: &Shader, mesh: &Mesh<Vertex>) {
+        let patches = shader.is_tessellated();
+        if self.instances.len() >= MIN_INSTANCE_BATCH {
+            Self::set_instanced_uniform(shader, true);
+            if patches {
+                mesh.render_instanced_patches(&self.instances);
+            } else {
+                mesh.render_instanced(&self.instances);
+            }
+            Self::set_instanced_uniform(shader, false);
+            self.frame_stats.draw_calls += 1;
+            self.frame_stats.instanced += self.instances.len() as u32;
+        } else {
+            for instance in &self.instances {
+                self.set_model_uniforms(instance.model, shader);
+                if patches {
+                    mesh.render_patches();
+                } else {
+                    mesh.render();
+                }
+            }
+            self.frame_stats.draw_calls += self.instances.len() as u32;
+        }
+    }
+
+    /// Renders the directional cascades and the point light cube maps, then binds them
+    /// for the main pass.
+    fn render_shadow_maps(&mut self) {
+        self.cascades.clear();
+        self.shadow_light = None;
+        self.point_shadows.clear();
+        if !self.shadows_enabled || self.cameras.is_empty() {
+            return;
+        }
+
+        let mut casters: Vec<&DrawCommand> = self
+            .opaque
+            .iter()
+            .chain(self.shadow_only.iter())
+            .filter(|c| c.cast_shadows)
+            .collect();
+        casters.sort_by_key(|c| c.mesh_key());
+        // `draw_instances` borrows self mutably, keep copies instead of the commands
+        let casters: Vec<ShadowCaster> = casters
+            .into_iter()
+            .map(|c| ShadowCaster { mesh: c.mesh.clone(), model: c.model, bounds: c.bounds })
+            .collect();
+
+        let mut previous_viewport = [0; 4];
+        let mut previous_framebuffer = 0;
+        unsafe {
+            gl::GetIntegerv(gl::VIEWPORT, previous_viewport.as_mut_ptr());
+            gl::GetIntegerv(gl::DRAW_FRAMEBUFFER_BINDING, &mut previous_framebuffer);
+        }
+
+        self.render_cascades(&casters);
+        self.render_point_shadows(&casters);
+
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, previous_framebuffer as u32);
+            gl::Viewport(previous_viewport[0], previous_viewport[1], previous_viewport[2], previous_viewport[3]);
+        }
+    }
+
+    /// Depth pass of all shadow casters into the cascades of the first directional light.
+    fn render_cascades(&mut self, casters: &[ShadowCaster]) {
+        let Some((light_index, direction)) = self.lights.iter().enumerate().find_map(|(i, light)| match light.kind {
+            LightKind::Directional { direction } => Some((i, direction)),
+            _ => None,
+        }) else {
+            return;
+        };
+
+        let settings = self.shadow_settings;
+        let layers = settings.cascade_count.clamp(1, MAX_CASCADES);
+        let shadow_map = match self.shadow_map.take() {
+            Some(mut shadow_map) => shadow_map.resize(settings.resolution, layers).map(|_| shadow_map),
+            None => ShadowMap::new(settings.resolution, layers),
+        };
+        let shadow_map = match shadow_map {
+            Ok(shadow_map) => shadow_map,
+            Err(e) => {
+                eprintln!("Disabling shadows: {}", e);
+                self.shadows_enabled = false;
+                return;
+            }
+        };
+
+        // the first camera of the frame is the one the cascades are fitted to
+        let camera = self.cameras[0];
+        self.cascades = compute_cascades(&camera.view, &camera.projection, direction, &settings);
+        self.shadow_light = Some(light_index);
+
+        let shader = shadow_map.shader();
+        shader.bind();
+        self.current_shader = Some(shader.id());
+        for (layer, cascade) in self.cascades.clone().iter().enumerate() {
+            shadow_map.begin_layer(layer);
+            if let Some(loc) = shader.get_uniform_location("light_view_proj") {
+                shader.set_uniform(loc, cascade.view_proj);
+            }
+            self.draw_casters(shader, casters.iter());
+        }
+
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + SHADOW_MAP_UNIT);
+            gl::BindTexture(gl::TEXTURE_2D_ARRAY, shadow_map.depth_texture_id());
+        }
+        self.shadow_map = Some(shadow_map);
+    }
+
+    /// Cube map depth passes for the most important shadowed point and spot lights.
+    fn render_point_shadows(&mut self, casters: &[ShadowCaster]) {
+        let camera_position = self.cameras[0].position;
+        let selected = select_point_shadows(&self.lights, camera_position, self.point_shadow_budget);
+        if selected.is_empty() {
+            return;
+        }
+
+        if self.point_shadow_shader.is_none() {
+            let shader = Shader::from_source(
+                include_str!("../res/shaders/point_shadow.vert").to_string(),
+                include_str!("../res/shaders/point_shadow.frag").to_string(),
+            );
+            match shader {
+                Ok(shader) => self.point_shadow_shader = Some(shader),
+                Err(e) => {
+                    eprintln!("Disabling point light shadows: {}", e);
+                    self.point_shadow_budget = 0;
+                    return;
+                }
+            }
+        }
+        let shader = self.point_shadow_shader.take().unwrap();
+        shader.bind();
+        self.current_shader = Some(shader.id());
+
+        for (slot, light_index) in selected.into_iter().enumerate() {
+            let light = self.lights[light_index];
+            let (Some(settings), Some(position)) = (light.shadow, light.position()) else {
+                continue;
+            };
+
+            let cube_map = match self.point_shadow_maps.get_mut(slot) {
+                Some(cube_map) => cube_map.resize(settings.resolution).map(|_| ()),
+                None => CubeShadowMap::new(settings.resolution).map(|cube_map| self.point_shadow_maps.push(cube_map)),
+            };
+            if let Err(e) = cube_map {
+                eprintln!("Skipping point light shadow: {}", e);
+                continue;
+            }
+
+            if let Some(loc) = shader.get_uniform_location("light_pos") {
+                shader.set_uniform(loc, position);
+            }
+            if let Some(loc) = shader.get_uniform_location("light_far") {
+                shader.set_uniform(loc, settings.far);
+            }
+            let in_range = |c: &&ShadowCaster| glm::length(c.bounds.center - position) - c.bounds.radius < settings.far;
+            for (face, view_proj) in cube_face_view_projs(position, settings.near, settings.far).into_iter().enumerate() {
+                self.point_shadow_maps[slot].begin_face(face as u32);
+                if let Some(loc) = shader.get_uniform_location("light_view_proj") {
+                    shader.set_uniform(loc, view_proj);
+                }
+                self.draw_casters(&shader, casters.iter().filter(in_range));
+            }
+
+            unsafe {
+                gl::ActiveTexture(gl::TEXTURE0 + POINT_SHADOW_MAP_UNIT + slot as u32);
+                gl::BindTexture(gl::TEXTURE_CUBE_MAP, self.point_shadow_maps[slot].depth_texture_id());
+            }
+            self.point_shadows.push((light_index, settings));
+        }
+        self.point_shadow_shader = Some(shader);
+    }
+
+    /// Depth and normal prepass of the opaque objects seen by the first camera, then the
+    /// occlusion and blur passes. Binds the result for the main pass.
+    fn render_ssao(&mut self) {
+        self.ssao_rendered = false;
+        if !self.ssao_settings.enabled || self.cameras.is_empty() {
+            return;
+        }
+
+        let mut previous_viewport = [0; 4];
+        let mut previous_framebuffer = 0;
+        unsafe {
+            gl::GetIntegerv(gl::VIEWPORT, previous_viewport.as_mut_ptr());
+            gl::GetIntegerv(gl::DRAW_FRAMEBUFFER_BINDING, &mut previous_framebuffer);
+        }
+        let (width, height) = (previous_viewport[2].max(1) as u32, previous_viewport[3].max(1) as u32);
+        let ssao = match self.ssao.take() {
+            Some(mut ssao) => ssao.resize(width, height).map(|_| ssao),
+            None => Ssao::new(width, height),
+        };
+        let mut ssao = match ssao {
+            Ok(ssao) => ssao,
+            Err(e) => {
+                eprintln!("Disabling SSAO: {}", e);
+                self.ssao_settings.enabled = false;
+                return;
+            }
+        };
+
+        let mut objects: Vec<&DrawCommand> = self.opaque.iter().filter(|c| c.camera == 0).collect();
+        objects.sort_by_key(|c| c.mesh_key());
+        let objects: Vec<ShadowCaster> = objects
+            .into_iter()
+            .map(|c| ShadowCaster { mesh: c.mesh.clone(), model: c.model, bounds: c.bounds })
+            .collect();
+
+        let camera = self.cameras[0];
+        ssao.begin_prepass();
+        let shader = ssao.prepass_shader();
+        shader.bind();
+        self.current_shader = Some(shader.id());
+        self.set_camera_uniforms(&camera, shader);
+        self.draw_casters(shader, objects.iter());
+        ssao.render_occlusion(&camera.projection, &self.ssao_settings);
+
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, previous_framebuffer as u32);
+            gl::Viewport(previous_viewport[0], previous_viewport[1], previous_viewport[2], previous_viewport[3]);
+            gl::ActiveTexture(gl::TEXTURE0 + SSAO_UNIT);
+            gl::BindTexture(gl::TEXTURE_2D, ssao.texture_id());
+        }
+        self.ssao = Some(ssao);
+        self.ssao_rendered = true;
+    }
+
+    fn set_ssao_uniforms(&self, shader: &Shader) {
+        if let Some(loc) = shader.get_uniform_location("has_ssao") {
+            shader.set_uniform(loc, self.ssao_rendered as i32);
+        }
+    }
+
+    /// Draws the casters (sorted by mesh) with one instanced draw per mesh.
+    fn draw_casters<'a, I: Iterator<Item = &'a ShadowCaster>>(&mut self, shader: &Shader, casters: I) {
+        let mut casters = casters.peekable();
+        while let Some(first) = casters.next() {
+            self.instances.clear();
+            self.instances.push(InstanceData::new(first.model));
+            if self.instancing {
+                while let Some(next) = casters.next_if(|c| Arc::ptr_eq(&c.mesh, &first.mesh)) {
+                    self.instances.push(InstanceData::new(next.model));
+                }
+            }
+            self.draw_instances(shader, &first.mesh);
+        }
+    }
+
+    fn set_shadow_uniforms(&self, shader: &Shader) {
+        let light_index = self.shadow_light.map_or(-1, |i| i as i32);
+        if let Some(loc) = shader.get_uniform_location("shadow_light_index") {
+            shader.set_uniform(loc, light_index);
+        }
+        if let Some(loc) = shader.get_uniform_location("cascade_count") {
+            shader.set_uniform(loc, self.cascades.len() as i32);
+        }
+
+        for slot in 0..MAX_POINT_SHADOWS {
+            let (light_index, settings) = match self.point_shadows.get(slot) {
+                Some((light_index, settings)) => (*light_index as i32, Some(settings)),
+                None => (-1, None),
+            };
+            if let Some(loc) = shader.get_uniform_location(&format!("point_shadow_light_index[{}]", slot)) {
+                shader.set_uniform(loc, light_index);
+            }
+            if let Some(settings) = settings {
+                if let Some(loc) = shader.get_uniform_location(&format!("point_shadow_far[{}]", slot)) {
+                    shader.set_uniform(loc, settings.far);
+                }
+                if let Some(loc) = shader.get_uniform_location(&format!("point_shadow_bias[{}]", slot)) {
+                    shader.set_uniform(loc, settings.bias);
+                }
+            }
+        }
+
+        if self.cascades.is_empty() {
+            return;
+        }
+
+        let mut splits = [0.0; MAX_CASCADES];
+        let mut texel_sizes = [0.0; MAX_CASCADES];
+        for (i, cascade) in self.cascades.iter().enumerate() {
+            splits[i] = cascade.split_far;
+            texel_sizes[i] = cascade.texel_size;
+            if let Some(loc) = shader.get_uniform_location(&format!("cascade_view_proj[{}]", i)) {
+                shader.set_uniform(loc, cascade.view_proj);
+            }
+        }
+        if let Some(loc) = shader.get_uniform_location("cascade_splits") {
+            shader.set_uniform(loc, Vec4::new(splits[0], splits[1], splits[2], splits[3]));
+        }
+        if let Some(loc) = shader.get_uniform_location("cascade_texel_size") {
+            shader.set_uniform(loc, Vec4::new(texel_sizes[0], texel_sizes[1], texel_sizes[2], texel_sizes[3]));
+        }
+        if let Some(loc) = shader.get_uniform_location("shadow_depth_bias") {
+            shader.set_uniform(loc, self.shadow_settings.depth_bias);
+        }
+        if let Some(loc) = shader.get_uniform_location("shadow_normal_bias") {
+            shader.set_uniform(loc, self.shadow_settings.normal_bias);
+        }
+        if let Some(loc) = shader.get_uniform_location("shadow_pcf_radius") {
+            shader.set_uniform(loc, self.shadow_settings.pcf_radius);
+        }
+    }
+
+    fn upload_lights(&mut self) {
+        let mut gpu_lights: Vec<GpuLight> = self.lights.iter().map(|l| l.to_gpu()).collect();
+        if gpu_lights.is_empty() {
+            // binding an empty buffer range is an error, `light_count` keeps the dummy unused
+            gpu_lights.push(GpuLight::default());
+        }
+        self.light_ssbo.bind();
+        self.light_ssbo.buffer_data_with_usage(&gpu_lights, BufferUsage::StreamDraw);
+        unsafe {
+            gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, LIGHT_BUFFER_BINDING, self.light_ssbo.id());
+        }
+    }
+
+    fn set_light_uniforms(&self, shader: &Shader) {
+        if let Some(loc) = shader.get_uniform_location("light_count") {
+            shader.set_uniform(loc, self.lights.len() as i32);
+        }
+    }
+
+    fn render_sky(&mut self) {
+        let (Some(sky), Some(camera)) = (&self.sky, self.cameras.first()) else {
+            return;
+        };
+        if self.sky_pass.is_none() {
+            match SkyPass::new() {
+                Ok(sky_pass) => self.sky_pass = Some(sky_pass),
+                Err(e) => {
+                    eprintln!("Disabling sky: {}", e);
+                    self.sky = None;
+                    return;
+                }
+            }
+        }
+        let sky_pass = self.sky_pass.as_ref().unwrap();
+        sky_pass.render(sky, &camera.view, &camera.projection);
+        self.current_shader = Some(sky_pass.shader().id());
+        self.frame_stats.shader_binds += 1;
+        self.frame_stats.draw_calls += 1;
+    }
+
+    fn bind_environment(&self) {
+        if let Some(environment) = &self.environment {
+            unsafe {
+                gl::ActiveTexture(gl::TEXTURE0 + ENVIRONMENT_UNIT);
+                gl::BindTexture(gl::TEXTURE_CUBE_MAP, environment.prefiltered_id());
+                gl::ActiveTexture(gl::TEXTURE0 + BRDF_LUT_UNIT);
+                gl::BindTexture(gl::TEXTURE_2D, environment.brdf_lut_id());
+            }
+        }
+    }
+
+    fn set_environment_uniforms(&self, shader: &Shader) {
+        if let Some(loc) = shader.get_uniform_location("has_environment") {
+            shader.set_uniform(loc, self.environment.is_some() as i32);
+        }
+        let Some(environment) = &self.environment else {
+            return;
+        };
+        for (i, coefficient) in environment.irradiance().coefficients.iter().enumerate() {
+            if let Some(loc) = shader.get_uniform_location(&format!("irradiance_sh[{}]", i)) {
+                shader.set_uniform(loc, *coefficient);
+            }
+        }
+        if let Some(loc) = shader.get_uniform_location("prefilter_max_lod") {
+            shader.set_uniform(loc, (environment.prefilter_levels() - 1) as f32);
+        }
+        if let Some(loc) = shader.get_uniform_location("environment_intensity") {
+            shader.set_uniform(loc, environment.intensity());
+        }
+    }
+
+    /// Blend function and depth writes of `mode`, depth testing stays on for all modes.
+    fn set_blend_state(mode: BlendMode) {
+        unsafe {
+            match mode.blend_func() {
+                Some((src, dst)) => {
+                    gl::Enable(gl::BLEND);
+                    gl::BlendFunc(src, dst);
+                }
+                None => gl::Disable(gl::BLEND),
+            }
+            gl::DepthMask(if mode.writes_depth() { gl::TRUE } else { gl::FALSE });
+        }
+    }
+
+    fn set_instanced_uniform(shader: &Shader, instanced: bool) {
+        if let Some(loc) = shader.get_uniform_location("instanced") {
+            shader.set_uniform(loc, instanced as i32);
+        }
+    }
+
+    fn set_camera_uniforms(&self, camera: &CameraState, shader: &Shader) {
         if let Some(loc) = shader.get_uniform_location("proj_mat") {
-            shader.set_uniform(loc, camera.projection());
+            shader.set_uniform(loc, camera.projection);
         }
         if let Some(loc) = shader.get_uniform_location("view_mat") {
-            shader.set_uniform(loc, camera.view());
+            shader.set_uniform(loc, camera.view);
         }
         if let Some(loc) = shader.get_uniform_location("camera_pos") {
-            shader.set_uniform(loc, camera.position());
+            shader.set_uniform(loc, camera.position);
+        }
+        if let Some(loc) = shader.get_uniform_location("viewport_size") {
+            let mut viewport = [0; 4];
+            unsafe {
+                gl::GetIntegerv(gl::VIEWPORT, viewport.as_mut_ptr());
+            }
+            shader.set_uniform(loc, Vec2::new(viewport[2] as f32, viewport[3] as f32));
         }
     }
-    
-    fn set_model_uniforms(&self, transform: &Transform, shader: &Shader) {
+
+    fn set_model_uniforms(&self, model: Mat4, shader: &Shader) {
         if let Some(loc) = shader.get_uniform_location("model_mat") {
-            shader.set_uniform(loc, transform.model_matrix());
+            shader.set_uniform(loc, model);
         }
     }
-}
\ No newline at end of file
+}
@@ -1,9 +1,24 @@
 use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::ffi::CString;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::ptr::{null, null_mut};
 
+/// Deep enough for any real shader, stops include cycles.
+const MAX_INCLUDE_DEPTH: u32 = 8;
+
+/// Material textures and `material_surface`, a program including it shades its surface like the
+/// G-buffer pass.
+const SURFACE_INCLUDE: &str = "bun/surface.glsl";
+
+/// Engine shader code that any shader can `#include` by name, these win over files next to the
+/// including shader. Shaders built from source can only include these.
+const ENGINE_INCLUDES: [(&str, &str); 3] = [
+    ("bun/material.glsl", include_str!("../res/shaders/include/material.glsl")),
+    (SURFACE_INCLUDE, include_str!("../res/shaders/include/surface.glsl")),
+    ("bun/lighting.glsl", include_str!("../res/shaders/include/lighting.glsl")),
+];
+
 pub trait UniformValue {
     fn set_uniform(&self, location: i32);
 }
@@ -14,45 +29,156 @@ pub struct Shader {
     uniforms: RefCell<HashMap<String, i32>>,
     vertex_path: Option<PathBuf>,
     fragment_path: Option<PathBuf>,
+    // control and evaluation stage, only set for programs loaded from files
+    tessellation_paths: Option<(PathBuf, PathBuf)>,
+    tessellated: bool,
+    pbr_surface: Cell<bool>,
 }
 
 enum ShaderType {
     Vertex,
+    TessControl,
+    TessEvaluation,
     Fragment,
+    Compute,
 }
 
 impl Shader {
     pub fn new(vertex_path: &PathBuf, fragment_path: &PathBuf) -> Result<Self, String> {
-        let vertex = Self::create_shader(ShaderType::Vertex, vertex_path)?;
-        let fragment = Self::create_shader(ShaderType::Fragment, fragment_path)?;
+        let mut engine_includes = vec![];
+        let vertex = Self::create_shader(ShaderType::Vertex, vertex_path, &mut engine_includes)?;
+        let fragment = Self::create_shader(ShaderType::Fragment, fragment_path, &mut engine_includes)?;
+
+        let program = Self::create_program(&[vertex, fragment])?;
+
+        Ok(Self {
+            id: Cell::new(program),
+            uniforms: RefCell::new(HashMap::new()),
+            vertex_path: Some(vertex_path.clone()),
+            fragment_path: Some(fragment_path.clone()),
+            tessellation_paths: None,
+            tessellated: false,
+            pbr_surface: Cell::new(engine_includes.contains(&SURFACE_INCLUDE)),
+        })
+    }
 
-        let program = Self::create_program(vertex, fragment)?;
+    /// Vertex, tessellation control, tessellation evaluation and fragment stages. Meshes are
+    /// drawn as 3 vertex patches with this program.
+    pub fn with_tessellation(
+        vertex_path: &PathBuf,
+        tess_control_path: &PathBuf,
+        tess_evaluation_path: &PathBuf,
+        fragment_path: &PathBuf,
+    ) -> Result<Self, String> {
+        let mut engine_includes = vec![];
+        let program = Self::create_program(&[
+            Self::create_shader(ShaderType::Vertex, vertex_path, &mut engine_includes)?,
+            Self::create_shader(ShaderType::TessControl, tess_control_path, &mut engine_includes)?,
+            Self::create_shader(ShaderType::TessEvaluation, tess_evaluation_path, &mut engine_includes)?,
+            Self::create_shader(ShaderType::Fragment, fragment_path, &mut engine_includes)?,
+        ])?;
 
         Ok(Self {
             id: Cell::new(program),
             uniforms: RefCell::new(HashMap::new()),
             vertex_path: Some(vertex_path.clone()),
             fragment_path: Some(fragment_path.clone()),
+            tessellation_paths: Some((tess_control_path.clone(), tess_evaluation_path.clone())),
+            tessellated: true,
+            pbr_surface: Cell::new(engine_includes.contains(&SURFACE_INCLUDE)),
         })
     }
     
     pub fn from_source(vertex_src: String, fragment_src: String) -> Result<Self, String> {
-        let vertex = Self::create_shader_from_source(ShaderType::Vertex, vertex_src)?;
-        let fragment = Self::create_shader_from_source(ShaderType::Fragment, fragment_src)?;
+        let mut engine_includes = vec![];
+        let vertex = Self::create_shader_from_source(ShaderType::Vertex, vertex_src, &mut engine_includes)?;
+        let fragment = Self::create_shader_from_source(ShaderType::Fragment, fragment_src, &mut engine_includes)?;
         
-        let program = Self::create_program(vertex, fragment)?;
+        let program = Self::create_program(&[vertex, fragment])?;
         
         Ok(Self {
             id: Cell::new(program),
             uniforms: RefCell::new(HashMap::new()),
             vertex_path: None,
             fragment_path: None,
+            tessellation_paths: None,
+            tessellated: false,
+            pbr_surface: Cell::new(engine_includes.contains(&SURFACE_INCLUDE)),
+        })
+    }
+
+    pub fn tessellated_from_source(
+        vertex_src: String,
+        tess_control_src: String,
+        tess_evaluation_src: String,
+        fragment_src: String,
+    ) -> Result<Self, String> {
+        let mut engine_includes = vec![];
+        let program = Self::create_program(&[
+            Self::create_shader_from_source(ShaderType::Vertex, vertex_src, &mut engine_includes)?,
+            Self::create_shader_from_source(ShaderType::TessControl, tess_control_src, &mut engine_includes)?,
+            Self::create_shader_from_source(ShaderType::TessEvaluation, tess_evaluation_src, &mut engine_includes)?,
+            Self::create_shader_from_source(ShaderType::Fragment, fragment_src, &mut engine_includes)?,
+        ])?;
+
+        Ok(Self {
+            id: Cell::new(program),
+            uniforms: RefCell::new(HashMap::new()),
+            vertex_path: None,
+            fragment_path: None,
+            tessellation_paths: None,
+            tessellated: true,
+            pbr_surface: Cell::new(engine_includes.contains(&SURFACE_INCLUDE)),
+        })
+    }
+
+    /// A compute program, run it with [`Shader::dispatch`].
+    pub fn compute_from_source(compute_src: String) -> Result<Self, String> {
+        let compute = Self::create_shader_from_source(ShaderType::Compute, compute_src, &mut vec![])?;
+        let program = Self::create_program(&[compute])?;
+
+        Ok(Self {
+            id: Cell::new(program),
+            uniforms: RefCell::new(HashMap::new()),
+            vertex_path: None,
+            fragment_path: None,
+            tessellation_paths: None,
+            tessellated: false,
+            pbr_surface: Cell::new(false),
         })
     }
 
     pub fn id(&self) -> u32 {
         self.id.get()
     }
+    
+    pub fn vertex_path(&self) -> Option<&PathBuf> {
+        self.vertex_path.as_ref()
+    }
+    
+    pub fn fragment_path(&self) -> Option<&PathBuf> {
+        self.fragment_path.as_ref()
+    }
+
+    pub fn tess_control_path(&self) -> Option<&PathBuf> {
+        self.tessellation_paths.as_ref().map(|(control, _)| control)
+    }
+
+    pub fn tess_evaluation_path(&self) -> Option<&PathBuf> {
+        self.tessellation_paths.as_ref().map(|(_, evaluation)| evaluation)
+    }
+
+    /// Whether the program has tessellation stages and has to be drawn with patches.
+    pub fn is_tessellated(&self) -> bool {
+        self.tessellated
+    }
+
+    /// Whether the program builds its surface with `material_surface` from the engine include
+    /// `bun/surface.glsl`. Only these are drawn into the G-buffer on the deferred path,
+    /// the lighting pass would replace anything else the shader does.
+    pub fn has_pbr_surface(&self) -> bool {
+        self.pbr_surface.get()
+    }
 
     pub fn bind(&self) {
         unsafe {
@@ -60,6 +186,14 @@ impl Shader {
         }
     }
     
+    /// Binds the program and runs `x` * `y` * `z` work groups, only for compute programs.
+    pub fn dispatch(&self, x: u32, y: u32, z: u32) {
+        unsafe {
+            gl::UseProgram(self.id.get());
+            gl::DispatchCompute(x, y, z);
+        }
+    }
+
     pub fn unbind(&self) {
         unsafe {
             gl::UseProgram(0);
@@ -96,13 +230,22 @@ impl Shader {
         }
         let vertex_path = self.vertex_path.clone().unwrap();
         let fragment_path = self.fragment_path.clone().unwrap();
-        let vertex = Self::create_shader(ShaderType::Vertex, &vertex_path)?;
-        let fragment = Self::create_shader(ShaderType::Fragment, &fragment_path)?;
-        let new_id = Self::create_program(vertex, fragment)?;
+        let mut engine_includes = vec![];
+        let vertex = Self::create_shader(ShaderType::Vertex, &vertex_path, &mut engine_includes)?;
+        let fragment = Self::create_shader(ShaderType::Fragment, &fragment_path, &mut engine_includes)?;
+        let new_id = match &self.tessellation_paths {
+            Some((control_path, evaluation_path)) => {
+                let control = Self::create_shader(ShaderType::TessControl, control_path, &mut engine_includes)?;
+                let evaluation = Self::create_shader(ShaderType::TessEvaluation, evaluation_path, &mut engine_includes)?;
+                Self::create_program(&[vertex, control, evaluation, fragment])?
+            }
+            None => Self::create_program(&[vertex, fragment])?,
+        };
         unsafe {
             gl::DeleteProgram(self.id.get());
         }
         self.id.set(new_id);
+        self.pbr_surface.set(engine_includes.contains(&SURFACE_INCLUDE));
         
         let mut uniforms = self.uniforms.borrow_mut();
         uniforms.clear();
@@ -110,19 +253,83 @@ impl Shader {
         Ok(())
     }
 
-    fn create_shader(shader_type: ShaderType, path: &PathBuf) -> Result<u32, String> {
-        let shader_src = std::fs::read_to_string(path)
-            .map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
-        Self::create_shader_from_source(shader_type, shader_src)
+    /// Adds the engine includes of the stage to `engine_includes`, which all stages of a program share.
+    fn create_shader(shader_type: ShaderType, path: &PathBuf, engine_includes: &mut Vec<&'static str>) -> Result<u32, String> {
+        let shader_src = Self::expand_includes(&Self::read_source(path)?, path.parent(), 0, engine_includes)?;
+        Self::compile_shader(shader_type, shader_src)
+    }
+
+    fn read_source(path: &Path) -> Result<String, String> {
+        std::fs::read_to_string(path).map_err(|e| format!("Failed to read {:?}: {}", path, e))
+    }
+
+    /// Reads a shader file and replaces `#include "file"` lines with the engine include of that
+    /// name or else the file, relative to the including one. Lets stages and programs share
+    /// declarations like the material UBO.
+    pub fn load_source(path: &Path) -> Result<String, String> {
+        Self::expand_includes(&Self::read_source(path)?, path.parent(), 0, &mut vec![])
+    }
+
+    /// Replaces the `#include` lines of a shader without a file, only engine includes resolve.
+    pub fn resolve_includes(src: &str) -> Result<String, String> {
+        Self::expand_includes(src, None, 0, &mut vec![])
+    }
+
+    /// `engine_includes` collects the names of the engine includes that were used.
+    fn expand_includes(
+        src: &str,
+        dir: Option<&Path>,
+        depth: u32,
+        engine_includes: &mut Vec<&'static str>,
+    ) -> Result<String, String> {
+        let mut out = String::with_capacity(src.len());
+        for line in src.lines() {
+            match line.trim().strip_prefix("#include") {
+                Some(include) => {
+                    let include = include.trim().trim_matches('"');
+                    if depth >= MAX_INCLUDE_DEPTH {
+                        return Err(format!("Too many nested includes at \"{}\"", include));
+                    }
+                    let included = match ENGINE_INCLUDES.iter().find(|(name, _)| *name == include) {
+                        Some((name, engine_src)) => {
+                            engine_includes.push(name);
+                            Self::expand_includes(engine_src, None, depth + 1, engine_includes)?
+                        }
+                        None => {
+                            let dir = dir.ok_or_else(|| format!("Unknown shader include \"{}\"", include))?;
+                            let include_path = dir.join(include);
+                            let include_src = Self::read_source(&include_path)?;
+                            Self::expand_includes(&include_src, include_path.parent(), depth + 1, engine_includes)?
+                        }
+                    };
+                    out.push_str(&included);
+                }
+                None => out.push_str(line),
+            }
+            out.push('\n');
+        }
+        Ok(out)
+    }
+
+    fn create_shader_from_source(
+        shader_type: ShaderType,
+        shader_src: String,
+        engine_includes: &mut Vec<&'static str>,
+    ) -> Result<u32, String> {
+        let shader_src = Self::expand_includes(&shader_src, None, 0, engine_includes)?;
+        Self::compile_shader(shader_type, shader_src)
     }
     
-    fn create_shader_from_source(shader_type: ShaderType, shader_src: String) -> Result<u32, String> {
+    fn compile_shader(shader_type: ShaderType, shader_src: String) -> Result<u32, String> {
         let shader_src = CString::new(shader_src)
             .map_err(|e| format!("Failed to create CString: {}", e))?;
         unsafe {
             let shader_type = match shader_type {
                 ShaderType::Vertex => gl::VERTEX_SHADER,
+                ShaderType::TessControl => gl::TESS_CONTROL_SHADER,
+                ShaderType::TessEvaluation => gl::TESS_EVALUATION_SHADER,
                 ShaderType::Fragment => gl::FRAGMENT_SHADER,
+                ShaderType::Compute => gl::COMPUTE_SHADER,
             };
             let shader = gl::CreateShader(shader_type);
             gl::ShaderSource(shader, 1, &shader_src.as_ptr(), null());
@@ -142,12 +349,13 @@ impl Shader {
         }
     }
 
-    fn create_program(vertex: u32, fragment: u32) -> Result<u32, String> {
+    fn create_program(shaders: &[u32]) -> Result<u32, String> {
         unsafe {
             let program = gl::CreateProgram();
 
-            gl::AttachShader(program, vertex);
-            gl::AttachShader(program, fragment);
+            for &shader in shaders {
+                gl::AttachShader(program, shader);
+            }
             gl::LinkProgram(program);
 
             let mut success = 0;
@@ -160,8 +368,9 @@ impl Shader {
                 return Err(std::format!("Could not link program: {}", log_str));
             }
 
-            gl::DeleteShader(vertex);
-            gl::DeleteShader(fragment);
+            for &shader in shaders {
+                gl::DeleteShader(shader);
+            }
 
             Ok(program)
         }
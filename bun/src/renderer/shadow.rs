@@ -0,0 +1,367 @@
+use crate::renderer::light::Light;
+use crate::Shader;
+use gl::types::{GLint, GLsizei};
+use glm::{normalize, GenSquareMat, Mat4, Vec3, Vec4};
+use num_traits::One;
+use std::ptr::null;
+
+pub const MAX_CASCADES: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShadowSettings {
+    /// 1 to `MAX_CASCADES`.
+    pub cascade_count: usize,
+    /// Width and height of every cascade.
+    pub resolution: u32,
+    /// Shadows end at this view distance (or the far clip if that is closer).
+    pub max_distance: f32,
+    /// Blend between uniform (0) and logarithmic (1) cascade splits.
+    pub split_lambda: f32,
+    /// Casters up to this far behind a cascade (towards the light) still end up in it.
+    pub caster_distance: f32,
+    /// Constant depth bias in light space depth units.
+    pub depth_bias: f32,
+    /// World space offset along the surface normal, scaled by the cascade texel size.
+    pub normal_bias: f32,
+    /// PCF kernel radius in texels, 0 is a single hardware filtered tap.
+    pub pcf_radius: i32,
+}
+
+impl Default for ShadowSettings {
+    fn default() -> Self {
+        Self {
+            cascade_count: 4,
+            resolution: 2048,
+            max_distance: 80.0,
+            split_lambda: 0.75,
+            caster_distance: 50.0,
+            depth_bias: 0.0005,
+            normal_bias: 1.5,
+            pcf_radius: 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Cascade {
+    /// World to light clip space.
+    pub view_proj: Mat4,
+    /// View distance where this cascade ends.
+    pub split_far: f32,
+    /// World space size of one shadow map texel.
+    pub texel_size: f32,
+}
+
+/// View distances where the cascades end, the last one is the shadow distance.
+pub fn cascade_splits(near: f32, far: f32, count: usize, lambda: f32) -> Vec<f32> {
+    (1..=count)
+        .map(|i| {
+            let p = i as f32 / count as f32;
+            let log = near * (far / near).powf(p);
+            let uniform = near + (far - near) * p;
+            lambda * log + (1.0 - lambda) * uniform
+        })
+        .collect()
+}
+
+/// Fits one orthographic light projection around each slice of the camera frustum.
+/// The cascades are bounding spheres snapped to whole texels, so they don't shimmer
+/// when the camera moves or rotates. `projection` has to be a perspective projection.
+pub fn compute_cascades(view: &Mat4, projection: &Mat4, light_direction: Vec3, settings: &ShadowSettings) -> Vec<Cascade> {
+    let count = settings.cascade_count.clamp(1, MAX_CASCADES);
+    // clip planes back from the projection matrix
+    let near = projection[3].z / (projection[2].z - 1.0);
+    let far = projection[3].z / (projection[2].z + 1.0);
+    let shadow_far = far.min(settings.max_distance).max(near + 0.001);
+    let light_direction = normalize(light_direction);
+
+    let inv_view_proj = match (*projection * *view).inverse() {
+        Some(m) => m,
+        None => return vec![],
+    };
+    let unproject = |x: f32, y: f32, z: f32| {
+        let p = inv_view_proj * Vec4::new(x, y, z, 1.0);
+        p.truncate(3) / p.w
+    };
+    // the four frustum edges from the near to the far plane, view depth is linear along them
+    let edges = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)]
+        .map(|(x, y)| (unproject(x, y, -1.0), unproject(x, y, 1.0)));
+    let slice_corners = |distance: f32| {
+        let t = (distance - near) / (far - near);
+        edges.map(|(near_corner, far_corner)| near_corner + (far_corner - near_corner) * t)
+    };
+
+    // any up vector works as long as it isn't parallel to the light
+    let light_up = if light_direction.y.abs() > 0.99 {
+        Vec3::new(0.0, 0.0, 1.0)
+    } else {
+        Vec3::new(0.0, 1.0, 0.0)
+    };
+
+    let mut split_near = near;
+    cascade_splits(near, shadow_far, count, settings.split_lambda)
+        .into_iter()
+        .map(|split_far| {
+            let corners: Vec<Vec3> = slice_corners(split_near)
+                .into_iter()
+                .chain(slice_corners(split_far))
+                .collect();
+            split_near = split_far;
+
+            let center = corners.iter().fold(Vec3::new(0.0, 0.0, 0.0), |acc, c| acc + *c) / 8.0;
+            let radius = corners.iter().map(|c| glm::length(*c - center)).fold(0.0, f32::max);
+            // quantize so the projection size stays constant while the camera rotates
+            let radius = (radius * 16.0).ceil() / 16.0;
+
+            let eye = center - light_direction * (radius + settings.caster_distance);
+            let view = glm::ext::look_at(eye, center, light_up);
+            let proj = ortho(-radius, radius, -radius, radius, 0.0, 2.0 * radius + settings.caster_distance);
+            let mut view_proj = proj * view;
+
+            // snap the world origin to a texel so moving the camera only ever shifts whole texels
+            let half_resolution = settings.resolution as f32 / 2.0;
+            let origin = view_proj * Vec4::new(0.0, 0.0, 0.0, 1.0);
+            view_proj[3].x += ((origin.x * half_resolution).round() - origin.x * half_resolution) / half_resolution;
+            view_proj[3].y += ((origin.y * half_resolution).round() - origin.y * half_resolution) / half_resolution;
+
+            Cascade {
+                view_proj,
+                split_far,
+                texel_size: 2.0 * radius / settings.resolution as f32,
+            }
+        })
+        .collect()
+}
+
+/// OpenGL style orthographic projection (-1..1 depth), glm 0.3 doesn't have one.
+pub fn ortho(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> Mat4 {
+    let mut m = Mat4::one();
+    m[0].x = 2.0 / (right - left);
+    m[1].y = 2.0 / (top - bottom);
+    m[2].z = -2.0 / (far - near);
+    m[3].x = -(right + left) / (right - left);
+    m[3].y = -(top + bottom) / (top - bottom);
+    m[3].z = -(far + near) / (far - near);
+    m
+}
+
+/// Depth texture array with one layer per cascade and the depth only shader to fill it.
+pub struct ShadowMap {
+    fbo: u32,
+    depth_texture: u32,
+    resolution: u32,
+    layers: usize,
+    shader: Shader,
+}
+
+impl ShadowMap {
+    pub fn new(resolution: u32, layers: usize) -> Result<Self, String> {
+        let mut fbo = 0;
+        let mut depth_texture = 0;
+        unsafe {
+            gl::GenTextures(1, &mut depth_texture);
+            gl::GenFramebuffers(1, &mut fbo);
+        }
+        let shader = Shader::from_source(
+            include_str!("../res/shaders/shadow.vert").to_string(),
+            include_str!("../res/shaders/shadow.frag").to_string(),
+        )?;
+        let mut shadow_map = Self { fbo, depth_texture, resolution: 0, layers: 0, shader };
+        shadow_map.resize(resolution, layers)?;
+        Ok(shadow_map)
+    }
+
+    pub fn resize(&mut self, resolution: u32, layers: usize) -> Result<(), String> {
+        if self.resolution == resolution && self.layers == layers {
+            return Ok(());
+        }
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D_ARRAY, self.depth_texture);
+            gl::TexImage3D(
+                gl::TEXTURE_2D_ARRAY,
+                0,
+                gl::DEPTH_COMPONENT32F as GLint,
+                resolution as GLsizei,
+                resolution as GLsizei,
+                layers as GLsizei,
+                0,
+                gl::DEPTH_COMPONENT,
+                gl::FLOAT,
+                null(),
+            );
+            // linear + compare mode gives 2x2 hardware PCF per tap
+            gl::TexParameteri(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_BORDER as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_BORDER as GLint);
+            let border = [1.0f32; 4];
+            gl::TexParameterfv(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_BORDER_COLOR, border.as_ptr());
+            gl::TexParameteri(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_COMPARE_MODE, gl::COMPARE_REF_TO_TEXTURE as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_COMPARE_FUNC, gl::LEQUAL as GLint);
+
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            gl::FramebufferTextureLayer(gl::FRAMEBUFFER, gl::DEPTH_ATTACHMENT, self.depth_texture, 0, 0);
+            gl::DrawBuffer(gl::NONE);
+            gl::ReadBuffer(gl::NONE);
+            let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            if status != gl::FRAMEBUFFER_COMPLETE {
+                return Err(format!("Could not create shadow framebuffer: {:#X?}", status));
+            }
+        }
+        self.resolution = resolution;
+        self.layers = layers;
+        Ok(())
+    }
+
+    pub fn depth_texture_id(&self) -> u32 {
+        self.depth_texture
+    }
+
+    pub fn shader(&self) -> &Shader {
+        &self.shader
+    }
+
+    /// Binds the framebuffer with `layer` as depth target and clears it.
+    pub(crate) fn begin_layer(&self, layer: usize) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            gl::FramebufferTextureLayer(gl::FRAMEBUFFER, gl::DEPTH_ATTACHMENT, self.depth_texture, 0, layer as GLint);
+            gl::Viewport(0, 0, self.resolution as GLsizei, self.resolution as GLsizei);
+            gl::Clear(gl::DEPTH_BUFFER_BIT);
+        }
+    }
+}
+
+impl Drop for ShadowMap {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteFramebuffers(1, &self.fbo);
+            gl::DeleteTextures(1, &self.depth_texture);
+        }
+    }
+}
+
+/// Upper limit for `Renderer::set_point_shadow_budget`, the shaders have this many cube samplers.
+pub const MAX_POINT_SHADOWS: usize = 4;
+
+/// Looking direction and up vector of the six cube faces, in GL face order (+X, -X, +Y, -Y, +Z, -Z).
+const CUBE_FACES: [(Vec3, Vec3); 6] = [
+    (Vec3 { x: 1.0, y: 0.0, z: 0.0 }, Vec3 { x: 0.0, y: -1.0, z: 0.0 }),
+    (Vec3 { x: -1.0, y: 0.0, z: 0.0 }, Vec3 { x: 0.0, y: -1.0, z: 0.0 }),
+    (Vec3 { x: 0.0, y: 1.0, z: 0.0 }, Vec3 { x: 0.0, y: 0.0, z: 1.0 }),
+    (Vec3 { x: 0.0, y: -1.0, z: 0.0 }, Vec3 { x: 0.0, y: 0.0, z: -1.0 }),
+    (Vec3 { x: 0.0, y: 0.0, z: 1.0 }, Vec3 { x: 0.0, y: -1.0, z: 0.0 }),
+    (Vec3 { x: 0.0, y: 0.0, z: -1.0 }, Vec3 { x: 0.0, y: -1.0, z: 0.0 }),
+];
+
+/// World to clip matrices of the six 90 degree cube faces around `position`.
+pub fn cube_face_view_projs(position: Vec3, near: f32, far: f32) -> [Mat4; 6] {
+    let proj = glm::ext::perspective(std::f32::consts::FRAC_PI_2, 1.0, near, far);
+    CUBE_FACES.map(|(direction, up)| proj * glm::ext::look_at(position, position + direction, up))
+}
+
+/// Picks the shadowed point and spot lights that matter most from `camera_position`,
+/// at most `budget`. Returns indices into `lights`, most important first.
+///
+/// Lights are ranked by intensity over the squared distance from the camera to the
+/// edge of their shadow range, so every light the camera is inside of ranks by intensity.
+pub fn select_point_shadows(lights: &[Light], camera_position: Vec3, budget: usize) -> Vec<usize> {
+    let mut candidates: Vec<(usize, f32)> = lights
+        .iter()
+        .enumerate()
+        .filter_map(|(i, light)| {
+            let shadow = light.shadow?;
+            let position = light.position()?;
+            let distance = (glm::length(position - camera_position) - shadow.far).max(0.0);
+            let brightness = light.intensity * light.color.x.max(light.color.y).max(light.color.z);
+            Some((i, brightness / (1.0 + distance * distance)))
+        })
+        .filter(|(_, importance)| *importance > 0.0)
+        .collect();
+    candidates.sort_by(|a, b| b.1.total_cmp(&a.1));
+    candidates.into_iter().take(budget.min(MAX_POINT_SHADOWS)).map(|(i, _)| i).collect()
+}
+
+/// Depth cube map storing the linear distance to the light divided by `far`.
+pub struct CubeShadowMap {
+    fbo: u32,
+    depth_texture: u32,
+    resolution: u32,
+}
+
+impl CubeShadowMap {
+    pub fn new(resolution: u32) -> Result<Self, String> {
+        let mut fbo = 0;
+        let mut depth_texture = 0;
+        unsafe {
+            gl::GenTextures(1, &mut depth_texture);
+            gl::GenFramebuffers(1, &mut fbo);
+        }
+        let mut shadow_map = Self { fbo, depth_texture, resolution: 0 };
+        shadow_map.resize(resolution)?;
+        Ok(shadow_map)
+    }
+
+    pub fn resize(&mut self, resolution: u32) -> Result<(), String> {
+        if self.resolution == resolution {
+            return Ok(());
+        }
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, self.depth_texture);
+            for face in 0..6 {
+                gl::TexImage2D(
+                    gl::TEXTURE_CUBE_MAP_POSITIVE_X + face,
+                    0,
+                    gl::DEPTH_COMPONENT32F as GLint,
+                    resolution as GLsizei,
+                    resolution as GLsizei,
+                    0,
+                    gl::DEPTH_COMPONENT,
+                    gl::FLOAT,
+                    null(),
+                );
+            }
+            gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
+            gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+            gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as GLint);
+            gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as GLint);
+            gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_WRAP_R, gl::CLAMP_TO_EDGE as GLint);
+
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::DEPTH_ATTACHMENT, gl::TEXTURE_CUBE_MAP_POSITIVE_X, self.depth_texture, 0);
+            gl::DrawBuffer(gl::NONE);
+            gl::ReadBuffer(gl::NONE);
+            let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            if status != gl::FRAMEBUFFER_COMPLETE {
+                return Err(format!("Could not create cube shadow framebuffer: {:#X?}", status));
+            }
+        }
+        self.resolution = resolution;
+        Ok(())
+    }
+
+    pub fn depth_texture_id(&self) -> u32 {
+        self.depth_texture
+    }
+
+    /// Binds the framebuffer with `face` (0..6, GL face order) as depth target and clears it.
+    pub(crate) fn begin_face(&self, face: u32) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::DEPTH_ATTACHMENT, gl::TEXTURE_CUBE_MAP_POSITIVE_X + face, self.depth_texture, 0);
+            gl::Viewport(0, 0, self.resolution as GLsizei, self.resolution as GLsizei);
+            gl::Clear(gl::DEPTH_BUFFER_BIT);
+        }
+    }
+}
+
+impl Drop for CubeShadowMap {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteFramebuffers(1, &self.fbo);
+            gl::DeleteTextures(1, &self.depth_texture);
+        }
+    }
+}
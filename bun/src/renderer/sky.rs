@@ -0,0 +1,161 @@
+use crate::renderer::atmosphere::{Atmosphere, SKY_LUMINANCE_SCALE};
+use crate::renderer::environment::Environment;
+use crate::renderer::texture::{CubeMap, Texture};
+use crate::Shader;
+use glm::{GenSquareMat, Mat4, Vec4};
+use std::sync::Arc;
+
+/// Where the sky colors come from.
+#[derive(Clone)]
+pub enum SkySource {
+    /// The source cube map of an IBL environment, so background and ambient light match.
+    Environment(Arc<Environment>),
+    CubeMap(Arc<CubeMap>),
+    /// Equirectangular panorama, top row up.
+    Equirect(Arc<Texture>),
+    /// Procedural daylight sky, `Sky::rotation` doesn't apply, the sun path turns with
+    /// `Atmosphere::sunrise_azimuth` instead.
+    Atmosphere(Atmosphere),
+}
+
+/// Background drawn behind all opaque geometry, set with `Renderer::set_sky`.
+#[derive(Clone)]
+pub struct Sky {
+    pub source: SkySource,
+    /// In stops, the sky color is scaled by `2^exposure`.
+    pub exposure: f32,
+    /// Radians around the world up axis.
+    pub rotation: f32,
+}
+
+impl Sky {
+    pub fn new(source: SkySource) -> Self {
+        Self { source, exposure: 0.0, rotation: 0.0 }
+    }
+
+    pub fn with_exposure(mut self, exposure: f32) -> Self {
+        self.exposure = exposure;
+        self
+    }
+
+    pub fn with_rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+}
+
+// must match the defines in sky.frag
+const SKY_CUBE_MAP: i32 = 0;
+const SKY_EQUIRECT: i32 = 1;
+const SKY_ATMOSPHERE: i32 = 2;
+
+/// Shader and empty vertex array of the sky pass, the sky is a single fullscreen
+/// triangle on the far plane.
+pub(crate) struct SkyPass {
+    shader: Shader,
+    vao: u32,
+}
+
+impl SkyPass {
+    pub(crate) fn new() -> Result<Self, String> {
+        let shader = Shader::from_source(
+            include_str!("../res/shaders/sky.vert").to_string(),
+            include_str!("../res/shaders/sky.frag").to_string(),
+        )?;
+        let mut vao = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut vao);
+        }
+        Ok(Self { shader, vao })
+    }
+
+    pub(crate) fn shader(&self) -> &Shader {
+        &self.shader
+    }
+
+    /// Draws `sky` where the depth buffer is still cleared. Only the rotation of `view` is used,
+    /// the sky is infinitely far away.
+    pub(crate) fn render(&self, sky: &Sky, view: &Mat4, projection: &Mat4) {
+        let mut rotation = *view;
+        rotation[3] = Vec4::new(0.0, 0.0, 0.0, 1.0);
+        let Some(inverse_view_proj) = (*projection * rotation).inverse() else {
+            return;
+        };
+
+        let (source_type, texture) = match &sky.source {
+            SkySource::Environment(environment) => (SKY_CUBE_MAP, Some((gl::TEXTURE_CUBE_MAP, environment.cube_map_id()))),
+            SkySource::CubeMap(cube_map) => (SKY_CUBE_MAP, Some((gl::TEXTURE_CUBE_MAP, cube_map.id()))),
+            SkySource::Equirect(texture) => (SKY_EQUIRECT, Some((gl::TEXTURE_2D, texture.id()))),
+            SkySource::Atmosphere(_) => (SKY_ATMOSPHERE, None),
+        };
+
+        let shader = &self.shader;
+        shader.bind();
+        if let SkySource::Atmosphere(atmosphere) = &sky.source {
+            self.set_atmosphere_uniforms(atmosphere);
+        }
+        if let Some(loc) = shader.get_uniform_location("inverse_view_proj") {
+            shader.set_uniform(loc, inverse_view_proj);
+        }
+        if let Some(loc) = shader.get_uniform_location("sky_source") {
+            shader.set_uniform(loc, source_type);
+        }
+        if let Some(loc) = shader.get_uniform_location("sky_exposure") {
+            shader.set_uniform(loc, sky.exposure);
+        }
+        if let Some(loc) = shader.get_uniform_location("sky_rotation") {
+            let rotation = if source_type == SKY_ATMOSPHERE { 0.0 } else { sky.rotation };
+            shader.set_uniform(loc, rotation);
+        }
+
+        unsafe {
+            if let Some((target, id)) = texture {
+                let unit = if source_type == SKY_CUBE_MAP { 0 } else { 1 };
+                gl::ActiveTexture(gl::TEXTURE0 + unit);
+                gl::BindTexture(target, id);
+            }
+
+            // the cleared depth is 1.0 and the triangle sits exactly on it
+            gl::DepthFunc(gl::LEQUAL);
+            gl::DepthMask(gl::FALSE);
+            gl::BindVertexArray(self.vao);
+            gl::DrawArrays(gl::TRIANGLES, 0, 3);
+            gl::BindVertexArray(0);
+            gl::DepthMask(gl::TRUE);
+            gl::DepthFunc(gl::LESS);
+        }
+    }
+}
+
+impl SkyPass {
+    fn set_atmosphere_uniforms(&self, atmosphere: &Atmosphere) {
+        let shader = &self.shader;
+        let coefficients = atmosphere.preetham();
+        let values = [
+            ("preetham_a", coefficients.a),
+            ("preetham_b", coefficients.b),
+            ("preetham_c", coefficients.c),
+            ("preetham_d", coefficients.d),
+            ("preetham_e", coefficients.e),
+            ("preetham_zenith", coefficients.zenith),
+            ("sun_direction", atmosphere.sun_direction()),
+            ("sun_radiance", atmosphere.sun_transmittance() * atmosphere.sun_intensity),
+        ];
+        for (name, value) in values {
+            if let Some(loc) = shader.get_uniform_location(name) {
+                shader.set_uniform(loc, value);
+            }
+        }
+        if let Some(loc) = shader.get_uniform_location("sky_luminance_scale") {
+            shader.set_uniform(loc, SKY_LUMINANCE_SCALE * atmosphere.daylight());
+        }
+    }
+}
+
+impl Drop for SkyPass {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteVertexArrays(1, &self.vao);
+        }
+    }
+}
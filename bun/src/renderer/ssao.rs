@@ -0,0 +1,286 @@
+use crate::Shader;
+use gl::types::{GLint, GLsizei};
+use glm::{normalize, GenSquareMat, Mat4, Vec2, Vec3};
+use std::ptr::null;
+
+/// Upper limit of `SsaoSettings::sample_count`, the size of the kernel array in `ssao.frag`.
+pub const MAX_SSAO_SAMPLES: u32 = 64;
+/// Side of the tiled rotation noise, the blur averages over the same footprint.
+const NOISE_SIZE: u32 = 4;
+
+/// Screen space ambient occlusion (Chapman 2013 normal oriented hemisphere), set with
+/// `Renderer::set_ssao_settings`. Only darkens the ambient term of the shaders.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SsaoSettings {
+    pub enabled: bool,
+    /// View space radius of the sampled hemisphere.
+    pub radius: f32,
+    /// Samples per pixel, clamped to `MAX_SSAO_SAMPLES`.
+    pub sample_count: u32,
+    /// Exponent on the occlusion, higher is darker contact shadows.
+    pub intensity: f32,
+    /// View space depth difference below which samples don't occlude, against self occlusion.
+    pub bias: f32,
+    /// Shows the blurred AO buffer instead of the frame.
+    pub debug_view: bool,
+}
+
+impl Default for SsaoSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            radius: 0.5,
+            sample_count: 32,
+            intensity: 1.5,
+            bias: 0.025,
+            debug_view: false,
+        }
+    }
+}
+
+/// Sample offsets in a +z hemisphere of radius 1, denser towards the center so close
+/// geometry weighs more. Seeded, the same count always gives the same kernel.
+pub fn ssao_kernel(sample_count: u32) -> Vec<Vec3> {
+    let count = sample_count.clamp(1, MAX_SSAO_SAMPLES);
+    let mut rng = fastrand::Rng::with_seed(0x55A0);
+    (0..count)
+        .map(|i| {
+            let direction = normalize(Vec3::new(
+                rng.f32() * 2.0 - 1.0,
+                rng.f32() * 2.0 - 1.0,
+                rng.f32().max(0.05),
+            ));
+            let t = i as f32 / count as f32;
+            let scale = 0.1 + 0.9 * t * t;
+            direction * (rng.f32() * scale)
+        })
+        .collect()
+}
+
+/// Depth + view space normal prepass, the raw and the blurred occlusion.
+pub struct Ssao {
+    prepass_fbo: u32,
+    normal_texture: u32,
+    depth_texture: u32,
+    ao_fbo: u32,
+    ao_texture: u32,
+    blur_fbo: u32,
+    blur_texture: u32,
+    noise_texture: u32,
+    vao: u32,
+    width: u32,
+    height: u32,
+    kernel_size: u32,
+    prepass: Shader,
+    occlusion: Shader,
+    blur: Shader,
+}
+
+impl Ssao {
+    pub fn new(width: u32, height: u32) -> Result<Self, String> {
+        let prepass = Shader::from_source(
+            include_str!("../res/shaders/ssao_prepass.vert").to_string(),
+            include_str!("../res/shaders/ssao_prepass.frag").to_string(),
+        )?;
+        let occlusion = Shader::from_source(
+            include_str!("../res/shaders/fullscreen.vert").to_string(),
+            include_str!("../res/shaders/ssao.frag").to_string(),
+        )?;
+        let blur = Shader::from_source(
+            include_str!("../res/shaders/fullscreen.vert").to_string(),
+            include_str!("../res/shaders/ssao_blur.frag").to_string(),
+        )?;
+
+        let mut fbos = [0; 3];
+        let mut textures = [0; 5];
+        let mut vao = 0;
+        unsafe {
+            gl::GenFramebuffers(3, fbos.as_mut_ptr());
+            gl::GenTextures(5, textures.as_mut_ptr());
+            gl::GenVertexArrays(1, &mut vao);
+        }
+        let [prepass_fbo, ao_fbo, blur_fbo] = fbos;
+        let [normal_texture, depth_texture, ao_texture, blur_texture, noise_texture] = textures;
+
+        // random rotations around the view space normal, tiled over the screen
+        let mut rng = fastrand::Rng::with_seed(0x4015E);
+        let noise: Vec<f32> = (0..NOISE_SIZE * NOISE_SIZE).flat_map(|_| [rng.f32() * 2.0 - 1.0, rng.f32() * 2.0 - 1.0]).collect();
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, noise_texture);
+            gl::TexImage2D(gl::TEXTURE_2D, 0, gl::RG16F as GLint, NOISE_SIZE as GLsizei, NOISE_SIZE as GLsizei, 0, gl::RG, gl::FLOAT, noise.as_ptr() as *const _);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as GLint);
+        }
+
+        let mut ssao = Self {
+            prepass_fbo,
+            normal_texture,
+            depth_texture,
+            ao_fbo,
+            ao_texture,
+            blur_fbo,
+            blur_texture,
+            noise_texture,
+            vao,
+            width: 0,
+            height: 0,
+            kernel_size: 0,
+            prepass,
+            occlusion,
+            blur,
+        };
+        ssao.resize(width, height)?;
+        Ok(ssao)
+    }
+
+    pub fn resize(&mut self, width: u32, height: u32) -> Result<(), String> {
+        if self.width == width && self.height == height {
+            return Ok(());
+        }
+        let (w, h) = (width as GLsizei, height as GLsizei);
+        unsafe {
+            Self::allocate(self.normal_texture, gl::RGB16F, gl::RGB, gl::FLOAT, w, h);
+            Self::allocate(self.depth_texture, gl::DEPTH_COMPONENT32F, gl::DEPTH_COMPONENT, gl::FLOAT, w, h);
+            Self::allocate(self.ao_texture, gl::R8, gl::RED, gl::UNSIGNED_BYTE, w, h);
+            Self::allocate(self.blur_texture, gl::R8, gl::RED, gl::UNSIGNED_BYTE, w, h);
+
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.prepass_fbo);
+            gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::TEXTURE_2D, self.normal_texture, 0);
+            gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::DEPTH_ATTACHMENT, gl::TEXTURE_2D, self.depth_texture, 0);
+            Self::check_framebuffer("prepass")?;
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.ao_fbo);
+            gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::TEXTURE_2D, self.ao_texture, 0);
+            Self::check_framebuffer("occlusion")?;
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.blur_fbo);
+            gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::TEXTURE_2D, self.blur_texture, 0);
+            Self::check_framebuffer("blur")?;
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+        self.width = width;
+        self.height = height;
+        Ok(())
+    }
+
+    /// Blurred occlusion, 1 is unoccluded. Valid after `render_occlusion`.
+    pub fn texture_id(&self) -> u32 {
+        self.blur_texture
+    }
+
+    /// Clears and binds the prepass target, the caller draws the opaque geometry with `prepass_shader`.
+    pub(crate) fn begin_prepass(&self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.prepass_fbo);
+            gl::Viewport(0, 0, self.width as GLsizei, self.height as GLsizei);
+            gl::ClearColor(0.0, 0.0, 1.0, 1.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+        }
+    }
+
+    pub(crate) fn prepass_shader(&self) -> &Shader {
+        &self.prepass
+    }
+
+    /// Occlusion from the prepass followed by the blur. Leaves the framebuffer unbound.
+    pub(crate) fn render_occlusion(&mut self, projection: &Mat4, settings: &SsaoSettings) {
+        let Some(inv_projection) = projection.inverse() else {
+            return;
+        };
+        unsafe {
+            gl::Disable(gl::DEPTH_TEST);
+            gl::BindVertexArray(self.vao);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.ao_fbo);
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, self.depth_texture);
+            gl::ActiveTexture(gl::TEXTURE1);
+            gl::BindTexture(gl::TEXTURE_2D, self.normal_texture);
+            gl::ActiveTexture(gl::TEXTURE2);
+            gl::BindTexture(gl::TEXTURE_2D, self.noise_texture);
+        }
+
+        let shader = &self.occlusion;
+        shader.bind();
+        let sample_count = settings.sample_count.clamp(1, MAX_SSAO_SAMPLES);
+        // the kernel only changes with the sample count
+        if self.kernel_size != sample_count {
+            for (i, sample) in ssao_kernel(sample_count).into_iter().enumerate() {
+                if let Some(loc) = shader.get_uniform_location(&format!("samples[{}]", i)) {
+                    shader.set_uniform(loc, sample);
+                }
+            }
+            self.kernel_size = sample_count;
+        }
+        if let Some(loc) = shader.get_uniform_location("sample_count") {
+            shader.set_uniform(loc, sample_count as i32);
+        }
+        if let Some(loc) = shader.get_uniform_location("radius") {
+            shader.set_uniform(loc, settings.radius);
+        }
+        if let Some(loc) = shader.get_uniform_location("bias") {
+            shader.set_uniform(loc, settings.bias);
+        }
+        if let Some(loc) = shader.get_uniform_location("intensity") {
+            shader.set_uniform(loc, settings.intensity);
+        }
+        if let Some(loc) = shader.get_uniform_location("proj_mat") {
+            shader.set_uniform(loc, *projection);
+        }
+        if let Some(loc) = shader.get_uniform_location("inv_proj_mat") {
+            shader.set_uniform(loc, inv_projection);
+        }
+        if let Some(loc) = shader.get_uniform_location("noise_scale") {
+            shader.set_uniform(loc, Vec2::new(self.width as f32 / NOISE_SIZE as f32, self.height as f32 / NOISE_SIZE as f32));
+        }
+        unsafe {
+            gl::DrawArrays(gl::TRIANGLES, 0, 3);
+        }
+
+        // a box blur over the noise tile removes the rotation pattern
+        let shader = &self.blur;
+        shader.bind();
+        if let Some(loc) = shader.get_uniform_location("texel_size") {
+            shader.set_uniform(loc, Vec2::new(1.0 / self.width as f32, 1.0 / self.height as f32));
+        }
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.blur_fbo);
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, self.ao_texture);
+            gl::DrawArrays(gl::TRIANGLES, 0, 3);
+
+            gl::BindVertexArray(0);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            gl::Enable(gl::DEPTH_TEST);
+        }
+    }
+
+    unsafe fn allocate(texture: u32, internal_format: u32, format: u32, ty: u32, width: GLsizei, height: GLsizei) {
+        gl::BindTexture(gl::TEXTURE_2D, texture);
+        gl::TexImage2D(gl::TEXTURE_2D, 0, internal_format as GLint, width, height, 0, format, ty, null());
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as GLint);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as GLint);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as GLint);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as GLint);
+    }
+
+    unsafe fn check_framebuffer(name: &str) -> Result<(), String> {
+        let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+        if status != gl::FRAMEBUFFER_COMPLETE {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            return Err(format!("Could not create SSAO {} framebuffer: {:#X?}", name, status));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Ssao {
+    fn drop(&mut self) {
+        let fbos = [self.prepass_fbo, self.ao_fbo, self.blur_fbo];
+        let textures = [self.normal_texture, self.depth_texture, self.ao_texture, self.blur_texture, self.noise_texture];
+        unsafe {
+            gl::DeleteFramebuffers(3, fbos.as_ptr());
+            gl::DeleteTextures(5, textures.as_ptr());
+            gl::DeleteVertexArrays(1, &self.vao);
+        }
+    }
+}
@@ -0,0 +1,387 @@
+use crate::renderer::material::Material;
+use crate::renderer::mesh_data::MeshData;
+use crate::renderer::renderer::Renderer;
+use crate::{Camera, Mesh, Vertex};
+use glm::{Mat4, Vec2, Vec3, Vec4};
+use num_traits::One;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Grid of heights between 0 and 1. Sample `x` runs along +X, row `z` along +Z.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heightmap {
+    width: u32,
+    depth: u32,
+    samples: Vec<f32>,
+}
+
+impl Heightmap {
+    /// Loads a grayscale image, 16 bit PNGs keep their full precision. The first image row is
+    /// the -Z edge of the terrain.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        println!("loading heightmap: {}", path.as_ref().to_string_lossy());
+        let image = image::open(path.as_ref())
+            .map_err(|e| format!("Could not load heightmap {:?}: {}", path.as_ref(), e))?
+            .into_luma16();
+        let (width, depth) = image.dimensions();
+        let samples = image.into_raw().into_iter().map(|h| h as f32 / u16::MAX as f32).collect();
+        Self::from_samples(width, depth, samples)
+    }
+
+    /// `samples` row by row, `width` * `depth` of them.
+    pub fn from_samples(width: u32, depth: u32, samples: Vec<f32>) -> Result<Self, String> {
+        if width < 2 || depth < 2 {
+            return Err(format!("Heightmap has to be at least 2x2, got {}x{}", width, depth));
+        }
+        if samples.len() != (width * depth) as usize {
+            return Err(format!("Heightmap of {}x{} needs {} samples, got {}", width, depth, width * depth, samples.len()));
+        }
+        Ok(Self { width, depth, samples })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
+    pub fn samples(&self) -> &[f32] {
+        &self.samples
+    }
+
+    /// Bilinear height at `u`, `v` in [0, 1], clamped to the edges.
+    pub fn sample(&self, u: f32, v: f32) -> f32 {
+        let x = u.clamp(0.0, 1.0) * (self.width - 1) as f32;
+        let z = v.clamp(0.0, 1.0) * (self.depth - 1) as f32;
+        let (x0, z0) = ((x as u32).min(self.width - 2), (z as u32).min(self.depth - 2));
+        let (tx, tz) = (x - x0 as f32, z - z0 as f32);
+        let at = |x: u32, z: u32| self.samples[(z * self.width + x) as usize];
+        let top = at(x0, z0) + (at(x0 + 1, z0) - at(x0, z0)) * tx;
+        let bottom = at(x0, z0 + 1) + (at(x0 + 1, z0 + 1) - at(x0, z0 + 1)) * tx;
+        top + (bottom - top) * tz
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TerrainSettings {
+    /// Extent along X and Z, centered on the terrain origin.
+    pub size: f32,
+    /// Height of a white heightmap sample above the origin.
+    pub height_scale: f32,
+    /// Chunks along each side.
+    pub chunks: u32,
+    /// Quads along a chunk side at full detail, rounded up to a power of two.
+    pub chunk_resolution: u32,
+    /// Detail levels, each one halves the resolution of the previous one.
+    pub lod_levels: u32,
+    /// Camera distance to a chunk where the first reduced level starts, every further level
+    /// starts at twice the distance of the previous one.
+    pub lod_distance: f32,
+}
+
+impl Default for TerrainSettings {
+    fn default() -> Self {
+        Self {
+            size: 512.0,
+            height_scale: 64.0,
+            chunks: 16,
+            chunk_resolution: 32,
+            lod_levels: 4,
+            lod_distance: 48.0,
+        }
+    }
+}
+
+/// Edges of a chunk whose neighbour is one level coarser. Their odd vertices are moved onto
+/// the neighbour's edge so there are no cracks between the two.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct CoarserEdges {
+    pub neg_x: bool,
+    pub pos_x: bool,
+    pub neg_z: bool,
+    pub pos_z: bool,
+}
+
+/// Heights and chunk geometry of a terrain, everything that doesn't need a GL context.
+/// Positions are relative to the terrain origin.
+pub struct TerrainData {
+    heightmap: Heightmap,
+    settings: TerrainSettings,
+}
+
+impl TerrainData {
+    pub fn new(heightmap: Heightmap, mut settings: TerrainSettings) -> Self {
+        settings.chunks = settings.chunks.max(1);
+        settings.chunk_resolution = settings.chunk_resolution.max(1).next_power_of_two();
+        // the coarsest level still has one quad per chunk
+        settings.lod_levels = settings.lod_levels.clamp(1, settings.chunk_resolution.trailing_zeros() + 1);
+        Self { heightmap, settings }
+    }
+
+    pub fn heightmap(&self) -> &Heightmap {
+        &self.heightmap
+    }
+
+    pub fn settings(&self) -> &TerrainSettings {
+        &self.settings
+    }
+
+    pub fn chunk_size(&self) -> f32 {
+        self.settings.size / self.settings.chunks as f32
+    }
+
+    /// Height at `x`, `z`, `None` outside the terrain.
+    pub fn height_at(&self, x: f32, z: f32) -> Option<f32> {
+        let half = self.settings.size * 0.5;
+        if x.abs() > half || z.abs() > half {
+            return None;
+        }
+        Some(self.height_clamped(x, z))
+    }
+
+    /// Surface normal at `x`, `z`, `None` outside the terrain.
+    pub fn normal_at(&self, x: f32, z: f32) -> Option<Vec3> {
+        self.height_at(x, z)?;
+        Some(self.normal_clamped(x, z))
+    }
+
+    fn height_clamped(&self, x: f32, z: f32) -> f32 {
+        let size = self.settings.size;
+        self.heightmap.sample(x / size + 0.5, z / size + 0.5) * self.settings.height_scale
+    }
+
+    fn normal_clamped(&self, x: f32, z: f32) -> Vec3 {
+        // central differences over one full detail quad
+        let step = self.chunk_size() / self.settings.chunk_resolution as f32;
+        let dx = self.height_clamped(x - step, z) - self.height_clamped(x + step, z);
+        let dz = self.height_clamped(x, z - step) - self.height_clamped(x, z + step);
+        glm::normalize(Vec3::new(dx, 2.0 * step, dz))
+    }
+
+    /// Detail level of every chunk, row by row along +Z, for a camera at `camera`. Neighbours
+    /// differ by at most one level.
+    pub fn select_lods(&self, camera: Vec3) -> Vec<u32> {
+        let settings = &self.settings;
+        let chunk_size = self.chunk_size();
+        let half = settings.size * 0.5;
+        let mut lods = Vec::with_capacity((settings.chunks * settings.chunks) as usize);
+        for cz in 0..settings.chunks {
+            for cx in 0..settings.chunks {
+                // distance to the chunk box, zero inside of it
+                let min = Vec3::new(-half + cx as f32 * chunk_size, 0.0, -half + cz as f32 * chunk_size);
+                let max = Vec3::new(min.x + chunk_size, settings.height_scale, min.z + chunk_size);
+                let closest = Vec3::new(
+                    camera.x.clamp(min.x, max.x),
+                    camera.y.clamp(min.y, max.y),
+                    camera.z.clamp(min.z, max.z),
+                );
+                let distance = glm::length(camera - closest);
+                let lod = if distance < settings.lod_distance {
+                    0
+                } else {
+                    (distance / settings.lod_distance).log2() as u32 + 1
+                };
+                lods.push(lod.min(settings.lod_levels - 1));
+            }
+        }
+        balance_lods(&mut lods, settings.chunks);
+        lods
+    }
+
+    /// Full detail at `lod` 0, every level halves the quads along each side.
+    pub fn chunk_mesh_data(&self, cx: u32, cz: u32, lod: u32, coarser: CoarserEdges) -> MeshData<Vertex> {
+        let settings = &self.settings;
+        let quads = (settings.chunk_resolution >> lod).max(1);
+        let chunk_size = self.chunk_size();
+        let step = chunk_size / quads as f32;
+        let half = settings.size * 0.5;
+        let (x0, z0) = (-half + cx as f32 * chunk_size, -half + cz as f32 * chunk_size);
+
+        let mut vertices = Vec::with_capacity(((quads + 1) * (quads + 1)) as usize);
+        for row in 0..=quads {
+            for col in 0..=quads {
+                let (x, z) = (x0 + col as f32 * step, z0 + row as f32 * step);
+                let mut y = self.height_clamped(x, z);
+                // the coarser neighbour has no vertex here, sit on the line between its two
+                let stitch_x = (row == 0 && coarser.neg_z) || (row == quads && coarser.pos_z);
+                let stitch_z = (col == 0 && coarser.neg_x) || (col == quads && coarser.pos_x);
+                if stitch_x && col % 2 == 1 {
+                    y = (self.height_clamped(x - step, z) + self.height_clamped(x + step, z)) * 0.5;
+                } else if stitch_z && row % 2 == 1 {
+                    y = (self.height_clamped(x, z - step) + self.height_clamped(x, z + step)) * 0.5;
+                }
+
+                let normal = self.normal_clamped(x, z);
+                // along +X in the surface, the bitangent then follows -Z like the uv v axis
+                let tangent = glm::normalize(Vec3::new(normal.y, -normal.x, 0.0));
+                let tangent = Vec4::new(tangent.x, tangent.y, tangent.z, 1.0);
+                let uv = Vec2::new(x / settings.size + 0.5, 0.5 - z / settings.size);
+                vertices.push(Vertex::new(Vec3::new(x, y, z), normal, uv, tangent));
+            }
+        }
+
+        let stride = quads + 1;
+        let mut indices = Vec::with_capacity((quads * quads * 6) as usize);
+        for row in 0..quads {
+            for col in 0..quads {
+                let a = col + row * stride;
+                let b = a + 1;
+                let c = a + stride;
+                let d = c + 1;
+                indices.extend_from_slice(&[a, c, b, b, c, d]);
+            }
+        }
+
+        MeshData::new(vertices, indices)
+    }
+}
+
+/// Lowers levels until no chunk is more than one level coarser than a neighbour, which is
+/// what the stitching of `CoarserEdges` can close. `lods` is row by row with `chunks` per row.
+pub fn balance_lods(lods: &mut [u32], chunks: u32) {
+    let chunks = chunks as usize;
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for i in 0..lods.len() {
+            let (x, z) = (i % chunks, i / chunks);
+            let mut limit = u32::MAX;
+            if x > 0 {
+                limit = limit.min(lods[i - 1] + 1);
+            }
+            if x + 1 < chunks {
+                limit = limit.min(lods[i + 1] + 1);
+            }
+            if z > 0 {
+                limit = limit.min(lods[i - chunks] + 1);
+            }
+            if i + chunks < lods.len() {
+                limit = limit.min(lods[i + chunks] + 1);
+            }
+            if lods[i] > limit {
+                lods[i] = limit;
+                changed = true;
+            }
+        }
+    }
+}
+
+/// Edges of chunk `x`, `z` that border a coarser chunk in `lods`.
+pub fn coarser_edges(lods: &[u32], chunks: u32, x: u32, z: u32) -> CoarserEdges {
+    let lod = |x: u32, z: u32| lods[(z * chunks + x) as usize];
+    let own = lod(x, z);
+    CoarserEdges {
+        neg_x: x > 0 && lod(x - 1, z) > own,
+        pos_x: x + 1 < chunks && lod(x + 1, z) > own,
+        neg_z: z > 0 && lod(x, z - 1) > own,
+        pos_z: z + 1 < chunks && lod(x, z + 1) > own,
+    }
+}
+
+/// Chunked heightmap terrain. Call `update` with the camera position every frame before
+/// `render`, it picks the chunk levels and builds the meshes that are missing.
+pub struct Terrain {
+    data: TerrainData,
+    material: Arc<Material>,
+    origin: Vec3,
+    cast_shadows: bool,
+    lods: Vec<u32>,
+    // meshes of the current levels by chunk index, level and stitched edges
+    meshes: HashMap<(u32, u32, CoarserEdges), Arc<Mesh<Vertex>>>,
+}
+
+impl Terrain {
+    pub fn new(data: TerrainData, material: Arc<Material>) -> Self {
+        Self {
+            data,
+            material,
+            origin: Vec3::new(0.0, 0.0, 0.0),
+            cast_shadows: true,
+            lods: vec![],
+            meshes: HashMap::new(),
+        }
+    }
+
+    pub fn data(&self) -> &TerrainData {
+        &self.data
+    }
+
+    pub fn material(&self) -> &Arc<Material> {
+        &self.material
+    }
+
+    pub fn set_material(&mut self, material: Arc<Material>) {
+        self.material = material;
+    }
+
+    /// World position of the terrain center at height 0.
+    pub fn origin(&self) -> Vec3 {
+        self.origin
+    }
+
+    pub fn set_origin(&mut self, origin: Vec3) {
+        self.origin = origin;
+    }
+
+    pub fn cast_shadows(&self) -> bool {
+        self.cast_shadows
+    }
+
+    pub fn set_cast_shadows(&mut self, cast_shadows: bool) {
+        self.cast_shadows = cast_shadows;
+    }
+
+    /// World height of the surface below `x`, `z`, `None` outside the terrain.
+    pub fn height_at(&self, x: f32, z: f32) -> Option<f32> {
+        self.data.height_at(x - self.origin.x, z - self.origin.z).map(|h| h + self.origin.y)
+    }
+
+    /// World normal of the surface below `x`, `z`, `None` outside the terrain.
+    pub fn normal_at(&self, x: f32, z: f32) -> Option<Vec3> {
+        self.data.normal_at(x - self.origin.x, z - self.origin.z)
+    }
+
+    /// Level chosen by the last `update`, row by row along +Z.
+    pub fn lods(&self) -> &[u32] {
+        &self.lods
+    }
+
+    /// Picks the chunk levels for a camera at the world position `camera`. Needs a GL context
+    /// for the meshes of levels that weren't used by the last update, the ones it no longer
+    /// uses are dropped.
+    // render objects take their mesh as an Arc, the chunk meshes stay on the gl thread
+    #[allow(clippy::arc_with_non_send_sync)]
+    pub fn update(&mut self, camera: Vec3) {
+        self.lods = self.data.select_lods(camera - self.origin);
+        let chunks = self.data.settings.chunks;
+        let mut previous = std::mem::take(&mut self.meshes);
+        for z in 0..chunks {
+            for x in 0..chunks {
+                let lod = self.lods[(z * chunks + x) as usize];
+                let coarser = coarser_edges(&self.lods, chunks, x, z);
+                let key = (z * chunks + x, lod, coarser);
+                let mesh = previous
+                    .remove(&key)
+                    .unwrap_or_else(|| Arc::new(Mesh::from_mesh_data(&self.data.chunk_mesh_data(x, z, lod, coarser))));
+                self.meshes.insert(key, mesh);
+            }
+        }
+    }
+
+    /// Queues every chunk at the levels of the last `update`. Chunks outside the view are
+    /// culled by the renderer like any other object.
+    pub fn render(&self, renderer: &mut Renderer, camera: &Camera) {
+        let chunks = self.data.settings.chunks;
+        let model = glm::ext::translate(&Mat4::one(), self.origin);
+        for (i, &lod) in self.lods.iter().enumerate() {
+            let (x, z) = (i as u32 % chunks, i as u32 / chunks);
+            let coarser = coarser_edges(&self.lods, chunks, x, z);
+            if let Some(mesh) = self.meshes.get(&(i as u32, lod, coarser)) {
+                renderer.submit(mesh, &self.material, model, camera, (self.cast_shadows, true));
+            }
+        }
+    }
+}
@@ -1,12 +1,17 @@
 use gl::types::GLint;
 use glm::Vec3;
 use std::ffi::c_void;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+/// Highest texture unit `bind` accepts.
+const MAX_TEXTURE_UNIT: u32 = 31;
 
 pub struct Texture {
     texture_id: u32,
     width: i32,
     height: i32,
+    spec: TextureSpec,
+    path: Option<PathBuf>,
 }
 
 pub struct ImageData {
@@ -16,7 +21,8 @@ pub struct ImageData {
     pub data: Vec<u8>,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TextureUsage {
     Albedo,      // color, sRGB
     Normal,      // vector data, linear
@@ -24,7 +30,8 @@ pub enum TextureUsage {
     Emissive,    // usually sRGB
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TextureSpec {
     pub usage: TextureUsage,
     pub min_filter: u32,
@@ -41,10 +48,17 @@ impl Texture {
     {
         let file_name = String::from(path.as_ref().to_string_lossy());
         println!("loading texture: {}", file_name);
+        let source_path = path.as_ref().to_path_buf();
         let image = image::open(path).unwrap();
-        let image = image.flipv();
-        
-        let rgba = image.to_rgba8();
+        let mut texture = Self::from_image(&image, spec)?;
+        texture.path = Some(source_path);
+        Ok(texture)
+    }
+
+    /// Texture from an already decoded image, e.g. one embedded in a model file. The first
+    /// image row ends up at the top, at v = 1.
+    pub fn from_image(image: &image::DynamicImage, spec: TextureSpec) -> Result<Self, String> {
+        let rgba = image.flipv().to_rgba8();
         let width = rgba.width() as i32;
         let height = rgba.height() as i32;
         
@@ -60,7 +74,9 @@ impl Texture {
         Ok(Self {
             width,
             height,
-            texture_id
+            texture_id,
+            spec,
+            path: None,
         })
     }
     
@@ -80,12 +96,13 @@ impl Texture {
         Ok(Self {
             width,
             height,
-            texture_id
+            texture_id,
+            spec,
+            path: None,
         })
     }
 
     pub fn bind(&self, unit: u32) -> Result<(), String> {
-        const MAX_TEXTURE_UNIT: u32 = 31;
         if unit > MAX_TEXTURE_UNIT {
             return Err(format!("Texture unit is too big. {unit} > {MAX_TEXTURE_UNIT}"))
         }
@@ -139,6 +156,8 @@ impl Texture {
     pub fn id(&self) -> u32 { self.texture_id }
     pub fn width(&self) -> i32 { self.width }
     pub fn height(&self) -> i32 { self.height }
+    pub fn spec(&self) -> TextureSpec { self.spec }
+    pub fn path(&self) -> Option<&Path> { self.path.as_deref() }
 }
 
 impl Drop for Texture {
@@ -150,6 +169,186 @@ impl Drop for Texture {
 }
 
 
+/// Six color images as a cube map, for skies.
+pub struct CubeMap {
+    texture_id: u32,
+    size: i32,
+}
+
+impl CubeMap {
+    /// Faces in GL order: +X, -X, +Y, -Y, +Z, -Z. All faces have to be square and the same size.
+    pub fn from_faces<P: AsRef<Path>>(faces: &[P; 6]) -> Result<Self, String> {
+        let mut texture_id = 0;
+        let mut size = 0;
+        unsafe {
+            gl::GenTextures(1, &mut texture_id);
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, texture_id);
+        }
+        // owns the texture from here on, an error below deletes it
+        let mut cube_map = Self { texture_id, size: 0 };
+        for (face, path) in faces.iter().enumerate() {
+            println!("loading cube map face: {}", path.as_ref().to_string_lossy());
+            let image = image::open(path.as_ref())
+                .map_err(|e| format!("Could not load cube map face {:?}: {}", path.as_ref(), e))?
+                .to_rgba8();
+            let (width, height) = image.dimensions();
+            if width != height || (face > 0 && width as i32 != size) {
+                return Err(format!("Cube map face {:?} is {}x{}, faces have to be square and equal", path.as_ref(), width, height));
+            }
+            size = width as i32;
+            unsafe {
+                gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+                gl::TexImage2D(
+                    gl::TEXTURE_CUBE_MAP_POSITIVE_X + face as u32,
+                    0,
+                    gl::SRGB8_ALPHA8 as GLint,
+                    size,
+                    size,
+                    0,
+                    gl::RGBA,
+                    gl::UNSIGNED_BYTE,
+                    image.as_raw().as_ptr() as *const c_void,
+                );
+            }
+        }
+        unsafe {
+            gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_MIN_FILTER, gl::LINEAR_MIPMAP_LINEAR as GLint);
+            gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+            gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as GLint);
+            gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as GLint);
+            gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_WRAP_R, gl::CLAMP_TO_EDGE as GLint);
+            gl::GenerateMipmap(gl::TEXTURE_CUBE_MAP);
+
+            let err = gl::GetError();
+            if err != gl::NO_ERROR {
+                return Err(format!("Error loading cube map: {:#X}", err));
+            }
+        }
+        cube_map.size = size;
+        Ok(cube_map)
+    }
+
+    pub fn id(&self) -> u32 { self.texture_id }
+    pub fn size(&self) -> i32 { self.size }
+}
+
+impl Drop for CubeMap {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteTextures(1, &self.texture_id);
+        }
+    }
+}
+
+
+/// Color images as the layers of one array texture, e.g. the splat layers of a terrain.
+pub struct TextureArray {
+    texture_id: u32,
+    size: i32,
+    spec: TextureSpec,
+    paths: Vec<PathBuf>,
+}
+
+impl TextureArray {
+    /// One layer per path, in order. Every image is scaled to `size` x `size`.
+    pub fn from_paths<P: AsRef<Path>>(paths: &[P], spec: TextureSpec, size: u32) -> Result<Self, String> {
+        if paths.is_empty() {
+            return Err("Texture array needs at least one layer".to_string());
+        }
+        let mut texture_id = 0;
+        unsafe {
+            gl::GenTextures(1, &mut texture_id);
+            gl::BindTexture(gl::TEXTURE_2D_ARRAY, texture_id);
+            gl::TexImage3D(
+                gl::TEXTURE_2D_ARRAY,
+                0,
+                spec.internal_format(true) as GLint,
+                size as i32,
+                size as i32,
+                paths.len() as i32,
+                0,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                std::ptr::null(),
+            );
+        }
+        // owns the texture from here on, an error below deletes it
+        let array = Self {
+            texture_id,
+            size: size as i32,
+            spec,
+            paths: paths.iter().map(|p| p.as_ref().to_path_buf()).collect(),
+        };
+        for (layer, path) in paths.iter().enumerate() {
+            println!("loading texture array layer: {}", path.as_ref().to_string_lossy());
+            let image = image::open(path.as_ref())
+                .map_err(|e| format!("Could not load texture array layer {:?}: {}", path.as_ref(), e))?
+                .flipv()
+                .to_rgba8();
+            let image = if image.dimensions() != (size, size) {
+                image::imageops::resize(&image, size, size, image::imageops::FilterType::Triangle)
+            } else {
+                image
+            };
+            unsafe {
+                gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+                gl::TexSubImage3D(
+                    gl::TEXTURE_2D_ARRAY,
+                    0,
+                    0,
+                    0,
+                    layer as i32,
+                    size as i32,
+                    size as i32,
+                    1,
+                    gl::RGBA,
+                    gl::UNSIGNED_BYTE,
+                    image.as_raw().as_ptr() as *const c_void,
+                );
+            }
+        }
+        unsafe {
+            gl::TexParameteri(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_MIN_FILTER, spec.min_filter as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_MAG_FILTER, spec.mag_filter as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_WRAP_S, spec.wrap_s as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_WRAP_T, spec.wrap_t as GLint);
+            gl::GenerateMipmap(gl::TEXTURE_2D_ARRAY);
+
+            let err = gl::GetError();
+            if err != gl::NO_ERROR {
+                return Err(format!("Error loading texture array: {:#X}", err));
+            }
+        }
+        Ok(array)
+    }
+
+    pub fn bind(&self, unit: u32) -> Result<(), String> {
+        if unit > MAX_TEXTURE_UNIT {
+            return Err(format!("Texture unit is too big. {unit} > {MAX_TEXTURE_UNIT}"))
+        }
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + unit);
+            gl::BindTexture(gl::TEXTURE_2D_ARRAY, self.texture_id);
+        }
+        Ok(())
+    }
+
+    pub fn id(&self) -> u32 { self.texture_id }
+    pub fn size(&self) -> i32 { self.size }
+    pub fn layers(&self) -> usize { self.paths.len() }
+    pub fn spec(&self) -> TextureSpec { self.spec }
+    pub fn paths(&self) -> &[PathBuf] { &self.paths }
+}
+
+impl Drop for TextureArray {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteTextures(1, &self.texture_id);
+        }
+    }
+}
+
+
 impl TextureSpec {
     pub fn albedo() -> Self {
         Self {
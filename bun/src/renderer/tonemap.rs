@@ -0,0 +1,202 @@
+use crate::renderer::buffer::{BufferUsage, SSBO};
+use crate::Shader;
+
+/// Number of luminance buckets of the auto exposure histogram, the first one counts near black pixels.
+pub const HISTOGRAM_BINS: usize = 256;
+/// Binding points of the histogram and the adapted luminance SSBOs in the shaders.
+const HISTOGRAM_BINDING: u32 = 4;
+const EXPOSURE_BINDING: u32 = 5;
+/// Local size of `luminance_histogram.comp` in both directions.
+const HISTOGRAM_GROUP_SIZE: u32 = 16;
+
+/// Curve mapping the HDR frame into displayable range, the index is the `tonemapper` uniform.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Tonemapper {
+    /// Narkowicz 2015 fit of the ACES reference rendering transform.
+    #[default]
+    AcesFitted,
+    /// Troy Sobotka's AgX with the base look, desaturates bright colors like film.
+    AgX,
+    /// Luminance based Reinhard, keeps hues but flattens highlights.
+    Reinhard,
+    /// Khronos PBR Neutral, leaves base colors under the highlight threshold untouched.
+    PbrNeutral,
+    /// Only clamps, for debugging lighting values.
+    None,
+}
+
+impl Tonemapper {
+    pub fn shader_index(self) -> i32 {
+        match self {
+            Tonemapper::AcesFitted => 0,
+            Tonemapper::AgX => 1,
+            Tonemapper::Reinhard => 2,
+            Tonemapper::PbrNeutral => 3,
+            Tonemapper::None => 4,
+        }
+    }
+
+    /// The next tonemapper, wraps around. Handy for cycling through them with a key.
+    pub fn next(self) -> Self {
+        match self {
+            Tonemapper::AcesFitted => Tonemapper::AgX,
+            Tonemapper::AgX => Tonemapper::Reinhard,
+            Tonemapper::Reinhard => Tonemapper::PbrNeutral,
+            Tonemapper::PbrNeutral => Tonemapper::None,
+            Tonemapper::None => Tonemapper::AcesFitted,
+        }
+    }
+}
+
+/// Eye adaptation from a log luminance histogram of the HDR frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AutoExposureSettings {
+    /// log2 luminance range covered by the histogram, pixels outside are clamped into it.
+    pub min_log_luminance: f32,
+    pub max_log_luminance: f32,
+    /// How fast the exposure follows the scene, per second. Brightening usually adapts faster.
+    pub speed_up: f32,
+    pub speed_down: f32,
+}
+
+impl Default for AutoExposureSettings {
+    fn default() -> Self {
+        Self {
+            min_log_luminance: -8.0,
+            max_log_luminance: 6.0,
+            speed_up: 3.0,
+            speed_down: 1.0,
+        }
+    }
+}
+
+/// Exposure and tonemapping of the final image, set with `Renderer::set_tonemap_settings`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TonemapSettings {
+    pub tonemapper: Tonemapper,
+    /// Linear scale before tonemapping. With auto exposure it is the compensation on top of
+    /// the adapted exposure.
+    pub exposure: f32,
+    /// `None` keeps `exposure` fixed.
+    pub auto_exposure: Option<AutoExposureSettings>,
+}
+
+impl Default for TonemapSettings {
+    fn default() -> Self {
+        Self {
+            tonemapper: Tonemapper::AcesFitted,
+            exposure: 0.8,
+            auto_exposure: None,
+        }
+    }
+}
+
+/// Histogram bucket of a pixel luminance, same as `luminance_histogram.comp`.
+pub fn luminance_histogram_bin(luminance: f32, settings: &AutoExposureSettings) -> usize {
+    if luminance < 0.005 {
+        return 0;
+    }
+    let range = settings.max_log_luminance - settings.min_log_luminance;
+    let t = ((luminance.log2() - settings.min_log_luminance) / range).clamp(0.0, 1.0);
+    (t * (HISTOGRAM_BINS - 2) as f32 + 1.0) as usize
+}
+
+/// Average luminance of a histogram, ignoring the near black bucket, same as `luminance_average.comp`.
+pub fn histogram_average_luminance(histogram: &[u32], settings: &AutoExposureSettings) -> f32 {
+    let (weighted, count) = histogram
+        .iter()
+        .enumerate()
+        .skip(1)
+        .fold((0.0, 0u64), |(weighted, count), (bin, &n)| (weighted + bin as f64 * n as f64, count + n as u64));
+    if count == 0 {
+        return 2f32.powf(settings.min_log_luminance);
+    }
+    let t = ((weighted / count as f64) as f32 - 1.0) / (HISTOGRAM_BINS - 2) as f32;
+    let range = settings.max_log_luminance - settings.min_log_luminance;
+    2f32.powf(settings.min_log_luminance + t * range)
+}
+
+/// Moves the adapted luminance towards the frame average, frame rate independent.
+pub fn adapt_luminance(current: f32, target: f32, dt: f32, settings: &AutoExposureSettings) -> f32 {
+    let speed = if target > current { settings.speed_up } else { settings.speed_down };
+    current + (target - current) * (1.0 - (-dt * speed).exp())
+}
+
+/// Exposure that maps an average luminance to middle grey (Lagarde 2014, saturation based EV100).
+pub fn exposure_from_luminance(luminance: f32) -> f32 {
+    1.0 / (9.6 * luminance.max(1e-4))
+}
+
+/// Compute passes and buffers of the eye adaptation. The adapted luminance never leaves the GPU,
+/// `screen.frag` reads it from the SSBO.
+pub struct AutoExposure {
+    histogram: Shader,
+    average: Shader,
+    histogram_ssbo: SSBO,
+    exposure_ssbo: SSBO,
+}
+
+impl AutoExposure {
+    pub fn new() -> Result<Self, String> {
+        let histogram = Shader::compute_from_source(include_str!("../res/shaders/luminance_histogram.comp").to_string())?;
+        let average = Shader::compute_from_source(include_str!("../res/shaders/luminance_average.comp").to_string())?;
+
+        let histogram_ssbo = SSBO::new();
+        histogram_ssbo.bind();
+        histogram_ssbo.buffer_data_with_usage(&[0u32; HISTOGRAM_BINS], BufferUsage::DynamicDraw);
+        let exposure_ssbo = SSBO::new();
+        exposure_ssbo.bind();
+        // starts adapted to a mid grey scene
+        exposure_ssbo.buffer_data_with_usage(&[0.18f32], BufferUsage::DynamicDraw);
+        exposure_ssbo.unbind();
+
+        Ok(Self { histogram, average, histogram_ssbo, exposure_ssbo })
+    }
+
+    /// Builds the histogram of `source` (the HDR frame) and adapts the luminance over `dt` seconds.
+    /// Leaves the adapted luminance bound for `screen.frag`.
+    pub fn render(&self, source: u32, width: u32, height: u32, dt: f32, settings: &AutoExposureSettings) {
+        unsafe {
+            gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, HISTOGRAM_BINDING, self.histogram_ssbo.id());
+            gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, EXPOSURE_BINDING, self.exposure_ssbo.id());
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, source);
+        }
+
+        let range = settings.max_log_luminance - settings.min_log_luminance;
+        let shader = &self.histogram;
+        shader.bind();
+        if let Some(loc) = shader.get_uniform_location("min_log_luminance") {
+            shader.set_uniform(loc, settings.min_log_luminance);
+        }
+        if let Some(loc) = shader.get_uniform_location("inv_log_luminance_range") {
+            shader.set_uniform(loc, 1.0 / range);
+        }
+        shader.dispatch(width.div_ceil(HISTOGRAM_GROUP_SIZE), height.div_ceil(HISTOGRAM_GROUP_SIZE), 1);
+        unsafe {
+            gl::MemoryBarrier(gl::SHADER_STORAGE_BARRIER_BIT);
+        }
+
+        let shader = &self.average;
+        shader.bind();
+        if let Some(loc) = shader.get_uniform_location("min_log_luminance") {
+            shader.set_uniform(loc, settings.min_log_luminance);
+        }
+        if let Some(loc) = shader.get_uniform_location("log_luminance_range") {
+            shader.set_uniform(loc, range);
+        }
+        if let Some(loc) = shader.get_uniform_location("dt") {
+            shader.set_uniform(loc, dt);
+        }
+        if let Some(loc) = shader.get_uniform_location("speed_up") {
+            shader.set_uniform(loc, settings.speed_up);
+        }
+        if let Some(loc) = shader.get_uniform_location("speed_down") {
+            shader.set_uniform(loc, settings.speed_down);
+        }
+        shader.dispatch(1, 1, 1);
+        unsafe {
+            gl::MemoryBarrier(gl::SHADER_STORAGE_BARRIER_BIT);
+        }
+    }
+}
@@ -2,6 +2,7 @@ use glm::{Mat4, Vec3};
 use num_traits::One;
 use std::cell::{Cell, RefCell};
 
+#[derive(Debug, Clone)]
 pub struct Transform {
     position: Vec3,
     scale: Vec3, // radians
@@ -18,6 +19,31 @@ impl Transform {
         result
     }
     
+    /// Splits a model matrix into position, scale and rotation. Shear, e.g. from a non-uniform
+    /// scale above a rotation, can't be represented and is lost.
+    pub fn from_matrix(m: &Mat4) -> Self {
+        let position = Vec3::new(m[3][0], m[3][1], m[3][2]);
+        let columns = [m[0], m[1], m[2]].map(|c| Vec3::new(c.x, c.y, c.z));
+        let mut scale = columns.map(glm::length);
+        // a mirroring matrix keeps its handedness by flipping one axis
+        if glm::dot(glm::cross(columns[0], columns[1]), columns[2]) < 0.0 {
+            scale[0] = -scale[0];
+        }
+        let r = [0, 1, 2].map(|i| if scale[i] != 0.0 { columns[i] / scale[i] } else { columns[i] });
+        Self::new(position, Vec3::new(scale[0], scale[1], scale[2]), euler_from_rotation(r))
+    }
+
+    /// Angles for `rotation` from a quaternion `[x, y, z, w]`, normalized first.
+    pub fn euler_from_quaternion(q: [f32; 4]) -> Vec3 {
+        let length = q.iter().map(|v| v * v).sum::<f32>().sqrt();
+        let [x, y, z, w] = q.map(|v| v / length);
+        euler_from_rotation([
+            Vec3::new(1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y + z * w), 2.0 * (x * z - y * w)),
+            Vec3::new(2.0 * (x * y - z * w), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z + x * w)),
+            Vec3::new(2.0 * (x * z + y * w), 2.0 * (y * z - x * w), 1.0 - 2.0 * (x * x + y * y)),
+        ])
+    }
+
     pub fn pos(&self) -> Vec3 {
         self.position
     }
@@ -62,4 +88,51 @@ impl Transform {
         m = glm::ext::scale(&m, self.scale);
         *self.model_matrix.borrow_mut() = m;
     }
-}
\ No newline at end of file
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct TransformData {
+    #[serde(with = "crate::serde_glm::vec3", default = "zero_vec3")]
+    position: Vec3,
+    #[serde(with = "crate::serde_glm::vec3", default = "one_vec3")]
+    scale: Vec3,
+    #[serde(with = "crate::serde_glm::vec3", default = "zero_vec3")]
+    rotation: Vec3,
+}
+
+#[cfg(feature = "serde")]
+fn zero_vec3() -> Vec3 { Vec3::new(0.0, 0.0, 0.0) }
+#[cfg(feature = "serde")]
+fn one_vec3() -> Vec3 { Vec3::new(1.0, 1.0, 1.0) }
+
+// only the authored values are stored, the model matrix is rebuilt on load
+#[cfg(feature = "serde")]
+impl serde::Serialize for Transform {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        TransformData {
+            position: self.position,
+            scale: self.scale,
+            rotation: self.rotation,
+        }.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Transform {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = TransformData::deserialize(deserializer)?;
+        Ok(Transform::new(data.position, data.scale, data.rotation))
+    }
+}
+
+// model rotation is Rx * Ry * Rz, r[column].row
+fn euler_from_rotation(r: [Vec3; 3]) -> Vec3 {
+    let sin_y = r[2].x.clamp(-1.0, 1.0);
+    if sin_y.abs() < 0.99999 {
+        Vec3::new((-r[2].y).atan2(r[2].z), sin_y.asin(), (-r[1].x).atan2(r[0].x))
+    } else {
+        // gimbal lock, x and z rotate around the same axis
+        Vec3::new(r[1].z.atan2(r[1].y), std::f32::consts::FRAC_PI_2.copysign(sin_y), 0.0)
+    }
+}
@@ -1,17 +1,18 @@
 use crate::renderer::vertex_array::VAO;
-use glm::{Vec2, Vec3};
+use glm::{Mat4, Vec2, Vec3, Vec4};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 #[repr(C)]
 pub struct Vertex {
     pub v: Vec3,
     pub vn: Vec3,
     pub vt: Vec2,
-    pub tangent: Vec3,
+    /// `w` is the handedness, the bitangent is `cross(vn, tangent.xyz) * w`.
+    pub tangent: Vec4,
 }
 
 impl Vertex {
-    pub fn new(v: Vec3, vn: Vec3, vt: Vec2, tan: Vec3) -> Self {
+    pub fn new(v: Vec3, vn: Vec3, vt: Vec2, tan: Vec4) -> Self {
         Vertex {
             v,
             vn,
@@ -21,22 +22,33 @@ impl Vertex {
     }
 }
 
-pub trait VertexLayout {
+pub trait VertexLayout: Clone {
+    /// Attribute locations used by the vertex, per-instance attributes start right after.
+    const ATTRIBUTE_COUNT: u32;
+
     fn setup_attributes(vao: &VAO);
+    /// Position used for bounding volumes.
+    fn position(&self) -> Vec3;
 }
 
 impl VertexLayout for Vertex {
+    const ATTRIBUTE_COUNT: u32 = 4;
+
     fn setup_attributes(vao: &VAO) {
-        vao.vertex_attrib_pointer(0, 3, 11, 0);
-        vao.vertex_attrib_pointer(1, 3, 11, 3);
-        vao.vertex_attrib_pointer(2, 2, 11, 6);
-        vao.vertex_attrib_pointer(3, 3, 11, 8);
+        vao.vertex_attrib_pointer(0, 3, 12, 0);
+        vao.vertex_attrib_pointer(1, 3, 12, 3);
+        vao.vertex_attrib_pointer(2, 2, 12, 6);
+        vao.vertex_attrib_pointer(3, 4, 12, 8);
+    }
+
+    fn position(&self) -> Vec3 {
+        self.v
     }
 }
 
 
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 #[repr(C)]
 pub struct ScreenVertex {
     pub v: Vec2,
@@ -54,8 +66,36 @@ impl ScreenVertex {
 
 
 impl VertexLayout for ScreenVertex {
+    const ATTRIBUTE_COUNT: u32 = 2;
+
     fn setup_attributes(vao: &VAO) {
         vao.vertex_attrib_pointer(0, 2, 4, 0);
         vao.vertex_attrib_pointer(1, 2, 4, 2);
     }
+
+    fn position(&self) -> Vec3 {
+        Vec3::new(self.v.x, self.v.y, 0.0)
+    }
+}
+
+
+/// Per-instance data for `Mesh::render_instanced`, read by the shaders as
+/// `layout (location = 4) in mat4 in_instance_model`.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct InstanceData {
+    pub model: Mat4,
+}
+
+impl InstanceData {
+    pub fn new(model: Mat4) -> Self {
+        Self { model }
+    }
+
+    /// A mat4 takes four vec4 locations starting at `first_location`.
+    pub fn setup_attributes(vao: &VAO, first_location: u32) {
+        for column in 0..4 {
+            vao.instance_attrib_pointer(first_location + column, 4, 16, column as usize * 4);
+        }
+    }
 }
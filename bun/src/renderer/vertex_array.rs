@@ -39,6 +39,14 @@ impl VAO {
             gl::EnableVertexAttribArray(index);
         }
     }
+    
+    /// Like `vertex_attrib_pointer`, but the attribute advances once per instance.
+    pub fn instance_attrib_pointer(&self, index: u32, num_components: i32, stride: usize, offset: usize) {
+        self.vertex_attrib_pointer(index, num_components, stride, offset);
+        unsafe {
+            gl::VertexAttribDivisor(index, 1);
+        }
+    }
 }
 
 impl Drop for VAO {
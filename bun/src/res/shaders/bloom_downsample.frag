@@ -0,0 +1,54 @@
+#version 460 core
+
+in vec2 frag_uv;
+
+layout(binding = 0) uniform sampler2D source;
+uniform vec2 source_texel;
+uniform bool karis_average = false;
+
+out vec3 out_col;
+
+float luma(vec3 c) {
+    return dot(c, vec3(0.2126, 0.7152, 0.0722));
+}
+
+float karis_weight(vec3 c) {
+    return 1.0 / (1.0 + luma(c));
+}
+
+// 13 tap downsample from Call of Duty: Advanced Warfare, five overlapping 2x2 boxes
+void main() {
+    vec2 d = source_texel;
+    vec3 a = texture(source, frag_uv + vec2(-2.0, 2.0) * d).rgb;
+    vec3 b = texture(source, frag_uv + vec2(0.0, 2.0) * d).rgb;
+    vec3 c = texture(source, frag_uv + vec2(2.0, 2.0) * d).rgb;
+    vec3 e = texture(source, frag_uv + vec2(-2.0, 0.0) * d).rgb;
+    vec3 f = texture(source, frag_uv).rgb;
+    vec3 g = texture(source, frag_uv + vec2(2.0, 0.0) * d).rgb;
+    vec3 h = texture(source, frag_uv + vec2(-2.0, -2.0) * d).rgb;
+    vec3 i = texture(source, frag_uv + vec2(0.0, -2.0) * d).rgb;
+    vec3 j = texture(source, frag_uv + vec2(2.0, -2.0) * d).rgb;
+    vec3 k = texture(source, frag_uv + vec2(-1.0, 1.0) * d).rgb;
+    vec3 l = texture(source, frag_uv + vec2(1.0, 1.0) * d).rgb;
+    vec3 m = texture(source, frag_uv + vec2(-1.0, -1.0) * d).rgb;
+    vec3 n = texture(source, frag_uv + vec2(1.0, -1.0) * d).rgb;
+
+    vec3 boxes[5] = vec3[](
+        (a + b + e + f) * 0.25,
+        (b + c + f + g) * 0.25,
+        (e + f + h + i) * 0.25,
+        (f + g + i + j) * 0.25,
+        (k + l + m + n) * 0.25
+    );
+    float box_weights[5] = float[](0.125, 0.125, 0.125, 0.125, 0.5);
+
+    vec3 result = vec3(0.0);
+    float total = 0.0;
+    for (int box = 0; box < 5; box++) {
+        float weight = box_weights[box] * (karis_average ? karis_weight(boxes[box]) : 1.0);
+        result += boxes[box] * weight;
+        total += weight;
+    }
+    // NaNs or negative values from the scene would spread over the whole chain
+    out_col = max(result / total, 0.0001);
+}
@@ -0,0 +1,23 @@
+#version 460 core
+
+in vec2 frag_uv;
+
+layout(binding = 0) uniform sampler2D source;
+uniform float filter_radius = 0.005;
+
+out vec3 out_col;
+
+// 3x3 tent filter, additively blended onto the next larger mip
+void main() {
+    float r = filter_radius;
+    vec3 result = texture(source, frag_uv).rgb * 4.0;
+    result += (texture(source, frag_uv + vec2(-r, 0.0)).rgb
+        + texture(source, frag_uv + vec2(r, 0.0)).rgb
+        + texture(source, frag_uv + vec2(0.0, -r)).rgb
+        + texture(source, frag_uv + vec2(0.0, r)).rgb) * 2.0;
+    result += texture(source, frag_uv + vec2(-r, -r)).rgb
+        + texture(source, frag_uv + vec2(r, -r)).rgb
+        + texture(source, frag_uv + vec2(-r, r)).rgb
+        + texture(source, frag_uv + vec2(r, r)).rgb;
+    out_col = result / 16.0;
+}
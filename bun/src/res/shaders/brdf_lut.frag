@@ -0,0 +1,52 @@
+#version 460 core
+
+#define PI 3.1415926535897932384626433832795
+
+in vec2 frag_uv;
+
+uniform int sample_count = 512;
+
+out vec2 out_col;
+
+vec2 hammersley(uint i, uint n) {
+    uint bits = bitfieldReverse(i);
+    return vec2(float(i) / float(n), float(bits) * 2.3283064365386963e-10);
+}
+
+vec3 importance_sample_ggx(vec2 xi, float a) {
+    float phi = 2.0 * PI * xi.x;
+    float cos_theta = sqrt((1.0 - xi.y) / (1.0 + (a * a - 1.0) * xi.y));
+    float sin_theta = sqrt(1.0 - cos_theta * cos_theta);
+    return vec3(cos(phi) * sin_theta, sin(phi) * sin_theta, cos_theta);
+}
+
+// k = a / 2 for IBL instead of (r + 1)^2 / 8 for direct light
+float geometry_schlick_ggx(float N_dot_X, float roughness) {
+    float k = roughness * roughness / 2.0;
+    return N_dot_X / (N_dot_X * (1.0 - k) + k);
+}
+
+// scale (x) and bias (y) of F0 in the split sum, x = N dot V, y = roughness
+void main() {
+    float N_dot_V = max(frag_uv.x, 0.001);
+    float roughness = frag_uv.y;
+    float a = roughness * roughness;
+    vec3 V = vec3(sqrt(1.0 - N_dot_V * N_dot_V), 0.0, N_dot_V);
+
+    vec2 result = vec2(0.0);
+    for (int i = 0; i < sample_count; i++) {
+        vec3 H = importance_sample_ggx(hammersley(uint(i), uint(sample_count)), a);
+        vec3 L = normalize(2.0 * dot(V, H) * H - V);
+        float N_dot_L = max(L.z, 0.0);
+        if (N_dot_L <= 0.0) {
+            continue;
+        }
+        float N_dot_H = max(H.z, 0.0);
+        float V_dot_H = max(dot(V, H), 0.0);
+        float G = geometry_schlick_ggx(N_dot_V, roughness) * geometry_schlick_ggx(N_dot_L, roughness);
+        float G_vis = G * V_dot_H / (N_dot_H * N_dot_V);
+        float Fc = pow(1.0 - V_dot_H, 5.0);
+        result += vec2((1.0 - Fc) * G_vis, Fc * G_vis);
+    }
+    out_col = result / float(sample_count);
+}
@@ -3,10 +3,13 @@
 layout (location = 0) in vec3 in_pos;
 layout (location = 1) in vec3 in_normal;
 layout (location = 2) in vec2 in_uv;
+layout (location = 4) in mat4 in_instance_model;
 
 uniform mat4 proj_mat;
 uniform mat4 view_mat;
 uniform mat4 model_mat;
+// set by the renderer for instanced draws, the model matrix then comes per instance
+uniform bool instanced;
 
 uniform vec4 tint;
 
@@ -16,12 +19,13 @@ out vec2 frag_uv;
 out vec3 frag_pos;
 
 void main() {
-    gl_Position = proj_mat * view_mat * model_mat * vec4(in_pos.xyz, 1.0);
+    mat4 model = instanced ? in_instance_model : model_mat;
+    gl_Position = proj_mat * view_mat * model * vec4(in_pos.xyz, 1.0);
     frag_col = vec4(1.0) * tint;
 
-    mat3 normal_mat = transpose(inverse(mat3(model_mat)));
+    mat3 normal_mat = transpose(inverse(mat3(model)));
     frag_normal = normalize(normal_mat * in_normal);
 
     frag_uv = in_uv;
-    frag_pos = vec3(model_mat * vec4(in_pos, 1.0));
+    frag_pos = vec3(model * vec4(in_pos, 1.0));
 }
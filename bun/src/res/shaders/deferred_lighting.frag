@@ -0,0 +1,63 @@
+#version 460
+
+in vec2 frag_uv;
+
+// see GBUFFER_LAYOUT in deferred.rs
+layout(binding = 0) uniform sampler2D gbuffer_albedo;
+layout(binding = 1) uniform sampler2D gbuffer_normal;
+layout(binding = 2) uniform sampler2D gbuffer_material;
+layout(binding = 3) uniform sampler2D gbuffer_emissive;
+layout(binding = 4) uniform sampler2D gbuffer_depth;
+
+uniform vec3 camera_pos;
+uniform mat4 inverse_view_proj;
+
+// from the normal alpha of the G-buffer
+bool receive_shadows = true;
+
+#include "bun/lighting.glsl"
+
+out vec4 out_col;
+
+// the lighting of pbr.frag, with the surface read back from the G-buffer
+void main() {
+    float depth = texture(gbuffer_depth, frag_uv).r;
+    if (depth >= 1.0) {
+        // nothing was drawn, keep the clear color for the sky pass
+        discard;
+    }
+
+    vec4 world = inverse_view_proj * vec4(vec3(frag_uv, depth) * 2.0 - 1.0, 1.0);
+    vec3 frag_pos = world.xyz / world.w;
+
+    vec3 albedo = texture(gbuffer_albedo, frag_uv).rgb;
+    vec4 normal_shadows = texture(gbuffer_normal, frag_uv);
+    vec3 N = normalize(normal_shadows.xyz);
+    receive_shadows = normal_shadows.w > 0.5;
+    vec2 material = texture(gbuffer_material, frag_uv).rg;
+    float roughness = material.r;
+    float metallic = material.g;
+    vec3 emissive = texture(gbuffer_emissive, frag_uv).rgb;
+
+    vec3 V = normalize(camera_pos - frag_pos); // view direction
+
+    vec3 Lo = vec3(0.0);
+    for (int i = 0; i < light_count; i++) {
+        vec3 L; // light direction
+        vec3 radiance = light_radiance(lights[i], frag_pos, L);
+        if (dot(N, L) <= 0.0 || dot(radiance, radiance) <= 0.0) {
+            continue;
+        }
+        radiance *= light_shadow(i, frag_pos, N, L);
+        Lo += direct_light(N, V, L, radiance, albedo, roughness, metallic);
+    }
+
+    // material occlusion is stored in the albedo alpha
+    float ao = texture(gbuffer_albedo, frag_uv).a;
+    if (has_ssao) {
+        ao *= texture(ssao_texture, frag_uv).r;
+    }
+    vec3 ambient = ambient_light(N, V, albedo, roughness, metallic, ao);
+
+    out_col = vec4(Lo + ambient + emissive, 1.0);
+}
@@ -0,0 +1,30 @@
+#version 460 core
+
+#define PI 3.1415926535897932384626433832795
+
+in vec2 frag_uv;
+
+uniform sampler2D equirect;
+uniform int face;
+
+out vec4 out_col;
+
+// direction through frag_uv of the cube face in GL order (+X, -X, +Y, -Y, +Z, -Z)
+vec3 cube_direction(vec2 uv) {
+    vec2 c = uv * 2.0 - 1.0;
+    switch (face) {
+        case 0: return normalize(vec3(1.0, -c.y, -c.x));
+        case 1: return normalize(vec3(-1.0, -c.y, c.x));
+        case 2: return normalize(vec3(c.x, 1.0, c.y));
+        case 3: return normalize(vec3(c.x, -1.0, -c.y));
+        case 4: return normalize(vec3(c.x, -c.y, 1.0));
+        default: return normalize(vec3(-c.x, -c.y, -1.0));
+    }
+}
+
+void main() {
+    vec3 d = cube_direction(frag_uv);
+    // the image is uploaded top row first, so v = 0 is straight up
+    vec2 uv = vec2(atan(d.z, d.x) / (2.0 * PI) + 0.5, 0.5 - asin(clamp(d.y, -1.0, 1.0)) / PI);
+    out_col = vec4(texture(equirect, uv).rgb, 1.0);
+}
@@ -0,0 +1,10 @@
+#version 460 core
+
+out vec2 frag_uv;
+
+// fullscreen triangle without any vertex buffer
+void main() {
+    vec2 pos = vec2((gl_VertexID << 1) & 2, gl_VertexID & 2);
+    frag_uv = pos;
+    gl_Position = vec4(pos * 2.0 - 1.0, 0.0, 1.0);
+}
@@ -0,0 +1,40 @@
+#version 460
+
+in vec4 frag_col;
+in vec2 frag_uv;
+in vec3 frag_pos;
+in mat3 frag_tbn;
+
+uniform vec3 camera_pos;
+uniform vec2 texture_scale = vec2(1.0);
+uniform bool receive_shadows = true;
+
+#include "bun/material.glsl"
+#include "bun/surface.glsl"
+
+// see GBUFFER_LAYOUT in deferred.rs
+layout(location = 0) out vec4 out_albedo;
+layout(location = 1) out vec4 out_normal;
+layout(location = 2) out vec2 out_material;
+layout(location = 3) out vec4 out_emissive;
+
+// same surface as pbr.frag, only written out instead of lit. Without the lights the height
+// map only offsets the texture coordinates, there is no parallax self shadowing.
+void main() {
+    vec2 uv = frag_uv * texture_scale;
+    if (height_has_texture == 1) {
+        float depth;
+        uv = parallax_uv(uv, transpose(frag_tbn) * normalize(camera_pos - frag_pos), dFdx(uv), dFdy(uv), depth);
+    }
+
+    Surface surface = material_surface(fract(uv), frag_uv, frag_tbn, frag_col);
+    // only opaque and cutout materials end up here, blended ones are drawn forward
+    if (alpha_mode == ALPHA_CUTOUT && surface.alpha < alpha_cutoff) {
+        discard;
+    }
+
+    out_albedo = vec4(surface.albedo, surface.occlusion);
+    out_normal = vec4(surface.normal, receive_shadows ? 1.0 : 0.0);
+    out_material = vec2(surface.roughness, surface.metallic);
+    out_emissive = vec4(surface.emission, 1.0);
+}
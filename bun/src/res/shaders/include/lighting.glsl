@@ -0,0 +1,257 @@
+// lights, shadows and image based lighting of the forward and the deferred path. The including
+// shader declares `uniform vec3 camera_pos` and a `receive_shadows` bool.
+#define PI 3.1415926535
+
+#define LIGHT_DIRECTIONAL 0
+#define LIGHT_POINT 1
+#define LIGHT_SPOT 2
+
+struct Light {
+    vec4 position_type;   // xyz position, w type
+    vec4 direction_range; // xyz direction, w range
+    vec4 color_intensity; // rgb color, a intensity
+    vec4 cone;            // x cos inner angle, y cos outer angle
+};
+
+layout(std430, binding = 3) readonly buffer LightBuffer {
+    Light lights[];
+};
+uniform int light_count = 0;
+
+#define MAX_CASCADES 4
+
+// cascaded shadow map of the light at shadow_light_index, set by the renderer
+layout(binding = 8) uniform sampler2DArrayShadow shadow_map;
+uniform mat4 view_mat;
+uniform int shadow_light_index = -1;
+uniform int cascade_count = 0;
+uniform mat4 cascade_view_proj[MAX_CASCADES];
+uniform vec4 cascade_splits;
+uniform vec4 cascade_texel_size;
+uniform float shadow_depth_bias = 0.0005;
+uniform float shadow_normal_bias = 1.5;
+uniform int shadow_pcf_radius = 1;
+
+#define MAX_POINT_SHADOWS 4
+
+// cube maps of linear light distance / far, slot i belongs to point_shadow_light_index[i] (-1 if unused)
+layout(binding = 9) uniform samplerCube point_shadow_maps[MAX_POINT_SHADOWS];
+uniform int point_shadow_light_index[MAX_POINT_SHADOWS] = int[](-1, -1, -1, -1);
+uniform float point_shadow_far[MAX_POINT_SHADOWS];
+uniform float point_shadow_bias[MAX_POINT_SHADOWS];
+
+// image based lighting, set by the renderer when it has an environment
+layout(binding = 13) uniform samplerCube env_prefiltered;
+layout(binding = 14) uniform sampler2D brdf_lut;
+uniform bool has_environment = false;
+uniform vec3 irradiance_sh[9];
+uniform float prefilter_max_lod = 4.0;
+uniform float environment_intensity = 1.0;
+
+// screen space ambient occlusion at the same resolution as the frame, set by the renderer
+layout(binding = 15) uniform sampler2D ssao_texture;
+uniform bool has_ssao = false;
+
+vec3 fresnel_schlick(float cos_theta, vec3 F0) {
+    return F0 + (1.0 - F0) * pow(1.0 - cos_theta, 5.0);
+}
+
+float distribution_ggx(vec3 N, vec3 H, float roughness) {
+    float a = roughness * roughness;
+    float a2 = a * a;
+    float N_dot_H = max(dot(N, H), 0.0);
+    float N_dot_H2 = N_dot_H * N_dot_H;
+
+    float denom = (N_dot_H2 * (a2 - 1.0) + 1.0);
+    return a2 / (PI * denom * denom);
+}
+
+float geometry_schlick_ggx(float N_dot_V, float roughness) {
+    float r = roughness + 1.0;
+    float k = (r * r) / 8.0;
+    return N_dot_V / (N_dot_V * (1.0 - k) + k);
+}
+
+float geometry_smith(vec3 N, vec3 V, vec3 L, float roughness) {
+    float N_dot_V = max(dot(N, V), 0.0);
+    float N_dot_L = max(dot(N, L), 0.0);
+    float ggx_V = geometry_schlick_ggx(N_dot_V, roughness);
+    float ggx_L = geometry_schlick_ggx(N_dot_L, roughness);
+    return ggx_V * ggx_L;
+}
+
+// smooth falloff that reaches exactly zero at the range
+float range_attenuation(float dist, float range) {
+    float ratio = dist / max(range, 0.0001);
+    float window = clamp(1.0 - ratio * ratio * ratio * ratio, 0.0, 1.0);
+    return window * window / (dist * dist + 1.0);
+}
+
+vec3 light_radiance(Light light, vec3 pos, out vec3 L) {
+    int type = int(light.position_type.w);
+    vec3 radiance = light.color_intensity.rgb * light.color_intensity.a;
+    if (type == LIGHT_DIRECTIONAL) {
+        L = normalize(-light.direction_range.xyz);
+        return radiance;
+    }
+
+    vec3 to_light = light.position_type.xyz - pos;
+    float dist = length(to_light);
+    L = to_light / max(dist, 0.0001);
+    radiance *= range_attenuation(dist, light.direction_range.w);
+
+    if (type == LIGHT_SPOT) {
+        float cos_theta = dot(-L, normalize(light.direction_range.xyz));
+        radiance *= smoothstep(light.cone.y, light.cone.x, cos_theta);
+    }
+    return radiance;
+}
+
+vec3 fresnel_schlick_roughness(float cos_theta, vec3 F0, float roughness) {
+    return F0 + (max(vec3(1.0 - roughness), F0) - F0) * pow(clamp(1.0 - cos_theta, 0.0, 1.0), 5.0);
+}
+
+// cosine convolved radiance, same basis as `sh_basis` in environment.rs
+vec3 sh_irradiance(vec3 n) {
+    return irradiance_sh[0] * 0.282095
+        + irradiance_sh[1] * 0.488603 * n.y
+        + irradiance_sh[2] * 0.488603 * n.z
+        + irradiance_sh[3] * 0.488603 * n.x
+        + irradiance_sh[4] * 1.092548 * n.x * n.y
+        + irradiance_sh[5] * 1.092548 * n.y * n.z
+        + irradiance_sh[6] * 0.315392 * (3.0 * n.z * n.z - 1.0)
+        + irradiance_sh[7] * 1.092548 * n.x * n.z
+        + irradiance_sh[8] * 0.546274 * (n.x * n.x - n.y * n.y);
+}
+
+// 1 is fully lit, 0 fully in shadow
+float shadow_factor(vec3 pos, vec3 normal, vec3 L) {
+    if (!receive_shadows || cascade_count == 0) {
+        return 1.0;
+    }
+
+    float view_depth = -(view_mat * vec4(pos, 1.0)).z;
+    int cascade = -1;
+    for (int i = 0; i < cascade_count; i++) {
+        if (view_depth < cascade_splits[i]) {
+            cascade = i;
+            break;
+        }
+    }
+    if (cascade < 0) {
+        return 1.0;
+    }
+
+    // push the lookup out of the surface, more at grazing angles where acne shows up first
+    float N_dot_L = clamp(dot(normal, L), 0.0, 1.0);
+    vec3 offset_pos = pos + normal * shadow_normal_bias * cascade_texel_size[cascade] * (1.0 - N_dot_L);
+    vec4 light_clip = cascade_view_proj[cascade] * vec4(offset_pos, 1.0);
+    vec3 coords = light_clip.xyz / light_clip.w * 0.5 + 0.5;
+    if (coords.z > 1.0) {
+        return 1.0;
+    }
+
+    float depth = coords.z - shadow_depth_bias;
+    vec2 texel = 1.0 / vec2(textureSize(shadow_map, 0).xy);
+    float lit = 0.0;
+    int taps = 0;
+    for (int x = -shadow_pcf_radius; x <= shadow_pcf_radius; x++) {
+        for (int y = -shadow_pcf_radius; y <= shadow_pcf_radius; y++) {
+            lit += texture(shadow_map, vec4(coords.xy + vec2(x, y) * texel, float(cascade), depth));
+            taps++;
+        }
+    }
+    return lit / float(taps);
+}
+
+const vec3 point_shadow_offsets[20] = vec3[](
+    vec3(1, 1, 1), vec3(1, -1, 1), vec3(-1, -1, 1), vec3(-1, 1, 1),
+    vec3(1, 1, -1), vec3(1, -1, -1), vec3(-1, -1, -1), vec3(-1, 1, -1),
+    vec3(1, 1, 0), vec3(1, -1, 0), vec3(-1, -1, 0), vec3(-1, 1, 0),
+    vec3(1, 0, 1), vec3(-1, 0, 1), vec3(1, 0, -1), vec3(-1, 0, -1),
+    vec3(0, 1, 1), vec3(0, -1, 1), vec3(0, -1, -1), vec3(0, 1, -1)
+);
+
+// 1 is fully lit, 0 fully in shadow
+float point_shadow_factor(int slot, vec3 pos, vec3 light_pos) {
+    vec3 to_frag = pos - light_pos;
+    float current = length(to_frag);
+    float far = point_shadow_far[slot];
+    if (!receive_shadows || current >= far) {
+        return 1.0;
+    }
+
+    // the filter grows with the distance to the camera, close shadows stay crisp
+    float disk_radius = (1.0 + length(camera_pos - pos) / far) / 50.0;
+    float bias = point_shadow_bias[slot];
+    float lit = 0.0;
+    for (int i = 0; i < 20; i++) {
+        float closest = texture(point_shadow_maps[slot], to_frag + point_shadow_offsets[i] * disk_radius).r * far;
+        lit += current - bias > closest ? 0.0 : 1.0;
+    }
+    return lit / 20.0;
+}
+
+// cascade and point shadows of light i, `normal` is the geometric normal the cascade lookup
+// is pushed out along
+float light_shadow(int i, vec3 pos, vec3 normal, vec3 L) {
+    float lit = i == shadow_light_index ? shadow_factor(pos, normal, L) : 1.0;
+    for (int slot = 0; slot < MAX_POINT_SHADOWS; slot++) {
+        if (point_shadow_light_index[slot] == i) {
+            lit *= point_shadow_factor(slot, pos, lights[i].position_type.xyz);
+        }
+    }
+    return lit;
+}
+
+// Cook-Torrance specular and Lambert diffuse of one light
+vec3 direct_light(vec3 N, vec3 V, vec3 L, vec3 radiance, vec3 albedo, float roughness, float metallic) {
+    vec3 F0 = mix(vec3(0.04), albedo, metallic);
+    float N_dot_L = max(dot(N, L), 0.0);
+    float N_dot_V = max(dot(N, V), 0.0);
+    vec3 H = normalize(V + L); // half way vector
+
+    vec3 F = fresnel_schlick(max(dot(H, V), 0.0), F0);
+    float D = distribution_ggx(N, H, roughness);
+    float G = geometry_smith(N, V, L, roughness);
+
+    vec3 specular = (D * G * F) / (4.0 * N_dot_L * N_dot_V + 0.0001);
+
+    vec3 kD = (1.0 - F) * (1.0 - metallic);
+    vec3 diffuse = kD * albedo / PI;
+
+    return (diffuse + specular) * radiance * N_dot_L;
+}
+
+// ambient light from the environment, or a flat sky and ground without one. `occlusion`
+// darkens both terms.
+vec3 ambient_light(vec3 N, vec3 V, vec3 albedo, float roughness, float metallic, float occlusion) {
+    vec3 F0 = mix(vec3(0.04), albedo, metallic);
+    float N_dot_V = max(dot(N, V), 0.0);
+
+    vec3 ambient_diffuse;
+    vec3 ambient_specular;
+    if (has_environment) {
+        vec3 F_env = fresnel_schlick_roughness(N_dot_V, F0, roughness);
+        vec3 kD = (1.0 - F_env) * (1.0 - metallic);
+        ambient_diffuse = kD * albedo * max(sh_irradiance(N), 0.0) / PI;
+
+        // split sum: prefiltered radiance times the BRDF scale and bias of F0
+        vec3 R = reflect(-V, N);
+        vec3 prefiltered = textureLod(env_prefiltered, R, roughness * prefilter_max_lod).rgb;
+        vec2 brdf = texture(brdf_lut, vec2(N_dot_V, roughness)).rg;
+        ambient_specular = prefiltered * (F_env * brdf.x + brdf.y);
+
+        ambient_diffuse *= environment_intensity;
+        ambient_specular *= environment_intensity;
+    } else {
+        vec3 sky_color    = vec3(0.25, 0.3, 0.4);
+        vec3 ground_color = vec3(0.05, 0.04, 0.03);
+        float hemi = N.y * 0.5 + 0.5;
+        ambient_diffuse = mix(ground_color, sky_color, hemi) * albedo * (1.0 - metallic);
+
+        vec3 F_env = fresnel_schlick(N_dot_V, F0);
+        ambient_specular = vec3(0.5) * F_env * (1.0 - roughness);
+    }
+    return (ambient_diffuse + ambient_specular) * occlusion;
+}
@@ -0,0 +1,49 @@
+// `MaterialUBO` in material.rs, shared by every stage of the pbr programs and the G-buffer pass
+layout(std140, binding = 2) uniform MaterialUBO {
+    vec4 albedo_color;
+    vec4 emissive_color;
+
+    float metallic_value;
+    float roughness_value;
+    float normal_scale_value;
+
+    int albedo_has_texture;
+    int normal_has_texture;
+    int emissive_has_texture;
+    int metallic_has_texture;
+    int roughness_has_texture;
+
+    int alpha_mode;
+    float alpha_cutoff;
+
+    int occlusion_has_texture;
+    // channel of the texture the scalar maps are read from, they can share one packed texture
+    int roughness_channel;
+    int metallic_channel;
+    int occlusion_channel;
+    float occlusion_strength;
+
+    int height_has_texture;
+    float height_scale;
+    int height_min_layers;
+    int height_max_layers;
+    int height_self_shadowing;
+
+    int displacement_has_texture;
+    float displacement_scale;
+    float tessellation_edge_length;
+    float tessellation_max_level;
+
+    vec4 splat_tiling;
+    vec4 splat_roughness;
+    int splat_has_texture;
+    int splat_layer_count;
+    int splat_normal_has_texture;
+};
+
+// `BlendMode` in material.rs
+#define ALPHA_OPAQUE 0
+#define ALPHA_CUTOUT 1
+#define ALPHA_BLEND 2
+#define ALPHA_ADDITIVE 3
+#define ALPHA_PREMULTIPLIED 4
@@ -0,0 +1,155 @@
+// material textures and the surface they describe, shared by pbr.frag and gbuffer.frag.
+// Needs bun/material.glsl.
+layout(binding = 0) uniform sampler2D tex_albedo;
+layout(binding = 1) uniform sampler2D tex_normal;
+layout(binding = 2) uniform sampler2D tex_roughness;
+layout(binding = 3) uniform sampler2D tex_metallic;
+layout(binding = 4) uniform sampler2D tex_emissive;
+layout(binding = 5) uniform sampler2D tex_occlusion;
+layout(binding = 6) uniform sampler2D tex_height;
+layout(binding = 7) uniform sampler2D tex_splat;
+// SPLAT_ALBEDO_UNIT and SPLAT_NORMAL_UNIT in renderer.rs
+layout(binding = 16) uniform sampler2DArray tex_splat_albedo;
+layout(binding = 17) uniform sampler2DArray tex_splat_normal;
+
+// binary search steps between the last two layers of the parallax ray march
+#define PARALLAX_REFINEMENT_STEPS 5
+
+// depth below the top of the height field, 0 at white
+float height_depth(vec2 uv, vec2 dx, vec2 dy) {
+    return 1.0 - textureGrad(tex_height, uv, dx, dy).r;
+}
+
+// steps the tangent space view ray down through the height field until it is below the
+// surface, then narrows the hit down between the last two layers
+vec2 parallax_uv(vec2 uv, vec3 V_ts, vec2 dx, vec2 dy, out float depth) {
+    float layers = mix(float(height_max_layers), float(height_min_layers), abs(V_ts.z));
+    float layer_depth = 1.0 / layers;
+    vec2 delta = V_ts.xy / max(V_ts.z, 0.05) * height_scale * layer_depth;
+
+    depth = 0.0;
+    float map_depth = height_depth(uv, dx, dy);
+    for (int i = 0; i < height_max_layers && depth < map_depth; i++) {
+        uv -= delta;
+        depth += layer_depth;
+        map_depth = height_depth(uv, dx, dy);
+    }
+    if (depth == 0.0) {
+        return uv;
+    }
+
+    for (int i = 0; i < PARALLAX_REFINEMENT_STEPS; i++) {
+        delta *= 0.5;
+        layer_depth *= 0.5;
+        if (depth < map_depth) {
+            uv -= delta;
+            depth += layer_depth;
+        } else {
+            uv += delta;
+            depth -= layer_depth;
+        }
+        map_depth = height_depth(uv, dx, dy);
+    }
+    return uv;
+}
+
+// 1 is lit, less where the height field between the parallax hit and the light blocks it,
+// softer for blockers further away
+float parallax_shadow(vec2 uv, float depth, vec3 L_ts, vec2 dx, vec2 dy) {
+    if (L_ts.z <= 0.0 || depth <= 0.0) {
+        return 1.0;
+    }
+    float layers = mix(float(height_max_layers), float(height_min_layers), L_ts.z);
+    float layer_depth = depth / layers;
+    vec2 delta = L_ts.xy / L_ts.z * height_scale * layer_depth;
+
+    float blocked = 0.0;
+    for (int i = 1; i < height_max_layers && depth > 0.0; i++) {
+        uv += delta;
+        depth -= layer_depth;
+        float below = depth - height_depth(uv, dx, dy);
+        blocked = max(blocked, below * (1.0 - float(i) / layers));
+    }
+    return 1.0 - clamp(blocked * 16.0, 0.0, 1.0);
+}
+
+// layer weights from the splat map, only the first `splat_layer_count` channels count
+vec4 splat_weights(vec2 uv) {
+    vec4 weights = texture(tex_splat, uv);
+    weights *= vec4(lessThan(ivec4(0, 1, 2, 3), ivec4(splat_layer_count)));
+    float sum = weights.x + weights.y + weights.z + weights.w;
+    return sum > 0.0 ? weights / sum : vec4(1.0, 0.0, 0.0, 0.0);
+}
+
+struct Surface {
+    vec3 albedo;
+    float alpha;
+    vec3 normal;
+    float roughness;
+    float metallic;
+    // rgb color times strength
+    vec3 emission;
+    // material occlusion only, without SSAO
+    float occlusion;
+};
+
+// the material at `uv`, which already has the parallax offset and repeats in 0..1. Splat layers
+// use `mesh_uv`, the weights span the whole mesh.
+Surface material_surface(vec2 uv, vec2 mesh_uv, mat3 tbn, vec4 color) {
+    Surface surface;
+
+    // with a texture the color is white and only carries the alpha factor
+    vec4 base = albedo_has_texture == 1
+    ? texture(tex_albedo, uv) * albedo_color * color
+    : albedo_color * color;
+    surface.albedo = clamp(base.rgb, 0.0, 0.8);
+    surface.alpha = base.a;
+
+    surface.normal = normalize(tbn[2]);
+    if (normal_has_texture == 1) {
+        vec3 n = texture(tex_normal, uv).xyz;
+        n = n * 2.0 - 1.0;
+        n.xy *= normal_scale_value;
+        surface.normal = normalize(tbn * n);
+    }
+
+    surface.roughness = roughness_has_texture == 1
+    ? texture(tex_roughness, uv)[roughness_channel]
+    : roughness_value;
+
+    surface.metallic = metallic_has_texture == 1
+    ? texture(tex_metallic, uv)[metallic_channel]
+    : metallic_value;
+
+    if (splat_has_texture == 1) {
+        vec4 weights = splat_weights(mesh_uv);
+        vec3 splat_albedo = vec3(0.0);
+        vec3 splat_normal = vec3(0.0);
+        for (int i = 0; i < splat_layer_count; i++) {
+            vec3 layer_uv = vec3(mesh_uv * splat_tiling[i], float(i));
+            splat_albedo += texture(tex_splat_albedo, layer_uv).rgb * weights[i];
+            if (splat_normal_has_texture == 1) {
+                splat_normal += (texture(tex_splat_normal, layer_uv).xyz * 2.0 - 1.0) * weights[i];
+            }
+        }
+        surface.albedo = clamp(splat_albedo * albedo_color.rgb, 0.0, 0.8);
+        if (splat_normal_has_texture == 1) {
+            splat_normal.xy *= normal_scale_value;
+            surface.normal = normalize(tbn * splat_normal);
+        }
+        surface.roughness = dot(splat_roughness, weights);
+    }
+    surface.roughness = clamp(surface.roughness, 0.04, 1.0);
+    surface.metallic = clamp(surface.metallic, 0.0, 1.0);
+
+    // rgb color, a strength
+    surface.emission = emissive_color.rgb * emissive_color.a;
+    if (emissive_has_texture == 1) {
+        surface.emission *= texture(tex_emissive, uv).rgb;
+    }
+
+    surface.occlusion = occlusion_has_texture == 1
+    ? mix(1.0, texture(tex_occlusion, uv)[occlusion_channel], occlusion_strength)
+    : 1.0;
+    return surface;
+}
@@ -0,0 +1,12 @@
+#version 460
+
+in vec3 frag_pos;
+
+uniform vec3 light_pos;
+uniform float light_far;
+
+// linear distance instead of the perspective depth, the lookup in the PBR shader
+// only has a direction and compares distances
+void main() {
+    gl_FragDepth = length(frag_pos - light_pos) / light_far;
+}
@@ -0,0 +1,17 @@
+#version 460
+
+layout (location = 0) in vec3 in_pos;
+layout (location = 4) in mat4 in_instance_model;
+
+uniform mat4 light_view_proj;
+uniform mat4 model_mat;
+uniform bool instanced;
+
+out vec3 frag_pos;
+
+void main() {
+    mat4 model = instanced ? in_instance_model : model_mat;
+    vec4 world_pos = model * vec4(in_pos, 1.0);
+    frag_pos = world_pos.xyz;
+    gl_Position = light_view_proj * world_pos;
+}
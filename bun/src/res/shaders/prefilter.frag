@@ -0,0 +1,76 @@
+#version 460 core
+
+#define PI 3.1415926535897932384626433832795
+
+in vec2 frag_uv;
+
+uniform samplerCube environment;
+uniform int face;
+uniform float roughness;
+uniform float source_resolution;
+uniform int sample_count = 512;
+
+out vec4 out_col;
+
+vec3 cube_direction(vec2 uv) {
+    vec2 c = uv * 2.0 - 1.0;
+    switch (face) {
+        case 0: return normalize(vec3(1.0, -c.y, -c.x));
+        case 1: return normalize(vec3(-1.0, -c.y, c.x));
+        case 2: return normalize(vec3(c.x, 1.0, c.y));
+        case 3: return normalize(vec3(c.x, -1.0, -c.y));
+        case 4: return normalize(vec3(c.x, -c.y, 1.0));
+        default: return normalize(vec3(-c.x, -c.y, -1.0));
+    }
+}
+
+vec2 hammersley(uint i, uint n) {
+    uint bits = bitfieldReverse(i);
+    return vec2(float(i) / float(n), float(bits) * 2.3283064365386963e-10);
+}
+
+vec3 importance_sample_ggx(vec2 xi, vec3 N, float a) {
+    float phi = 2.0 * PI * xi.x;
+    float cos_theta = sqrt((1.0 - xi.y) / (1.0 + (a * a - 1.0) * xi.y));
+    float sin_theta = sqrt(1.0 - cos_theta * cos_theta);
+    vec3 H = vec3(cos(phi) * sin_theta, sin(phi) * sin_theta, cos_theta);
+
+    vec3 up = abs(N.z) < 0.999 ? vec3(0.0, 0.0, 1.0) : vec3(1.0, 0.0, 0.0);
+    vec3 tangent = normalize(cross(up, N));
+    vec3 bitangent = cross(N, tangent);
+    return normalize(tangent * H.x + bitangent * H.y + N * H.z);
+}
+
+float distribution_ggx(float N_dot_H, float a) {
+    float a2 = a * a;
+    float d = N_dot_H * N_dot_H * (a2 - 1.0) + 1.0;
+    return a2 / (PI * d * d);
+}
+
+void main() {
+    // view = normal = reflection, the usual split sum simplification
+    vec3 N = cube_direction(frag_uv);
+    vec3 V = N;
+    float a = roughness * roughness;
+
+    vec3 color = vec3(0.0);
+    float total_weight = 0.0;
+    for (int i = 0; i < sample_count; i++) {
+        vec3 H = importance_sample_ggx(hammersley(uint(i), uint(sample_count)), N, a);
+        vec3 L = normalize(2.0 * dot(V, H) * H - V);
+        float N_dot_L = dot(N, L);
+        if (N_dot_L <= 0.0) {
+            continue;
+        }
+        // sample a lower mip for unlikely directions to avoid fireflies (filtered importance sampling)
+        float N_dot_H = max(dot(N, H), 0.0);
+        float pdf = distribution_ggx(N_dot_H, a) * 0.25 + 0.0001;
+        float texel_solid_angle = 4.0 * PI / (6.0 * source_resolution * source_resolution);
+        float sample_solid_angle = 1.0 / (float(sample_count) * pdf + 0.0001);
+        float mip = roughness == 0.0 ? 0.0 : 0.5 * log2(sample_solid_angle / texel_solid_angle);
+
+        color += textureLod(environment, L, mip).rgb * N_dot_L;
+        total_weight += N_dot_L;
+    }
+    out_col = vec4(color / max(total_weight, 0.0001), 1.0);
+}
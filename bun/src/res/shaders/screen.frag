@@ -4,8 +4,32 @@ out vec4 out_color;
 in vec2 frag_uv;
 
 layout(binding = 0) uniform sampler2D screen_texture;
+// half resolution bloom, mixed in before tonemapping
+layout(binding = 1) uniform sampler2D bloom_texture;
+uniform float bloom_intensity = 0.0;
+// single channel buffer shown instead of the frame, e.g. the AO
+layout(binding = 2) uniform sampler2D debug_texture;
+uniform bool show_debug_texture = false;
 
-float exposure = 0.8;
+// linear scale, the compensation on top of the adapted exposure with auto exposure
+uniform float exposure = 0.8;
+uniform bool auto_exposure = false;
+// written by luminance_average.comp, only bound with auto exposure
+layout(std430, binding = 5) readonly buffer Exposure {
+    float adapted_luminance;
+};
+
+// index of `Tonemapper`
+uniform int tonemapper = 0;
+
+const int TONEMAP_ACES_FITTED = 0;
+const int TONEMAP_AGX = 1;
+const int TONEMAP_REINHARD = 2;
+const int TONEMAP_PBR_NEUTRAL = 3;
+
+float luma(vec3 c) {
+    return dot(c, vec3(0.2126, 0.7152, 0.0722));
+}
 
 // Narkowicz 2015, "ACES Filmic Tone Mapping Curve"
 vec3 aces(vec3 x) {
@@ -17,29 +41,93 @@ vec3 aces(vec3 x) {
     return clamp((x * (a * x + b)) / (x * (c * x + d) + e), 0.0, 1.0);
 }
 
-float aces(float x) {
-    const float a = 2.51;
-    const float b = 0.03;
-    const float c = 2.43;
-    const float d = 0.59;
-    const float e = 0.14;
-    return clamp((x * (a * x + b)) / (x * (c * x + d) + e), 0.0, 1.0);
+// Sobotka's AgX, polynomial fit of the base contrast curve by Wrensch 2023
+vec3 agx_contrast(vec3 x) {
+    vec3 x2 = x * x;
+    vec3 x4 = x2 * x2;
+    return 15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x + 0.4298 * x2 + 0.1191 * x - 0.00232;
+}
+
+vec3 agx(vec3 x) {
+    const mat3 inset = mat3(
+        0.842479062253094, 0.0423282422610123, 0.0423756549057051,
+        0.0784335999999992, 0.878468636469772, 0.0784336,
+        0.0792237451477643, 0.0791661274605434, 0.879142973793104);
+    const mat3 outset = mat3(
+        1.19687900512017, -0.0528968517574562, -0.0529716355144438,
+        -0.0980208811401368, 1.15190312990417, -0.0980434501171241,
+        -0.0990297440797205, -0.0989611768448433, 1.15107367264116);
+    const float min_ev = -12.47393;
+    const float max_ev = 4.026069;
+
+    x = inset * x;
+    x = clamp(log2(max(x, vec3(1e-10))), min_ev, max_ev);
+    x = (x - min_ev) / (max_ev - min_ev);
+    x = agx_contrast(x);
+    x = outset * x;
+    // the curve outputs display encoded values, back to linear for the sRGB encode below
+    return pow(clamp(x, 0.0, 1.0), vec3(2.2));
+}
+
+vec3 reinhard(vec3 x) {
+    return x / (1.0 + luma(x));
 }
 
+// Khronos PBR Neutral tone mapper
+vec3 pbr_neutral(vec3 color) {
+    const float start_compression = 0.8 - 0.04;
+    const float desaturation = 0.15;
+
+    float x = min(color.r, min(color.g, color.b));
+    float offset = x < 0.08 ? x - 6.25 * x * x : 0.04;
+    color -= offset;
+
+    float peak = max(color.r, max(color.g, color.b));
+    if (peak < start_compression) {
+        return color;
+    }
+
+    const float d = 1.0 - start_compression;
+    float new_peak = 1.0 - d * d / (peak + d - start_compression);
+    color *= new_peak / peak;
 
+    float g = 1.0 - 1.0 / (desaturation * (peak - new_peak) + 1.0);
+    return mix(color, vec3(new_peak), g);
+}
+
+// IEC 61966-2-1 encoding, the linear toe keeps dark values from crushing
+vec3 linear_to_srgb(vec3 c) {
+    c = clamp(c, 0.0, 1.0);
+    vec3 low = c * 12.92;
+    vec3 high = 1.055 * pow(c, vec3(1.0 / 2.4)) - 0.055;
+    return mix(high, low, lessThanEqual(c, vec3(0.0031308)));
+}
 
 void main()
 {
-    vec3 hdr = texture(screen_texture, frag_uv).rgb;
+    if (show_debug_texture) {
+        out_color = vec4(texture(debug_texture, frag_uv).rrr, 1.0);
+        return;
+    }
 
-    // Exposure (linear)
-    hdr *= exposure;
+    vec3 hdr = texture(screen_texture, frag_uv).rgb;
+    hdr = mix(hdr, texture(bloom_texture, frag_uv).rgb, bloom_intensity);
 
-    // ACES tone mapping
-    vec3 mapped = aces(hdr);
+    // Exposure (linear), saturation based EV100 from the adapted luminance
+    float scale = exposure;
+    if (auto_exposure) {
+        scale *= 1.0 / (9.6 * max(adapted_luminance, 1e-4));
+    }
+    hdr *= scale;
 
-    // Gamma correction (linear → sRGB)
-    mapped = pow(mapped, vec3(1.0 / 2.2));
+    vec3 mapped;
+    switch (tonemapper) {
+        case TONEMAP_ACES_FITTED: mapped = aces(hdr); break;
+        case TONEMAP_AGX: mapped = agx(hdr); break;
+        case TONEMAP_REINHARD: mapped = reinhard(hdr); break;
+        case TONEMAP_PBR_NEUTRAL: mapped = pbr_neutral(hdr); break;
+        default: mapped = hdr; break;
+    }
 
-    out_color = vec4(mapped, 1.0);
-}
\ No newline at end of file
+    out_color = vec4(linear_to_srgb(mapped), 1.0);
+}
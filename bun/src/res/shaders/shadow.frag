@@ -0,0 +1,5 @@
+#version 460
+
+// depth only, the fixed function depth write does all the work
+void main() {
+}
@@ -0,0 +1,13 @@
+#version 460
+
+layout (location = 0) in vec3 in_pos;
+layout (location = 4) in mat4 in_instance_model;
+
+uniform mat4 light_view_proj;
+uniform mat4 model_mat;
+uniform bool instanced;
+
+void main() {
+    mat4 model = instanced ? in_instance_model : model_mat;
+    gl_Position = light_view_proj * model * vec4(in_pos, 1.0);
+}
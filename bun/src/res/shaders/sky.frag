@@ -0,0 +1,83 @@
+#version 460 core
+
+#define PI 3.1415926535897932384626433832795
+
+#define SKY_CUBE_MAP 0
+#define SKY_EQUIRECT 1
+#define SKY_ATMOSPHERE 2
+
+// angular radius of the sun disk, a bit larger than the real 0.27 degrees
+#define SUN_RADIUS 0.008
+
+in vec3 frag_dir;
+
+layout(binding = 0) uniform samplerCube sky_cube_map;
+layout(binding = 1) uniform sampler2D sky_equirect;
+uniform int sky_source = SKY_CUBE_MAP;
+uniform float sky_exposure = 0.0;
+uniform float sky_rotation = 0.0;
+
+// Preetham sky, x = luminance, yz = chromaticity, see atmosphere.rs
+uniform vec3 preetham_a;
+uniform vec3 preetham_b;
+uniform vec3 preetham_c;
+uniform vec3 preetham_d;
+uniform vec3 preetham_e;
+uniform vec3 preetham_zenith;
+uniform vec3 sun_direction;
+uniform vec3 sun_radiance;
+uniform float sky_luminance_scale;
+
+out vec4 out_col;
+
+vec3 perez(float cos_theta, float gamma) {
+    float cos_gamma = cos(gamma);
+    return (1.0 + preetham_a * exp(preetham_b / cos_theta))
+        * (1.0 + preetham_c * exp(preetham_d * gamma) + preetham_e * cos_gamma * cos_gamma);
+}
+
+vec3 yxy_to_rgb(vec3 yxy) {
+    if (yxy.z <= 0.0) {
+        return vec3(0.0);
+    }
+    vec3 xyz = vec3(yxy.y * yxy.x / yxy.z, yxy.x, (1.0 - yxy.y - yxy.z) * yxy.x / yxy.z);
+    mat3 xyz_to_rgb = mat3(
+        3.2406, -0.9689, 0.0557,
+        -1.5372, 1.8758, -0.2040,
+        -0.4986, 0.0415, 1.0570
+    );
+    return max(xyz_to_rgb * xyz, 0.0);
+}
+
+vec3 atmosphere(vec3 d) {
+    // below the horizon the sky stays at the horizon color
+    float cos_theta = max(d.y, 0.001);
+    float cos_sun = clamp(dot(d, sun_direction), -1.0, 1.0);
+    vec3 yxy = perez(cos_theta, acos(cos_sun)) * preetham_zenith;
+    vec3 color = yxy_to_rgb(vec3(max(yxy.x, 0.0), yxy.yz)) * sky_luminance_scale;
+
+    float disk = smoothstep(cos(SUN_RADIUS * 1.2), cos(SUN_RADIUS), cos_sun) * step(0.0, d.y);
+    return color + sun_radiance * disk;
+}
+
+void main() {
+    vec3 d = normalize(frag_dir);
+    // turn the lookup the other way to turn the sky by sky_rotation
+    float c = cos(sky_rotation);
+    float s = sin(sky_rotation);
+    d = vec3(c * d.x - s * d.z, d.y, s * d.x + c * d.z);
+
+    vec3 color;
+    if (sky_source == SKY_ATMOSPHERE) {
+        color = atmosphere(d);
+    } else if (sky_source == SKY_EQUIRECT) {
+        vec2 uv = vec2(atan(d.z, d.x) / (2.0 * PI) + 0.5, 0.5 - asin(clamp(d.y, -1.0, 1.0)) / PI);
+        // textures are flipped on load, so v = 1 is the top row
+        uv.y = 1.0 - uv.y;
+        // explicit lod, the derivatives jump at the atan seam
+        color = textureLod(sky_equirect, uv, 0.0).rgb;
+    } else {
+        color = texture(sky_cube_map, d).rgb;
+    }
+    out_col = vec4(color * exp2(sky_exposure), 1.0);
+}
@@ -0,0 +1,14 @@
+#version 460 core
+
+// projection times the camera rotation, inverted
+uniform mat4 inverse_view_proj;
+
+out vec3 frag_dir;
+
+// fullscreen triangle on the far plane, no vertex buffer needed
+void main() {
+    vec2 pos = vec2((gl_VertexID << 1) & 2, gl_VertexID & 2) * 2.0 - 1.0;
+    // w is the same for every vertex, so xyz interpolates linearly into the view direction
+    frag_dir = (inverse_view_proj * vec4(pos, 1.0, 1.0)).xyz;
+    gl_Position = vec4(pos, 1.0, 1.0);
+}
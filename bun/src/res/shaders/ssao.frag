@@ -0,0 +1,58 @@
+#version 460 core
+
+#define MAX_SAMPLES 64
+
+in vec2 frag_uv;
+
+layout(binding = 0) uniform sampler2D depth_texture;
+layout(binding = 1) uniform sampler2D normal_texture;
+layout(binding = 2) uniform sampler2D noise_texture;
+
+uniform vec3 samples[MAX_SAMPLES];
+uniform int sample_count = 32;
+uniform float radius = 0.5;
+uniform float bias = 0.025;
+uniform float intensity = 1.0;
+uniform mat4 proj_mat;
+uniform mat4 inv_proj_mat;
+uniform vec2 noise_scale;
+
+out float out_occlusion;
+
+vec3 view_position(vec2 uv) {
+    float depth = texture(depth_texture, uv).r;
+    vec4 view = inv_proj_mat * vec4(vec3(uv, depth) * 2.0 - 1.0, 1.0);
+    return view.xyz / view.w;
+}
+
+void main() {
+    if (texture(depth_texture, frag_uv).r >= 1.0) {
+        // nothing rendered here, the sky is never occluded
+        out_occlusion = 1.0;
+        return;
+    }
+
+    vec3 pos = view_position(frag_uv);
+    vec3 normal = normalize(texture(normal_texture, frag_uv).xyz);
+    vec3 random = vec3(texture(noise_texture, frag_uv * noise_scale).xy, 0.0);
+
+    // Gram-Schmidt, a tangent frame around the normal rotated by the noise
+    vec3 tangent = normalize(random - normal * dot(random, normal));
+    vec3 bitangent = cross(normal, tangent);
+    mat3 tbn = mat3(tangent, bitangent, normal);
+
+    float occlusion = 0.0;
+    for (int i = 0; i < sample_count; i++) {
+        vec3 sample_pos = pos + tbn * samples[i] * radius;
+
+        vec4 offset = proj_mat * vec4(sample_pos, 1.0);
+        vec2 sample_uv = offset.xy / offset.w * 0.5 + 0.5;
+        float sample_depth = view_position(sample_uv).z;
+
+        // samples behind far away geometry don't count, that's not contact
+        float range_check = smoothstep(0.0, 1.0, radius / abs(pos.z - sample_depth));
+        occlusion += (sample_depth >= sample_pos.z + bias ? 1.0 : 0.0) * range_check;
+    }
+
+    out_occlusion = pow(1.0 - occlusion / float(sample_count), intensity);
+}
@@ -0,0 +1,19 @@
+#version 460 core
+
+in vec2 frag_uv;
+
+layout(binding = 0) uniform sampler2D ao_texture;
+uniform vec2 texel_size;
+
+out float out_occlusion;
+
+// 4x4 box, the size of the noise tile
+void main() {
+    float result = 0.0;
+    for (int x = -2; x < 2; x++) {
+        for (int y = -2; y < 2; y++) {
+            result += texture(ao_texture, frag_uv + vec2(x, y) * texel_size).r;
+        }
+    }
+    out_occlusion = result / 16.0;
+}
@@ -0,0 +1,9 @@
+#version 460
+
+in vec3 view_normal;
+
+out vec3 out_normal;
+
+void main() {
+    out_normal = normalize(view_normal);
+}
@@ -0,0 +1,18 @@
+#version 460
+
+layout (location = 0) in vec3 in_pos;
+layout (location = 1) in vec3 in_normal;
+layout (location = 4) in mat4 in_instance_model;
+
+uniform mat4 proj_mat;
+uniform mat4 view_mat;
+uniform mat4 model_mat;
+uniform bool instanced;
+
+out vec3 view_normal;
+
+void main() {
+    mat4 model_view = view_mat * (instanced ? in_instance_model : model_mat);
+    gl_Position = proj_mat * model_view * vec4(in_pos, 1.0);
+    view_normal = transpose(inverse(mat3(model_view))) * in_normal;
+}
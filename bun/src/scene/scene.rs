@@ -0,0 +1,426 @@
+use crate::renderer::atmosphere::Atmosphere;
+use crate::renderer::environment::{Environment, EnvironmentSettings};
+use crate::renderer::light::{Light, LightKind, PointShadowSettings};
+use crate::renderer::material::Material;
+use crate::renderer::mesh_data::MeshData;
+use crate::renderer::ray::{pick, PickHit, Ray};
+use crate::renderer::render_object::RenderObject;
+use crate::renderer::sky::{Sky, SkySource};
+use crate::renderer::terrain::{Heightmap, Terrain, TerrainData, TerrainSettings};
+use crate::renderer::texture::{CubeMap, TextureSpec, TextureUsage};
+use crate::scene::scene_desc::{
+    AtmosphereDesc, CameraDesc, LightDesc, MeshDesc, ObjectDesc, PointShadowDesc, SceneDesc, SkyDesc, SkySourceDesc,
+    TerrainDesc,
+};
+use crate::{Camera, Mesh, Shader, Texture, Vertex};
+use glm::Vec3;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+pub struct SceneObject {
+    pub name: String,
+    mesh_name: String,
+    material_name: String,
+    pub object: RenderObject,
+}
+
+impl SceneObject {
+    pub fn mesh_name(&self) -> &str { &self.mesh_name }
+    pub fn material_name(&self) -> &str { &self.material_name }
+}
+
+pub struct SceneCamera {
+    pub name: String,
+    pub camera: Camera,
+}
+
+/// A scene loaded from a [`SceneDesc`]. Keeps the asset tables of the description
+/// around so the live state can be written back out with [`Scene::to_desc`].
+pub struct Scene {
+    desc: SceneDesc,
+
+    shaders: HashMap<String, Arc<Shader>>,
+    textures: HashMap<String, Arc<Texture>>,
+    meshes: HashMap<String, Arc<Mesh<Vertex>>>,
+    materials: HashMap<String, Arc<Material>>,
+
+    objects: Vec<SceneObject>,
+    cameras: Vec<SceneCamera>,
+    lights: Vec<Light>,
+    environment: Option<Arc<Environment>>,
+    sky: Option<Sky>,
+    terrain: Option<Terrain>,
+}
+
+impl Scene {
+    pub fn load<P: AsRef<Path>>(path: P, aspect_ratio: f32) -> Result<Self, String> {
+        println!("loading scene: {}", path.as_ref().to_string_lossy());
+        let desc = SceneDesc::load(path)?;
+        Self::from_desc(desc, aspect_ratio)
+    }
+
+    // gl objects are shared between materials and objects through Arc but only ever touched on the gl thread
+    #[allow(clippy::arc_with_non_send_sync)]
+    pub fn from_desc(desc: SceneDesc, aspect_ratio: f32) -> Result<Self, String> {
+        let mut shaders = HashMap::new();
+        for (name, shader) in &desc.shaders {
+            let (vertex, fragment) = (PathBuf::from(&shader.vertex), PathBuf::from(&shader.fragment));
+            let shader = match (&shader.tess_control, &shader.tess_evaluation) {
+                (None, None) => Shader::new(&vertex, &fragment)?,
+                (Some(control), Some(evaluation)) => {
+                    Shader::with_tessellation(&vertex, &PathBuf::from(control), &PathBuf::from(evaluation), &fragment)?
+                }
+                _ => return Err(format!("Shader '{}' needs both tessellation stages or none", name)),
+            };
+            shaders.insert(name.clone(), Arc::new(shader));
+        }
+
+        let mut textures = HashMap::new();
+        for (name, texture) in &desc.textures {
+            let spec = match texture.usage {
+                TextureUsage::Albedo => TextureSpec::albedo(),
+                TextureUsage::Normal => TextureSpec::normal(),
+                TextureUsage::Data => TextureSpec::data(),
+                TextureUsage::Emissive => TextureSpec::emissive(),
+            };
+            textures.insert(name.clone(), Arc::new(Texture::new(&texture.path, spec)?));
+        }
+
+        let mut meshes = HashMap::new();
+        for (name, mesh) in &desc.meshes {
+            let mesh = match mesh {
+                MeshDesc::Model(path) => Mesh::from_model(path)?,
+                MeshDesc::Quad => Mesh::from_mesh_data(&MeshData::quad()),
+                MeshDesc::SubdivQuad(res) => Mesh::from_mesh_data(&MeshData::subdiv_quad(*res)),
+                MeshDesc::Cube => Mesh::from_mesh_data(&MeshData::cube()),
+            };
+            meshes.insert(name.clone(), Arc::new(mesh));
+        }
+
+        let mut materials = HashMap::new();
+        for (name, material) in &desc.materials {
+            let material = material
+                .build_with(&shaders, &textures)
+                .map_err(|e| format!("Material '{}': {}", name, e))?;
+            materials.insert(name.clone(), Arc::new(material));
+        }
+
+        let mut objects = vec![];
+        for object in &desc.objects {
+            let mesh = meshes
+                .get(&object.mesh)
+                .ok_or_else(|| format!("Object '{}' references unknown mesh '{}'", object.name, object.mesh))?;
+            let material = materials
+                .get(&object.material)
+                .ok_or_else(|| format!("Object '{}' references unknown material '{}'", object.name, object.material))?;
+            let mut render_object = RenderObject::new(object.transform.clone(), mesh.clone(), material.clone());
+            render_object.set_cast_shadows(object.cast_shadows);
+            render_object.set_receive_shadows(object.receive_shadows);
+            objects.push(SceneObject {
+                name: object.name.clone(),
+                mesh_name: object.mesh.clone(),
+                material_name: object.material.clone(),
+                object: render_object,
+            });
+        }
+
+        let cameras = desc
+            .cameras
+            .iter()
+            .map(|c| SceneCamera {
+                name: c.name.clone(),
+                camera: Camera::new(vec3(c.position), vec3(c.rotation), c.fov, aspect_ratio, c.near_clip, c.far_clip),
+            })
+            .collect();
+
+        let lights = desc.lights.iter().map(build_light).collect();
+
+        let environment = match &desc.environment {
+            Some(env) => {
+                let mut environment = Environment::load(&env.path, EnvironmentSettings::default())?;
+                environment.set_intensity(env.intensity);
+                Some(Arc::new(environment))
+            }
+            None => None,
+        };
+
+        let sky = match &desc.sky {
+            Some(sky) => Some(build_sky(sky, environment.as_ref(), &textures)?),
+            None => None,
+        };
+
+        let terrain = match &desc.terrain {
+            Some(terrain) => Some(build_terrain(terrain, &materials)?),
+            None => None,
+        };
+
+        Ok(Self {
+            desc,
+            shaders,
+            textures,
+            meshes,
+            materials,
+            objects,
+            cameras,
+            lights,
+            environment,
+            sky,
+            terrain,
+        })
+    }
+
+    /// Writes the current object transforms and cameras back into a description.
+    pub fn to_desc(&self) -> SceneDesc {
+        let mut desc = self.desc.clone();
+        desc.objects = self
+            .objects
+            .iter()
+            .map(|o| ObjectDesc {
+                name: o.name.clone(),
+                mesh: o.mesh_name.clone(),
+                material: o.material_name.clone(),
+                transform: o.object.transform().clone(),
+                cast_shadows: o.object.cast_shadows(),
+                receive_shadows: o.object.receive_shadows(),
+            })
+            .collect();
+        desc.cameras = self
+            .cameras
+            .iter()
+            .map(|c| CameraDesc {
+                name: c.name.clone(),
+                position: array3(c.camera.position()),
+                rotation: array3(c.camera.rotation()),
+                fov: c.camera.fov(),
+                near_clip: c.camera.near_clip(),
+                far_clip: c.camera.far_clip(),
+            })
+            .collect();
+        desc.lights = self.lights.iter().map(light_desc).collect();
+        if let (Some(terrain_desc), Some(terrain)) = (desc.terrain.as_mut(), &self.terrain) {
+            terrain_desc.origin = array3(terrain.origin());
+            terrain_desc.cast_shadows = terrain.cast_shadows();
+        }
+        if let (Some(sky_desc), Some(sky)) = (desc.sky.as_mut(), &self.sky) {
+            sky_desc.exposure = sky.exposure;
+            sky_desc.rotation = sky.rotation;
+            if let SkySource::Atmosphere(atmosphere) = &sky.source {
+                sky_desc.source = SkySourceDesc::Atmosphere(AtmosphereDesc {
+                    turbidity: atmosphere.turbidity,
+                    time_of_day: atmosphere.time_of_day,
+                    noon_elevation: atmosphere.noon_elevation,
+                    sunrise_azimuth: atmosphere.sunrise_azimuth,
+                    sun_intensity: atmosphere.sun_intensity,
+                });
+            }
+        }
+        desc
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        self.to_desc().save(path)
+    }
+
+    pub fn objects(&self) -> &[SceneObject] {
+        &self.objects
+    }
+
+    pub fn objects_mut(&mut self) -> &mut [SceneObject] {
+        &mut self.objects
+    }
+
+    pub fn object(&self, name: &str) -> Option<&RenderObject> {
+        self.objects.iter().find(|o| o.name == name).map(|o| &o.object)
+    }
+
+    pub fn object_mut(&mut self, name: &str) -> Option<&mut RenderObject> {
+        self.objects.iter_mut().find(|o| o.name == name).map(|o| &mut o.object)
+    }
+
+    pub fn pick(&self, ray: &Ray) -> Option<(&SceneObject, PickHit)> {
+        let hit = pick(self.objects.iter().map(|o| &o.object), ray)?;
+        Some((&self.objects[hit.index], hit))
+    }
+
+    pub fn cameras(&self) -> &[SceneCamera] {
+        &self.cameras
+    }
+
+    pub fn cameras_mut(&mut self) -> &mut [SceneCamera] {
+        &mut self.cameras
+    }
+
+    pub fn camera_mut(&mut self, name: &str) -> Option<&mut Camera> {
+        self.cameras.iter_mut().find(|c| c.name == name).map(|c| &mut c.camera)
+    }
+
+    pub fn lights(&self) -> &[Light] {
+        &self.lights
+    }
+
+    pub fn lights_mut(&mut self) -> &mut Vec<Light> {
+        &mut self.lights
+    }
+
+    pub fn environment(&self) -> Option<&Arc<Environment>> {
+        self.environment.as_ref()
+    }
+
+    pub fn sky(&self) -> Option<&Sky> {
+        self.sky.as_ref()
+    }
+
+    pub fn sky_mut(&mut self) -> Option<&mut Sky> {
+        self.sky.as_mut()
+    }
+
+    /// The procedural sky, if the scene uses one.
+    pub fn atmosphere(&self) -> Option<&Atmosphere> {
+        match &self.sky.as_ref()?.source {
+            SkySource::Atmosphere(atmosphere) => Some(atmosphere),
+            _ => None,
+        }
+    }
+
+    pub fn atmosphere_mut(&mut self) -> Option<&mut Atmosphere> {
+        match &mut self.sky.as_mut()?.source {
+            SkySource::Atmosphere(atmosphere) => Some(atmosphere),
+            _ => None,
+        }
+    }
+
+    pub fn terrain(&self) -> Option<&Terrain> {
+        self.terrain.as_ref()
+    }
+
+    pub fn terrain_mut(&mut self) -> Option<&mut Terrain> {
+        self.terrain.as_mut()
+    }
+
+    pub fn shader(&self, name: &str) -> Option<&Arc<Shader>> {
+        self.shaders.get(name)
+    }
+
+    pub fn shaders(&self) -> impl Iterator<Item = (&String, &Arc<Shader>)> {
+        self.shaders.iter()
+    }
+
+    pub fn texture(&self, name: &str) -> Option<&Arc<Texture>> {
+        self.textures.get(name)
+    }
+
+    pub fn mesh(&self, name: &str) -> Option<&Arc<Mesh<Vertex>>> {
+        self.meshes.get(name)
+    }
+
+    pub fn material(&self, name: &str) -> Option<&Arc<Material>> {
+        self.materials.get(name)
+    }
+}
+
+fn build_terrain(desc: &TerrainDesc, materials: &HashMap<String, Arc<Material>>) -> Result<Terrain, String> {
+    let material = materials
+        .get(&desc.material)
+        .ok_or_else(|| format!("Terrain references unknown material '{}'", desc.material))?;
+    let settings = TerrainSettings {
+        size: desc.size,
+        height_scale: desc.height_scale,
+        chunks: desc.chunks,
+        chunk_resolution: desc.chunk_resolution,
+        lod_levels: desc.lod_levels,
+        lod_distance: desc.lod_distance,
+    };
+    let mut terrain = Terrain::new(TerrainData::new(Heightmap::load(&desc.heightmap)?, settings), material.clone());
+    terrain.set_origin(vec3(desc.origin));
+    terrain.set_cast_shadows(desc.cast_shadows);
+    Ok(terrain)
+}
+
+fn build_sky(
+    desc: &SkyDesc,
+    environment: Option<&Arc<Environment>>,
+    textures: &HashMap<String, Arc<Texture>>,
+) -> Result<Sky, String> {
+    let source = match &desc.source {
+        SkySourceDesc::Environment => SkySource::Environment(
+            environment.ok_or("Sky uses the environment but the scene has none")?.clone(),
+        ),
+        SkySourceDesc::CubeMap(faces) => SkySource::CubeMap(Arc::new(CubeMap::from_faces(faces)?)),
+        SkySourceDesc::Equirect(texture) => SkySource::Equirect(
+            textures
+                .get(texture)
+                .ok_or_else(|| format!("Sky references unknown texture '{}'", texture))?
+                .clone(),
+        ),
+        SkySourceDesc::Atmosphere(atmosphere) => SkySource::Atmosphere(Atmosphere {
+            turbidity: atmosphere.turbidity,
+            time_of_day: atmosphere.time_of_day,
+            noon_elevation: atmosphere.noon_elevation,
+            sunrise_azimuth: atmosphere.sunrise_azimuth,
+            sun_intensity: atmosphere.sun_intensity,
+        }),
+    };
+    Ok(Sky::new(source).with_exposure(desc.exposure).with_rotation(desc.rotation))
+}
+
+fn build_light(desc: &LightDesc) -> Light {
+    match *desc {
+        LightDesc::Directional { direction, color, intensity } => {
+            Light::directional(vec3(direction), vec3(color), intensity)
+        }
+        LightDesc::Point { position, color, intensity, range, shadow } => Light {
+            shadow: shadow.map(point_shadow),
+            ..Light::point(vec3(position), vec3(color), intensity, range)
+        },
+        LightDesc::Spot { position, direction, color, intensity, range, inner_angle, outer_angle, shadow } => Light {
+            shadow: shadow.map(point_shadow),
+            ..Light::spot(vec3(position), vec3(direction), vec3(color), intensity, range, inner_angle, outer_angle)
+        },
+    }
+}
+
+fn point_shadow(desc: PointShadowDesc) -> PointShadowSettings {
+    PointShadowSettings { resolution: desc.resolution, near: desc.near, far: desc.far, bias: desc.bias }
+}
+
+fn point_shadow_desc(settings: PointShadowSettings) -> PointShadowDesc {
+    PointShadowDesc { resolution: settings.resolution, near: settings.near, far: settings.far, bias: settings.bias }
+}
+
+fn light_desc(light: &Light) -> LightDesc {
+    let color = array3(light.color);
+    let intensity = light.intensity;
+    match light.kind {
+        LightKind::Directional { direction } => LightDesc::Directional {
+            direction: array3(direction),
+            color,
+            intensity,
+        },
+        LightKind::Point { position, range } => LightDesc::Point {
+            position: array3(position),
+            color,
+            intensity,
+            range,
+            shadow: light.shadow.map(point_shadow_desc),
+        },
+        LightKind::Spot { position, direction, range, inner_angle, outer_angle } => LightDesc::Spot {
+            position: array3(position),
+            direction: array3(direction),
+            color,
+            intensity,
+            range,
+            inner_angle,
+            outer_angle,
+            shadow: light.shadow.map(point_shadow_desc),
+        },
+    }
+}
+
+fn vec3(v: [f32; 3]) -> Vec3 {
+    Vec3::new(v[0], v[1], v[2])
+}
+
+fn array3(v: Vec3) -> [f32; 3] {
+    [v.x, v.y, v.z]
+}
@@ -0,0 +1,257 @@
+use crate::renderer::atmosphere::Atmosphere;
+use crate::renderer::material::MaterialData;
+use crate::renderer::terrain::TerrainSettings;
+use crate::renderer::texture::TextureUsage;
+use crate::Transform;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// On-disk description of a scene. Assets are declared once by name and
+/// referenced from materials and objects, so the same mesh or texture is only
+/// loaded a single time.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SceneDesc {
+    #[serde(default)]
+    pub shaders: BTreeMap<String, ShaderDesc>,
+    #[serde(default)]
+    pub textures: BTreeMap<String, TextureDesc>,
+    #[serde(default)]
+    pub meshes: BTreeMap<String, MeshDesc>,
+    #[serde(default)]
+    pub materials: BTreeMap<String, MaterialData>,
+    #[serde(default)]
+    pub objects: Vec<ObjectDesc>,
+    #[serde(default)]
+    pub cameras: Vec<CameraDesc>,
+    #[serde(default)]
+    pub lights: Vec<LightDesc>,
+    #[serde(default)]
+    pub environment: Option<EnvironmentDesc>,
+    #[serde(default)]
+    pub sky: Option<SkyDesc>,
+    #[serde(default)]
+    pub terrain: Option<TerrainDesc>,
+}
+
+/// Equirectangular `.hdr` or `.exr` image used for image based lighting.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvironmentDesc {
+    pub path: String,
+    #[serde(default = "one")]
+    pub intensity: f32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub enum SkySourceDesc {
+    /// The scene `environment`.
+    #[default]
+    Environment,
+    /// Face image paths in GL order: +X, -X, +Y, -Y, +Z, -Z.
+    CubeMap([String; 6]),
+    /// Name of an equirectangular texture.
+    Equirect(String),
+    /// Procedural sky, the scene gets the sun as an extra directional light.
+    Atmosphere(AtmosphereDesc),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AtmosphereDesc {
+    #[serde(default = "default_turbidity")]
+    pub turbidity: f32,
+    #[serde(default = "default_time_of_day")]
+    pub time_of_day: f32,
+    /// Radians.
+    #[serde(default = "default_noon_elevation")]
+    pub noon_elevation: f32,
+    /// Radians around the up axis, from +X towards +Z.
+    #[serde(default)]
+    pub sunrise_azimuth: f32,
+    #[serde(default = "default_sun_intensity")]
+    pub sun_intensity: f32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SkyDesc {
+    #[serde(default)]
+    pub source: SkySourceDesc,
+    #[serde(default)]
+    pub exposure: f32,
+    #[serde(default)]
+    pub rotation: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShaderDesc {
+    pub vertex: String,
+    pub fragment: String,
+    /// Tessellation control and evaluation stages, both or neither.
+    #[serde(default)]
+    pub tess_control: Option<String>,
+    #[serde(default)]
+    pub tess_evaluation: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextureDesc {
+    pub path: String,
+    pub usage: TextureUsage,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum MeshDesc {
+    Model(String),
+    Quad,
+    SubdivQuad(u32),
+    Cube,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObjectDesc {
+    pub name: String,
+    pub mesh: String,
+    pub material: String,
+    pub transform: Transform,
+    #[serde(default = "enabled")]
+    pub cast_shadows: bool,
+    #[serde(default = "enabled")]
+    pub receive_shadows: bool,
+}
+
+/// Heightmap terrain centered on `origin`, see `TerrainSettings` for the parameters.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerrainDesc {
+    /// Grayscale image, preferably 16 bit.
+    pub heightmap: String,
+    pub material: String,
+    #[serde(default)]
+    pub origin: [f32; 3],
+    #[serde(default = "default_terrain_size")]
+    pub size: f32,
+    #[serde(default = "default_terrain_height_scale")]
+    pub height_scale: f32,
+    #[serde(default = "default_terrain_chunks")]
+    pub chunks: u32,
+    #[serde(default = "default_terrain_chunk_resolution")]
+    pub chunk_resolution: u32,
+    #[serde(default = "default_terrain_lod_levels")]
+    pub lod_levels: u32,
+    #[serde(default = "default_terrain_lod_distance")]
+    pub lod_distance: f32,
+    #[serde(default = "enabled")]
+    pub cast_shadows: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CameraDesc {
+    pub name: String,
+    pub position: [f32; 3],
+    #[serde(default)]
+    pub rotation: [f32; 3],
+    pub fov: f32,
+    pub near_clip: f32,
+    pub far_clip: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum LightDesc {
+    Directional {
+        direction: [f32; 3],
+        color: [f32; 3],
+        intensity: f32,
+    },
+    Point {
+        position: [f32; 3],
+        color: [f32; 3],
+        intensity: f32,
+        range: f32,
+        #[serde(default)]
+        shadow: Option<PointShadowDesc>,
+    },
+    Spot {
+        position: [f32; 3],
+        direction: [f32; 3],
+        color: [f32; 3],
+        intensity: f32,
+        range: f32,
+        /// Cone half angles in radians.
+        inner_angle: f32,
+        outer_angle: f32,
+        #[serde(default)]
+        shadow: Option<PointShadowDesc>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct PointShadowDesc {
+    pub resolution: u32,
+    pub near: f32,
+    pub far: f32,
+    #[serde(default = "default_point_shadow_bias")]
+    pub bias: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SceneFormat {
+    Ron,
+    Json,
+}
+
+impl SceneFormat {
+    /// Picks the format from the file extension, `.json` is JSON and everything else RON.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Self {
+        match path.as_ref().extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => SceneFormat::Json,
+            _ => SceneFormat::Ron,
+        }
+    }
+}
+
+impl SceneDesc {
+    /// RON scenes may leave out `Some(..)` around optional values, so a material can name its
+    /// shader as `shader: "pbr"`.
+    pub fn parse(src: &str, format: SceneFormat) -> Result<Self, String> {
+        match format {
+            SceneFormat::Ron => ron::Options::default()
+                .with_default_extension(ron::extensions::Extensions::IMPLICIT_SOME)
+                .from_str(src)
+                .map_err(|e| format!("Could not parse scene: {}", e)),
+            SceneFormat::Json => serde_json::from_str(src).map_err(|e| format!("Could not parse scene: {}", e)),
+        }
+    }
+
+    pub fn serialize(&self, format: SceneFormat) -> Result<String, String> {
+        match format {
+            SceneFormat::Ron => ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+                .map_err(|e| format!("Could not serialize scene: {}", e)),
+            SceneFormat::Json => serde_json::to_string_pretty(self)
+                .map_err(|e| format!("Could not serialize scene: {}", e)),
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let src = std::fs::read_to_string(path.as_ref())
+            .map_err(|e| format!("Failed to read {:?}: {}", path.as_ref(), e))?;
+        Self::parse(&src, SceneFormat::from_path(path))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        let src = self.serialize(SceneFormat::from_path(path.as_ref()))?;
+        std::fs::write(path.as_ref(), src)
+            .map_err(|e| format!("Failed to write {:?}: {}", path.as_ref(), e))
+    }
+}
+
+fn one() -> f32 { 1.0 }
+fn enabled() -> bool { true }
+fn default_terrain_size() -> f32 { TerrainSettings::default().size }
+fn default_terrain_height_scale() -> f32 { TerrainSettings::default().height_scale }
+fn default_terrain_chunks() -> u32 { TerrainSettings::default().chunks }
+fn default_terrain_chunk_resolution() -> u32 { TerrainSettings::default().chunk_resolution }
+fn default_terrain_lod_levels() -> u32 { TerrainSettings::default().lod_levels }
+fn default_terrain_lod_distance() -> f32 { TerrainSettings::default().lod_distance }
+fn default_point_shadow_bias() -> f32 { 0.05 }
+fn default_turbidity() -> f32 { Atmosphere::default().turbidity }
+fn default_time_of_day() -> f32 { Atmosphere::default().time_of_day }
+fn default_noon_elevation() -> f32 { Atmosphere::default().noon_elevation }
+fn default_sun_intensity() -> f32 { Atmosphere::default().sun_intensity }
@@ -0,0 +1,44 @@
+//! `#[serde(with = "...")]` helpers for glm vectors, which have no serde support of
+//! their own. Vectors are written as plain float arrays, e.g. `(1.0, 2.0, 3.0)` in RON.
+
+pub mod vec2 {
+    use glm::Vec2;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(v: &Vec2, serializer: S) -> Result<S::Ok, S::Error> {
+        [v.x, v.y].serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec2, D::Error> {
+        let [x, y] = <[f32; 2]>::deserialize(deserializer)?;
+        Ok(Vec2::new(x, y))
+    }
+}
+
+pub mod vec3 {
+    use glm::Vec3;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(v: &Vec3, serializer: S) -> Result<S::Ok, S::Error> {
+        [v.x, v.y, v.z].serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec3, D::Error> {
+        let [x, y, z] = <[f32; 3]>::deserialize(deserializer)?;
+        Ok(Vec3::new(x, y, z))
+    }
+}
+
+pub mod vec4 {
+    use glm::Vec4;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(v: &Vec4, serializer: S) -> Result<S::Ok, S::Error> {
+        [v.x, v.y, v.z, v.w].serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec4, D::Error> {
+        let [x, y, z, w] = <[f32; 4]>::deserialize(deserializer)?;
+        Ok(Vec4::new(x, y, z, w))
+    }
+}
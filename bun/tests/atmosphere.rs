@@ -0,0 +1,68 @@
+use bun::glm::{length, Vec3};
+use bun::renderer::atmosphere::Atmosphere;
+use bun::renderer::light::LightKind;
+
+fn luminance(rgb: Vec3) -> f32 {
+    0.2126 * rgb.x + 0.7152 * rgb.y + 0.0722 * rgb.z
+}
+
+#[test]
+fn sun_follows_time_of_day() {
+    let atmosphere = Atmosphere::default();
+    for hours in [0.0, 3.0, 6.0, 9.5, 12.0, 17.0, 23.0] {
+        assert!((length(atmosphere.with_time_of_day(hours).sun_direction()) - 1.0).abs() < 1e-5);
+    }
+    assert!(atmosphere.with_time_of_day(6.0).sun_elevation().abs() < 1e-5);
+    assert!(atmosphere.with_time_of_day(18.0).sun_elevation().abs() < 1e-5);
+    assert!((atmosphere.with_time_of_day(12.0).sun_elevation() - atmosphere.noon_elevation).abs() < 1e-5);
+    assert!(atmosphere.with_time_of_day(0.0).sun_elevation() < 0.0);
+    // rises at the sunrise azimuth, sets on the opposite side
+    assert!(atmosphere.with_time_of_day(6.0).sun_direction().x > 0.99);
+    assert!(atmosphere.with_time_of_day(18.0).sun_direction().x < -0.99);
+}
+
+#[test]
+fn sun_light_matches_the_sky() {
+    let noon = Atmosphere::default().with_time_of_day(12.0);
+    let light = noon.sun_light();
+    match light.kind {
+        LightKind::Directional { direction } => assert!(length(direction + noon.sun_direction()) < 1e-5),
+        _ => panic!("the sun is a directional light"),
+    }
+
+    // more atmosphere in the way at dusk, so darker and redder
+    let dusk = noon.with_time_of_day(17.8);
+    let (noon_color, dusk_color) = (noon.sun_transmittance(), dusk.sun_transmittance());
+    assert!(luminance(dusk_color) < luminance(noon_color));
+    assert!(dusk_color.x / dusk_color.z > noon_color.x / noon_color.z);
+    assert!(noon_color.x <= 1.0 && noon_color.x > 0.5);
+
+    let night = noon.with_time_of_day(1.0);
+    assert_eq!(night.sun_transmittance(), Vec3::new(0.0, 0.0, 0.0));
+    assert_eq!(night.sky_radiance(Vec3::new(0.0, 1.0, 0.0)), Vec3::new(0.0, 0.0, 0.0));
+}
+
+#[test]
+fn clear_sky_radiance() {
+    let atmosphere = Atmosphere::default().with_time_of_day(10.0);
+    let up = Vec3::new(0.0, 1.0, 0.0);
+    let zenith = atmosphere.sky_radiance(up);
+    // blue sky
+    assert!(zenith.z > zenith.x, "{:?}", zenith);
+
+    // the sky around the sun is brighter than the opposite side
+    let sun = atmosphere.sun_direction();
+    let near_sun = atmosphere.sky_radiance(sun + up * 0.1);
+    let away = atmosphere.sky_radiance(Vec3::new(-sun.x, sun.y, -sun.z));
+    assert!(luminance(near_sun) > luminance(away));
+
+    // no black band below the horizon
+    let horizon = atmosphere.sky_radiance(Vec3::new(0.0, 0.0, 1.0));
+    let below = atmosphere.sky_radiance(Vec3::new(0.0, -0.05, 1.0));
+    assert!(luminance(below) > 0.0);
+    assert!(length(horizon - below) < 0.05 * length(horizon));
+
+    // hazier skies are brighter and less saturated
+    let hazy = Atmosphere { turbidity: 8.0, ..atmosphere }.sky_radiance(up);
+    assert!(hazy.x / hazy.z > zenith.x / zenith.z);
+}
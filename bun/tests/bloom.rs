@@ -0,0 +1,14 @@
+use bun::renderer::bloom::bloom_mip_sizes;
+
+#[test]
+fn mip_chain_halves_from_half_resolution() {
+    assert_eq!(
+        bloom_mip_sizes(1920, 1080, 6),
+        vec![(960, 540), (480, 270), (240, 135), (120, 67), (60, 33), (30, 16)]
+    );
+    assert_eq!(bloom_mip_sizes(1920, 1080, 2).len(), 2);
+    // tiny frames get a shorter chain instead of zero sized targets
+    assert_eq!(bloom_mip_sizes(16, 8, 6), vec![(8, 4), (4, 2)]);
+    assert!(bloom_mip_sizes(2, 2, 6).is_empty());
+}
+
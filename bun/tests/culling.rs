@@ -0,0 +1,101 @@
+use bun::glm::{Mat4, Vec3};
+use bun::renderer::bounds::{Aabb, BoundingSphere, Frustum};
+use bun::renderer::mesh_data::MeshData;
+use bun::{Camera, Transform};
+
+fn assert_vec3_near(a: Vec3, b: Vec3) {
+    assert!(bun::glm::length(a - b) < 1e-5, "{:?} != {:?}", a, b);
+}
+
+fn cube_positions() -> Vec<Vec3> {
+    MeshData::cube().vertices().iter().map(|v| v.v).collect()
+}
+
+// looking down +z from the origin
+fn camera() -> Camera {
+    Camera::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 0.0), 1.2, 16.0 / 9.0, 0.1, 100.0)
+}
+
+#[test]
+fn aabb_from_points() {
+    let aabb = Aabb::from_points(cube_positions());
+    assert_vec3_near(aabb.min, Vec3::new(-0.5, -0.5, -0.5));
+    assert_vec3_near(aabb.max, Vec3::new(0.5, 0.5, 0.5));
+    assert_vec3_near(aabb.center(), Vec3::new(0.0, 0.0, 0.0));
+
+    let empty = Aabb::from_points(std::iter::empty());
+    assert_eq!(empty.min, empty.max);
+}
+
+#[test]
+fn aabb_transformed() {
+    let aabb = Aabb::from_points(cube_positions());
+
+    let transform = Transform::new(Vec3::new(1.0, 2.0, 3.0), Vec3::new(2.0, 1.0, 1.0), Vec3::new(0.0, 0.0, 0.0));
+    let moved = aabb.transformed(&transform.model_matrix());
+    assert_vec3_near(moved.min, Vec3::new(0.0, 1.5, 2.5));
+    assert_vec3_near(moved.max, Vec3::new(2.0, 2.5, 3.5));
+
+    // 45 degrees around y grows the box to the diagonal of the cube
+    let rotated = Transform::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 1.0), Vec3::new(0.0, std::f32::consts::FRAC_PI_4, 0.0));
+    let rotated = aabb.transformed(&rotated.model_matrix());
+    let half_diagonal = 0.5 * std::f32::consts::SQRT_2;
+    assert_vec3_near(rotated.max, Vec3::new(half_diagonal, 0.5, half_diagonal));
+    assert_vec3_near(rotated.min, -rotated.max);
+}
+
+#[test]
+fn sphere_contains_mesh() {
+    let positions = cube_positions();
+    let sphere = BoundingSphere::from_points(positions.iter().copied());
+    assert_vec3_near(sphere.center, Vec3::new(0.0, 0.0, 0.0));
+    assert!((sphere.radius - 0.75f32.sqrt()).abs() < 1e-5);
+
+    let transform = Transform::new(Vec3::new(0.0, 5.0, 0.0), Vec3::new(1.0, 3.0, 1.0), Vec3::new(0.3, 0.5, 0.7));
+    let model = transform.model_matrix();
+    let world = sphere.transformed(&model);
+    for p in positions {
+        let p = (model * bun::glm::Vec4::new(p.x, p.y, p.z, 1.0)).truncate(3);
+        assert!(bun::glm::length(p - world.center) <= world.radius + 1e-5);
+    }
+}
+
+#[test]
+fn frustum_planes_from_camera() {
+    let frustum = camera().frustum();
+    assert!(frustum.contains_point(Vec3::new(0.0, 0.0, 10.0)));
+    assert!(!frustum.contains_point(Vec3::new(0.0, 0.0, -10.0)));
+    assert!(!frustum.contains_point(Vec3::new(0.0, 0.0, 0.05)));
+    assert!(!frustum.contains_point(Vec3::new(0.0, 0.0, 150.0)));
+    assert!(!frustum.contains_point(Vec3::new(0.0, 50.0, 10.0)));
+    assert!(!frustum.contains_point(Vec3::new(50.0, 0.0, 10.0)));
+
+    // an identity projection is the -1..1 cube
+    let cube = Frustum::from_matrix(&<Mat4 as bun::One>::one());
+    assert!(cube.contains_point(Vec3::new(0.9, -0.9, 0.9)));
+    assert!(!cube.contains_point(Vec3::new(1.1, 0.0, 0.0)));
+}
+
+#[test]
+fn frustum_culls_objects() {
+    let frustum = camera().frustum();
+    let aabb = Aabb::from_points(cube_positions());
+    let sphere = BoundingSphere::from_points(cube_positions());
+
+    let visible = |pos: Vec3| {
+        let model = Transform::new(pos, Vec3::new(1.0, 1.0, 1.0), Vec3::new(0.0, 0.0, 0.0)).model_matrix();
+        let in_box = frustum.intersects_aabb(&aabb.transformed(&model));
+        let in_sphere = frustum.intersects_sphere(&sphere.transformed(&model));
+        assert_eq!(in_box, in_sphere, "box and sphere disagree at {:?}", pos);
+        in_box
+    };
+
+    assert!(visible(Vec3::new(0.0, 0.0, 5.0)));
+    assert!(!visible(Vec3::new(0.0, 0.0, -5.0)));
+    assert!(!visible(Vec3::new(0.0, 0.0, 120.0)));
+    assert!(!visible(Vec3::new(30.0, 0.0, 5.0)));
+    // the center is outside but the cube reaches into the far plane
+    assert!(visible(Vec3::new(0.0, 0.0, 100.3)));
+    // surrounding the camera
+    assert!(visible(Vec3::new(0.0, 0.0, 0.0)));
+}
@@ -0,0 +1,2 @@
+uniform float shared_value;
+#include "nested/inner.glsl"
@@ -0,0 +1 @@
+uniform float inner_value;
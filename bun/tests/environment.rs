@@ -0,0 +1,79 @@
+use bun::glm::{length, Vec3};
+use bun::renderer::environment::{prefilter_roughness, EnvironmentMap, IrradianceSh};
+use std::f32::consts::PI;
+
+fn assert_vec3_near(a: Vec3, b: Vec3, tolerance: f32) {
+    assert!(length(a - b) < tolerance, "{:?} != {:?}", a, b);
+}
+
+fn map_from(width: u32, height: u32, f: impl Fn(Vec3) -> Vec3) -> EnvironmentMap {
+    let directions = EnvironmentMap::new(width, height, vec![Vec3::new(0.0, 0.0, 0.0); (width * height) as usize]).unwrap();
+    let pixels = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|(x, y)| f(directions.direction(x, y)))
+        .collect();
+    EnvironmentMap::new(width, height, pixels).unwrap()
+}
+
+#[test]
+fn directions_round_trip() {
+    let map = map_from(16, 8, |d| d);
+    for y in 0..map.height() {
+        for x in 0..map.width() {
+            assert_eq!(map.sample(map.direction(x, y)), map.pixel(x, y));
+        }
+    }
+    // top row is the sky
+    assert!(map.direction(3, 0).y > 0.9);
+    assert!(map.direction(3, 7).y < -0.9);
+    assert!(EnvironmentMap::new(2, 2, vec![]).is_err());
+}
+
+#[test]
+fn constant_environment_irradiance() {
+    let color = Vec3::new(0.5, 1.0, 2.0);
+    let sh = IrradianceSh::from_environment(&map_from(64, 32, |_| color));
+    for normal in [Vec3::new(0.0, 1.0, 0.0), Vec3::new(1.0, 0.0, 0.0), Vec3::new(-0.3, -0.5, 0.8)] {
+        assert_vec3_near(sh.irradiance(normal), color * PI, 0.05);
+    }
+}
+
+#[test]
+fn sky_hemisphere_irradiance() {
+    let sh = IrradianceSh::from_environment(&map_from(128, 64, |d| {
+        if d.y > 0.0 { Vec3::new(1.0, 1.0, 1.0) } else { Vec3::new(0.0, 0.0, 0.0) }
+    }));
+    // bands 0 and 1 are exact for a hemisphere facing straight up or down
+    assert_vec3_near(sh.irradiance(Vec3::new(0.0, 1.0, 0.0)), Vec3::new(PI, PI, PI), 0.05);
+    assert_vec3_near(sh.irradiance(Vec3::new(0.0, -1.0, 0.0)), Vec3::new(0.0, 0.0, 0.0), 0.05);
+    assert_vec3_near(sh.irradiance(Vec3::new(1.0, 0.0, 0.0)), Vec3::new(PI, PI, PI) * 0.5, 0.05);
+}
+
+#[test]
+fn load_hdr_and_exr() {
+    let dir = std::env::temp_dir().join(format!("bun_environment_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let mut image = image::Rgb32FImage::new(4, 2);
+    image.put_pixel(1, 0, image::Rgb([8.0, 2.0, 0.5]));
+    for extension in ["hdr", "exr"] {
+        let path = dir.join(format!("sky.{}", extension));
+        image::DynamicImage::ImageRgb32F(image.clone()).save(&path).unwrap();
+
+        let map = EnvironmentMap::load(&path).unwrap();
+        assert_eq!((map.width(), map.height()), (4, 2));
+        // values above 1 survive
+        assert_vec3_near(map.pixel(1, 0), Vec3::new(8.0, 2.0, 0.5), 0.1);
+        assert_vec3_near(map.pixel(0, 1), Vec3::new(0.0, 0.0, 0.0), 1e-6);
+    }
+    assert!(EnvironmentMap::load(dir.join("missing.hdr")).is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn prefilter_levels_cover_all_roughness() {
+    assert_eq!(prefilter_roughness(0, 5), 0.0);
+    assert_eq!(prefilter_roughness(2, 5), 0.5);
+    assert_eq!(prefilter_roughness(4, 5), 1.0);
+    assert_eq!(prefilter_roughness(0, 1), 0.0);
+}
@@ -0,0 +1,196 @@
+#![cfg(feature = "gltf")]
+
+use bun::glm::{Vec2, Vec3, Vec4};
+use bun::renderer::gltf::{apply_factor, GltfData, GltfProjection};
+use bun::renderer::material::BlendMode;
+use std::f32::consts::FRAC_PI_2;
+
+fn assert_vec3_near(a: Vec3, b: Vec3) {
+    assert!(bun::glm::length(a - b) < 1e-5, "{:?} != {:?}", a, b);
+}
+
+fn load(name: &str) -> GltfData {
+    GltfData::load(format!("tests/data/gltf/{}", name)).unwrap()
+}
+
+#[test]
+fn positions_only_get_flat_normals() {
+    let data = load("triangle.gltf");
+    assert_eq!(data.meshes.len(), 1);
+    assert!(data.materials.is_empty());
+    assert!(data.cameras.is_empty());
+    assert_eq!(data.roots, [0]);
+
+    let primitive = &data.meshes[0].primitives[0];
+    assert_eq!(primitive.material, None);
+    assert_eq!(primitive.data.indices(), &[0, 1, 2]);
+    for vertex in primitive.data.vertices() {
+        assert_vec3_near(vertex.vn, Vec3::new(0.0, 0.0, 1.0));
+        // no uvs, but still a unit tangent in the surface
+        let tangent = vertex.tangent.truncate(3);
+        assert!((bun::glm::length(tangent) - 1.0).abs() < 1e-5);
+        assert!(bun::glm::dot(tangent, vertex.vn).abs() < 1e-5);
+    }
+
+    let node = &data.nodes[0];
+    assert_eq!(node.name.as_deref(), Some("triangle"));
+    assert_vec3_near(node.transform.pos(), Vec3::new(1.0, 2.0, 3.0));
+    assert_eq!(node.world[3], Vec4::new(1.0, 2.0, 3.0, 1.0));
+}
+
+#[test]
+fn indexed_mesh_with_uvs_and_tangents() {
+    let data = load("textured_quad.gltf");
+    let mesh = &data.meshes[0];
+    assert_eq!(mesh.name.as_deref(), Some("quad"));
+    let primitive = &mesh.primitives[0];
+    assert_eq!(primitive.material, Some(0));
+    assert_eq!(primitive.data.indices(), &[0, 1, 2, 0, 2, 3]);
+
+    let vertices = primitive.data.vertices();
+    assert_eq!(vertices.len(), 4);
+    // v is flipped to match the textures, which are loaded bottom row first
+    assert_eq!(vertices[0].vt, Vec2::new(0.0, 0.0));
+    assert_eq!(vertices[2].vt, Vec2::new(1.0, 1.0));
+    for vertex in vertices {
+        assert_vec3_near(vertex.vn, Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(vertex.tangent, Vec4::new(1.0, 0.0, 0.0, 1.0));
+    }
+}
+
+#[test]
+fn tangent_handedness_is_kept() {
+    // the file stores tangents with w = -1, mirrored uvs need the flipped bitangent
+    let data = load("factors.gltf");
+    for vertex in data.meshes[0].primitives[0].data.vertices() {
+        assert_eq!(vertex.tangent, Vec4::new(1.0, 0.0, 0.0, -1.0));
+    }
+}
+
+#[test]
+fn node_hierarchy() {
+    let data = load("textured_quad.gltf");
+    assert_eq!(data.roots, [0, 2, 3]);
+    let (parent, child) = (&data.nodes[0], &data.nodes[1]);
+    assert_eq!(parent.children, [1]);
+    assert_eq!(child.parent, Some(0));
+    assert_eq!(child.mesh, Some(0));
+
+    assert_vec3_near(child.transform.pos(), Vec3::new(1.0, 0.0, 0.0));
+    assert_vec3_near(child.transform.rotation(), Vec3::new(0.0, FRAC_PI_2, 0.0));
+
+    // the parent moves up by one and doubles the size
+    let world = bun::Transform::from_matrix(&child.world);
+    assert_vec3_near(world.pos(), Vec3::new(2.0, 1.0, 0.0));
+    assert_vec3_near(world.scale(), Vec3::new(2.0, 2.0, 2.0));
+    assert_vec3_near(world.rotation(), Vec3::new(0.0, FRAC_PI_2, 0.0));
+}
+
+#[test]
+fn cameras() {
+    let data = load("textured_quad.gltf");
+    assert_eq!(data.cameras.len(), 2);
+
+    let main = &data.cameras[0];
+    assert_eq!(main.name.as_deref(), Some("main"));
+    assert_eq!(main.node, 2);
+    assert_eq!(
+        main.projection,
+        GltfProjection::Perspective { yfov: 0.8, aspect_ratio: Some(1.5), near: 0.1, far: Some(50.0) }
+    );
+    let camera = main.to_camera(16.0 / 9.0).unwrap();
+    assert_vec3_near(camera.position(), Vec3::new(0.0, 0.0, 5.0));
+    assert_vec3_near(camera.forward(), Vec3::new(0.0, 0.0, -1.0));
+    assert_eq!((camera.fov(), camera.near_clip(), camera.far_clip()), (0.8, 0.1, 50.0));
+
+    // unnamed cameras go by their node
+    let top_down = &data.cameras[1];
+    assert_eq!(top_down.name.as_deref(), Some("top_down"));
+    assert_eq!(top_down.projection, GltfProjection::Orthographic { xmag: 4.0, ymag: 3.0, near: 0.5, far: 20.0 });
+    assert_vec3_near(top_down.position, Vec3::new(0.0, 10.0, 0.0));
+    assert!((top_down.rotation.x + FRAC_PI_2).abs() < 1e-3);
+    assert!(top_down.to_camera(1.0).is_none());
+}
+
+#[test]
+fn metallic_roughness_material() {
+    let data = load("textured_quad.gltf");
+    let material = &data.materials[0];
+    assert_eq!(material.name.as_deref(), Some("checker"));
+    assert_eq!(material.base_color, Vec4::new(1.0, 1.0, 1.0, 0.5));
+    assert_eq!(material.base_color_texture, Some(0));
+    assert_eq!(material.metallic_roughness_texture, Some(1));
+    assert_eq!((material.normal_texture, material.normal_scale), (Some(0), 0.5));
+    assert_eq!((material.occlusion_texture, material.occlusion_strength), (Some(1), 0.75));
+    assert_eq!(material.emissive, Vec3::new(1.0, 0.5, 0.0));
+    assert_eq!((material.emissive_texture, material.emissive_strength), (None, 4.0));
+    assert_eq!(material.blend_mode, BlendMode::AlphaCutout { threshold: 0.3 });
+}
+
+#[test]
+fn factors_multiply_textures() {
+    let data = load("factors.gltf");
+    let material = &data.materials[0];
+    assert_eq!(material.base_color, Vec4::new(0.5, 1.0, 1.0, 1.0));
+    assert_eq!((material.metallic, material.roughness), (0.25, 0.5));
+    assert_eq!((material.emissive_texture, material.emissive), (Some(0), Vec3::new(1.0, 0.5, 0.0)));
+
+    // sRGB color is scaled in linear space, 0.5 linear is 188 in sRGB
+    let checker = &data.textures[material.base_color_texture.unwrap()].image;
+    let albedo = apply_factor(checker, Vec4::new(material.base_color.x, material.base_color.y, material.base_color.z, 1.0), true);
+    assert_eq!(albedo.get_pixel(0, 0).0, [188, 0, 0, 255]);
+    assert_eq!(albedo.get_pixel(1, 1).0, [0, 0, 255, 255]);
+
+    // roughness in green and metallic in blue, the occlusion in red keeps its value
+    let orm = &data.textures[material.metallic_roughness_texture.unwrap()].image;
+    let packed = apply_factor(orm, Vec4::new(1.0, material.roughness, material.metallic, 1.0), false);
+    assert_eq!(packed.get_pixel(0, 0).0, [64, 64, 64, 255]);
+
+    assert_eq!(apply_factor(orm, Vec4::new(1.0, 1.0, 1.0, 1.0), false), *orm);
+}
+
+#[test]
+fn textures_and_samplers() {
+    let data = load("textured_quad.gltf");
+    assert_eq!(data.textures.len(), 2);
+
+    let checker = &data.textures[0];
+    assert_eq!(checker.sampler.min_filter, bun::gl::NEAREST);
+    assert_eq!(checker.sampler.mag_filter, bun::gl::NEAREST);
+    assert_eq!(checker.sampler.wrap_s, bun::gl::CLAMP_TO_EDGE);
+    assert_eq!(checker.sampler.wrap_t, bun::gl::MIRRORED_REPEAT);
+    assert_eq!(checker.image.dimensions(), (2, 2));
+    assert_eq!(checker.image.get_pixel(0, 0).0, [255, 0, 0, 255]);
+    assert_eq!(checker.image.get_pixel(1, 1).0, [0, 0, 255, 255]);
+
+    // without a sampler the defaults repeat and filter linearly
+    let orm = &data.textures[1];
+    assert_eq!(orm.sampler.min_filter, bun::gl::LINEAR_MIPMAP_LINEAR);
+    assert_eq!(orm.sampler.mag_filter, bun::gl::LINEAR);
+    assert_eq!((orm.sampler.wrap_s, orm.sampler.wrap_t), (bun::gl::REPEAT, bun::gl::REPEAT));
+    assert_eq!(orm.image.get_pixel(0, 0).0, [64, 128, 255, 255]);
+}
+
+#[test]
+fn binary_file_with_triangle_strip() {
+    let data = load("strip.glb");
+    // no scenes, every top level node is a root
+    assert_eq!(data.roots, [0]);
+
+    let primitive = &data.meshes[0].primitives[0];
+    assert_eq!(primitive.data.indices(), &[0, 1, 2, 2, 1, 3]);
+    let vertices = primitive.data.vertices();
+    for triangle in primitive.data.indices().chunks(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| vertices[triangle[i] as usize].v);
+        assert!(bun::glm::cross(b - a, c - a).y > 0.0);
+    }
+
+    let material = &data.materials[primitive.material.unwrap()];
+    assert_eq!((material.metallic, material.roughness), (1.0, 0.25));
+    assert_eq!(data.textures[material.base_color_texture.unwrap()].image.get_pixel(0, 0).0, [0, 255, 0, 255]);
+}
+
+#[test]
+fn missing_file_is_an_error() {
+    assert!(GltfData::load("tests/data/gltf/missing.gltf").is_err());
+}
@@ -0,0 +1,28 @@
+use bun::renderer::material::{BlendMode, Channel};
+
+#[test]
+fn blend_modes_split_into_opaque_and_transparent() {
+    for mode in [BlendMode::Opaque, BlendMode::AlphaCutout { threshold: 0.5 }] {
+        assert!(!mode.is_transparent());
+        assert!(mode.writes_depth());
+        assert_eq!(mode.blend_func(), None);
+    }
+    for mode in [BlendMode::AlphaBlend, BlendMode::Additive, BlendMode::Premultiplied] {
+        assert!(mode.is_transparent());
+        assert!(!mode.writes_depth());
+        assert!(mode.blend_func().is_some());
+    }
+}
+
+#[test]
+fn blend_factors() {
+    assert_eq!(BlendMode::AlphaBlend.blend_func(), Some((bun::gl::SRC_ALPHA, bun::gl::ONE_MINUS_SRC_ALPHA)));
+    assert_eq!(BlendMode::Additive.blend_func(), Some((bun::gl::SRC_ALPHA, bun::gl::ONE)));
+    assert_eq!(BlendMode::Premultiplied.blend_func(), Some((bun::gl::ONE, bun::gl::ONE_MINUS_SRC_ALPHA)));
+}
+
+#[test]
+fn channels_index_rgba() {
+    let indices: Vec<i32> = [Channel::R, Channel::G, Channel::B, Channel::A].into_iter().map(Channel::index).collect();
+    assert_eq!(indices, [0, 1, 2, 3]);
+}
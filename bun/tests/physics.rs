@@ -0,0 +1,102 @@
+#![cfg(feature = "physics")]
+
+use bun::glm::Vec3;
+use bun::physics::physics::{BodyKind, ColliderShape, Physics, DEFAULT_CONTACT_FORCE_THRESHOLD};
+use bun::renderer::mesh_data::MeshData;
+use bun::Transform;
+
+fn ground(physics: &mut Physics) {
+    let transform = Transform::new(Vec3::new(0.0, -0.5, 0.0), Vec3::new(50.0, 1.0, 50.0), Vec3::new(0.0, 0.0, 0.0));
+    physics
+        .add_body(&transform, BodyKind::Fixed, &ColliderShape::trimesh(&MeshData::subdiv_quad(4)))
+        .unwrap();
+}
+
+#[test]
+fn boxes_fall_and_stack() {
+    let mut physics = Physics::new();
+    ground(&mut physics);
+
+    let cube = ColliderShape::convex_hull(&MeshData::cube());
+    let lower_transform = Transform::new(Vec3::new(0.0, 2.0, 0.0), Vec3::new(1.0, 1.0, 1.0), Vec3::new(0.0, 0.0, 0.0));
+    let upper_transform = Transform::new(Vec3::new(0.0, 4.0, 0.0), Vec3::new(1.0, 1.0, 1.0), Vec3::new(0.0, 0.0, 0.0));
+    let lower = physics.add_body(&lower_transform, BodyKind::Dynamic, &cube).unwrap();
+    let upper = physics.add_body(&upper_transform, BodyKind::Dynamic, &cube).unwrap();
+
+    let mut started = 0;
+    for _ in 0..240 {
+        physics.step();
+        started += physics.collision_events().iter().filter(|e| e.started()).count();
+    }
+    assert!(started >= 2);
+
+    let mut lower_transform = lower_transform;
+    let mut upper_transform = upper_transform;
+    physics.sync_transform(lower, &mut lower_transform);
+    physics.sync_transform(upper, &mut upper_transform);
+    assert!((lower_transform.pos().y - 0.0).abs() < 0.05, "lower box at {}", lower_transform.pos().y);
+    assert!((upper_transform.pos().y - 1.0).abs() < 0.05, "upper box at {}", upper_transform.pos().y);
+}
+
+#[test]
+fn landing_reports_contact_force() {
+    let drop = |threshold: f32| {
+        let mut physics = Physics::new();
+        ground(&mut physics);
+        let transform = Transform::new(Vec3::new(0.0, 3.0, 0.0), Vec3::new(1.0, 1.0, 1.0), Vec3::new(0.0, 0.0, 0.0));
+        let cube = physics
+            .add_body(&transform, BodyKind::Dynamic, &ColliderShape::convex_hull(&MeshData::cube()))
+            .unwrap();
+        // also applies to the colliders added before
+        physics.set_contact_force_threshold(threshold);
+        for _ in 0..180 {
+            physics.step();
+        }
+        for event in physics.contact_force_events() {
+            let bodies = [physics.collider_body(event.collider1), physics.collider_body(event.collider2)];
+            assert!(bodies.contains(&Some(cube)));
+            assert!(event.total_force_magnitude >= threshold);
+        }
+        physics.contact_force_events().len()
+    };
+
+    // the impact is reported, resting on the ground afterwards stays below the threshold
+    assert_eq!(drop(DEFAULT_CONTACT_FORCE_THRESHOLD), 1);
+    assert!(drop(0.0) > 1);
+    assert_eq!(drop(10_000.0), 0);
+}
+
+#[test]
+fn ray_hits_ground() {
+    let mut physics = Physics::new();
+    ground(&mut physics);
+    physics.step();
+
+    let hit = physics
+        .cast_ray(Vec3::new(1.0, 10.0, 1.0), Vec3::new(0.0, -1.0, 0.0), 100.0)
+        .expect("ray should hit the ground");
+    assert!((hit.distance - 10.5).abs() < 1e-3);
+    assert!((hit.point.y + 0.5).abs() < 1e-3);
+    assert!((hit.normal.y - 1.0).abs() < 1e-3);
+    assert!(physics.cast_ray(Vec3::new(1.0, 10.0, 1.0), Vec3::new(0.0, 1.0, 0.0), 100.0).is_none());
+}
+
+#[test]
+fn rotation_survives_sync() {
+    let mut physics = Physics::new();
+    physics.set_gravity(Vec3::new(0.0, 0.0, 0.0));
+    let rotation = Vec3::new(0.3, -1.1, 0.7);
+    let mut transform = Transform::new(Vec3::new(1.0, 2.0, 3.0), Vec3::new(1.0, 1.0, 1.0), rotation);
+    let body = physics
+        .add_body(&transform, BodyKind::Dynamic, &ColliderShape::Ball { radius: 0.5 })
+        .unwrap();
+
+    let before = transform.model_matrix();
+    physics.sync_transform(body, &mut transform);
+    let after = transform.model_matrix();
+    for c in 0..4 {
+        for r in 0..4 {
+            assert!((before[c][r] - after[c][r]).abs() < 1e-4);
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use bun::glm::{Vec2, Vec3, Vec4};
+use bun::renderer::bvh::Bvh;
+use bun::renderer::mesh_data::MeshData;
+use bun::renderer::ray::Ray;
+use bun::Camera;
+
+fn brute_force(mesh: &MeshData<bun::Vertex>, ray: &Ray) -> Option<f32> {
+    let vertices = mesh.vertices();
+    mesh.indices()
+        .chunks_exact(3)
+        .filter_map(|tri| {
+            ray.intersect_triangle(
+                vertices[tri[0] as usize].v,
+                vertices[tri[1] as usize].v,
+                vertices[tri[2] as usize].v,
+            )
+        })
+        .map(|(t, _, _)| t)
+        .min_by(|a, b| a.total_cmp(b))
+}
+
+#[test]
+fn ray_hits_cube_top() {
+    let cube = MeshData::cube();
+    let bvh = Bvh::build(&cube);
+    let ray = Ray::new(Vec3::new(0.1, 3.0, -0.2), Vec3::new(0.0, -1.0, 0.0));
+
+    let hit = bvh.raycast(&cube, &ray).expect("ray should hit the cube");
+    assert!((hit.distance - 2.5).abs() < 1e-5);
+    assert!((hit.point.y - 0.5).abs() < 1e-5);
+    assert!((hit.normal.y - 1.0).abs() < 1e-5);
+    let b = hit.barycentric;
+    assert!((b.x + b.y + b.z - 1.0).abs() < 1e-5);
+    assert!(b.x >= 0.0 && b.y >= 0.0 && b.z >= 0.0);
+}
+
+#[test]
+fn ray_misses_cube() {
+    let cube = MeshData::cube();
+    let bvh = Bvh::build(&cube);
+    let ray = Ray::new(Vec3::new(2.0, 3.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+    assert!(bvh.raycast(&cube, &ray).is_none());
+}
+
+#[test]
+fn bvh_matches_brute_force() {
+    let mut grid = MeshData::subdiv_quad(24);
+    // make the grid bumpy so the BVH has to split on all axes
+    for v in grid.vertices_mut() {
+        v.v.y = (v.v.x * 9.0).sin() * (v.v.z * 7.0).cos() * 0.2;
+    }
+    let bvh = Bvh::build(&grid);
+
+    let mut rng = bun::fastrand::Rng::with_seed(7);
+    for _ in 0..500 {
+        let origin = Vec3::new(rng.f32() * 2.0 - 1.0, 1.0 + rng.f32(), rng.f32() * 2.0 - 1.0);
+        let target = Vec3::new(rng.f32() - 0.5, 0.0, rng.f32() - 0.5);
+        let ray = Ray::new(origin, target - origin);
+
+        let expected = brute_force(&grid, &ray);
+        let actual = bvh.raycast(&grid, &ray).map(|h| h.distance);
+        match (expected, actual) {
+            (Some(e), Some(a)) => assert!((e - a).abs() < 1e-5, "{} != {}", e, a),
+            (None, None) => {}
+            _ => panic!("bvh {:?} disagrees with brute force {:?}", actual, expected),
+        }
+    }
+}
+
+#[test]
+fn screen_ray_projects_back_to_cursor() {
+    let camera = Camera::new(Vec3::new(1.0, 2.0, -3.0), Vec3::new(-0.2, 0.7, 0.0), 1.2, 16.0 / 9.0, 0.1, 100.0);
+
+    let center = camera.screen_ray(Vec2::new(0.0, 0.0));
+    assert!(bun::glm::length(center.direction - camera.forward()) < 1e-5);
+
+    for mouse in [Vec2::new(0.5, 0.5), Vec2::new(-0.9, 0.3), Vec2::new(0.2, -0.8)] {
+        let ray = camera.screen_ray(mouse);
+        let p = ray.at(10.0);
+        let clip = camera.projection() * camera.view() * Vec4::new(p.x, p.y, p.z, 1.0);
+        let ndc = Vec2::new(clip.x / clip.w, clip.y / clip.w);
+        // mouse y points down, NDC y points up
+        assert!((ndc.x - mouse.x).abs() < 1e-4, "{:?} vs {:?}", ndc, mouse);
+        assert!((ndc.y + mouse.y).abs() < 1e-4, "{:?} vs {:?}", ndc, mouse);
+    }
+}
@@ -0,0 +1,264 @@
+#![cfg(feature = "serde")]
+
+use bun::engine::runtime::AppConfig;
+use bun::glm::{Vec2, Vec3};
+use bun::renderer::buffer::BufferUsage;
+use bun::renderer::material::{
+    BlendMode, Channel, HeightMapData, HeightMode, MaterialData, MaterialPropertyData, NormalMapData, PackedMapData,
+    ShaderRef, SplatLayerData, SplatMapData, TextureRef,
+};
+use bun::renderer::texture::{TextureSpec, TextureUsage};
+use bun::{Camera, Transform};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::path::PathBuf;
+
+fn ron_round_trip<T: Serialize + DeserializeOwned>(value: &T) -> T {
+    let src = ron::to_string(value).unwrap();
+    ron::from_str(&src).unwrap()
+}
+
+fn json_round_trip<T: Serialize + DeserializeOwned>(value: &T) -> T {
+    let src = serde_json::to_string(value).unwrap();
+    serde_json::from_str(&src).unwrap()
+}
+
+fn assert_vec3_eq(a: Vec3, b: Vec3) {
+    assert_eq!((a.x, a.y, a.z), (b.x, b.y, b.z));
+}
+
+#[test]
+fn transform_round_trip() {
+    let transform = Transform::new(
+        Vec3::new(1.0, 2.0, 3.0),
+        Vec3::new(4.0, 5.0, 6.0),
+        Vec3::new(0.1, 0.2, 0.3),
+    );
+    for back in [ron_round_trip(&transform), json_round_trip(&transform)] {
+        assert_vec3_eq(back.pos(), transform.pos());
+        assert_vec3_eq(back.scale(), transform.scale());
+        assert_vec3_eq(back.rotation(), transform.rotation());
+        assert_eq!(back.model_matrix(), transform.model_matrix());
+    }
+}
+
+#[test]
+fn transform_defaults_missing_fields() {
+    let transform: Transform = ron::from_str("(position: (1.0, 0.0, 0.0))").unwrap();
+    assert_vec3_eq(transform.scale(), Vec3::new(1.0, 1.0, 1.0));
+    assert_vec3_eq(transform.rotation(), Vec3::new(0.0, 0.0, 0.0));
+}
+
+#[test]
+fn camera_round_trip() {
+    let camera = Camera::new(Vec3::new(0.0, 1.0, -2.0), Vec3::new(0.3, 1.2, 0.0), 1.2, 16.0 / 9.0, 0.1, 250.0);
+    for back in [ron_round_trip(&camera), json_round_trip(&camera)] {
+        assert_vec3_eq(back.position(), camera.position());
+        assert_vec3_eq(back.rotation(), camera.rotation());
+        assert_eq!(back.fov(), camera.fov());
+        assert_eq!(back.aspect_ratio(), camera.aspect_ratio());
+        assert_eq!(back.near_clip(), camera.near_clip());
+        assert_eq!(back.far_clip(), camera.far_clip());
+        assert_eq!(back.view(), camera.view());
+        assert_eq!(back.projection(), camera.projection());
+    }
+}
+
+#[test]
+fn app_config_round_trip() {
+    let config = AppConfig {
+        width: 1920,
+        height: 1080,
+        title: "serde test".to_string(),
+        max_fps: None,
+    };
+    assert_eq!(ron_round_trip(&config), config);
+    assert_eq!(json_round_trip(&config), config);
+}
+
+#[test]
+fn texture_and_buffer_types_round_trip() {
+    for spec in [TextureSpec::albedo(), TextureSpec::normal(), TextureSpec::data(), TextureSpec::emissive()] {
+        assert_eq!(ron_round_trip(&spec), spec);
+        assert_eq!(json_round_trip(&spec), spec);
+    }
+    for usage in [TextureUsage::Albedo, TextureUsage::Normal, TextureUsage::Data, TextureUsage::Emissive] {
+        assert_eq!(ron_round_trip(&usage), usage);
+    }
+    for usage in [BufferUsage::StaticDraw, BufferUsage::DynamicDraw, BufferUsage::StreamDraw] {
+        assert_eq!(ron_round_trip(&usage), usage);
+        assert_eq!(json_round_trip(&usage), usage);
+    }
+}
+
+#[test]
+fn material_data_round_trip() {
+    let file = |path: &str, spec| TextureRef::File { path: PathBuf::from(path), spec };
+    let material = MaterialData {
+        shader: Some(ShaderRef::Files {
+            vertex: PathBuf::from("res/shaders/pbr.vert"),
+            fragment: PathBuf::from("res/shaders/pbr.frag"),
+            tessellation: Some((PathBuf::from("res/shaders/pbr.tesc"), PathBuf::from("res/shaders/pbr.tese"))),
+        }),
+        albedo: MaterialPropertyData::Texture(file("res/textures/albedo.png", TextureSpec::albedo())),
+        metallic: MaterialPropertyData::Value(0.25),
+        roughness: MaterialPropertyData::Texture(TextureRef::Named("rough".to_string())),
+        normal: Some(NormalMapData { texture: file("res/textures/normal.png", TextureSpec::normal()), scale: 0.5 }),
+        emissive: MaterialPropertyData::Color(Vec3::new(1.0, 0.5, 0.0)),
+        emissive_strength: 4.0,
+        occlusion: None,
+        occlusion_strength: 0.5,
+        packed: Some(PackedMapData {
+            texture: file("res/textures/orm.png", TextureSpec::data()),
+            roughness: Some(Channel::G),
+            metallic: Some(Channel::B),
+            occlusion: Some(Channel::R),
+        }),
+        height: Some(HeightMapData {
+            texture: file("res/textures/height.png", TextureSpec::data()),
+            scale: 0.05,
+            mode: HeightMode::Displacement { edge_length: 16.0, max_level: 32.0 },
+            min_layers: 8,
+            max_layers: 32,
+            self_shadowing: false,
+        }),
+        splat: Some(SplatMapData {
+            weights: TextureRef::Named("splat".to_string()),
+            layers: vec![
+                SplatLayerData {
+                    albedo: PathBuf::from("res/textures/grass.png"),
+                    normal: None,
+                    tiling: 32.0,
+                    roughness: 0.9,
+                },
+                SplatLayerData {
+                    albedo: PathBuf::from("res/textures/rock.png"),
+                    normal: None,
+                    tiling: 16.0,
+                    roughness: 0.7,
+                },
+            ],
+            size: 512,
+        }),
+        texture_scale: Vec2::new(10.0, 10.0),
+        blend_mode: BlendMode::AlphaCutout { threshold: 0.5 },
+        alpha: 0.75,
+    };
+    assert_eq!(ron_round_trip(&material), material);
+    assert_eq!(json_round_trip(&material), material);
+
+    // the wrong kind of property is caught before anything is loaded
+    let mut wrong = material.clone();
+    wrong.roughness = MaterialPropertyData::Color(Vec3::new(0.5, 0.5, 0.5));
+    assert_eq!(wrong.build().err().unwrap(), "Material: roughness must be Value or Texture");
+    let mut wrong = material.clone();
+    wrong.emissive = MaterialPropertyData::Value(1.0);
+    assert_eq!(wrong.build().err().unwrap(), "Material: emissive must be Color or Texture");
+    let mut wrong = material;
+    wrong.splat.as_mut().unwrap().layers[0].normal = Some(PathBuf::from("res/textures/grass_normal.png"));
+    assert_eq!(wrong.build().err().unwrap(), "Material: needs a normal map on every splat layer or none");
+}
+
+#[test]
+fn texture_and_shader_refs_by_name() {
+    let named: MaterialPropertyData = ron::from_str(r#"Texture("brick")"#).unwrap();
+    assert_eq!(named, MaterialPropertyData::Texture(TextureRef::Named("brick".to_string())));
+    assert_eq!(ron::to_string(&named).unwrap(), r#"Texture("brick")"#);
+
+    let shader: ShaderRef = serde_json::from_str(r#"{"vertex": "a.vert", "fragment": "a.frag"}"#).unwrap();
+    assert!(matches!(shader, ShaderRef::Files { tessellation: None, .. }));
+    assert_eq!(serde_json::from_str::<ShaderRef>(r#""pbr""#).unwrap(), ShaderRef::Named("pbr".to_string()));
+}
+
+#[cfg(feature = "scene")]
+#[test]
+fn scene_sky_and_environment() {
+    use bun::scene::scene_desc::{SceneDesc, SceneFormat, SkySourceDesc};
+
+    let src = r#"(
+        environment: Some((path: "sky.hdr")),
+        sky: Some((exposure: -1.5)),
+    )"#;
+    let desc = SceneDesc::parse(src, SceneFormat::Ron).unwrap();
+    let environment = desc.environment.as_ref().unwrap();
+    assert_eq!(environment.path, "sky.hdr");
+    assert_eq!(environment.intensity, 1.0);
+    let sky = desc.sky.as_ref().unwrap();
+    assert!(matches!(sky.source, SkySourceDesc::Environment));
+    assert_eq!((sky.exposure, sky.rotation), (-1.5, 0.0));
+
+    let json = desc.serialize(SceneFormat::Json).unwrap();
+    let back = SceneDesc::parse(&json, SceneFormat::Json).unwrap();
+    assert_eq!(back.sky.unwrap().exposure, -1.5);
+
+    let cube: SceneDesc = SceneDesc::parse(
+        r#"(sky: Some((source: CubeMap(("px.png", "nx.png", "py.png", "ny.png", "pz.png", "nz.png")), rotation: 0.5)))"#,
+        SceneFormat::Ron,
+    )
+    .unwrap();
+    assert!(matches!(&cube.sky.unwrap().source, SkySourceDesc::CubeMap(faces) if faces[3] == "ny.png"));
+
+    let atmosphere = SceneDesc::parse(r#"(sky: Some((source: Atmosphere((time_of_day: 17.5)))))"#, SceneFormat::Ron).unwrap();
+    match atmosphere.sky.unwrap().source {
+        SkySourceDesc::Atmosphere(desc) => {
+            assert_eq!(desc.time_of_day, 17.5);
+            assert_eq!(desc.turbidity, bun::renderer::atmosphere::Atmosphere::default().turbidity);
+        }
+        other => panic!("expected an atmosphere, got {:?}", other),
+    }
+    let scene = SceneDesc::load("../kadse/res/scenes/default.ron").unwrap();
+    let ron = scene.serialize(SceneFormat::Ron).unwrap();
+    let back = SceneDesc::parse(&ron, SceneFormat::Ron).unwrap();
+    assert_eq!(back.materials, scene.materials);
+}
+
+#[cfg(feature = "scene")]
+#[test]
+fn scene_terrain_defaults() {
+    use bun::renderer::terrain::TerrainSettings;
+    use bun::scene::scene_desc::{SceneDesc, SceneFormat};
+
+    let src = r#"(
+        materials: {
+            "terrain": (
+                shader: "pbr",
+                albedo: Color((1.0, 1.0, 1.0)),
+                metallic: Value(0.0),
+                roughness: Value(1.0),
+                splat: Some((weights: "splat", layers: [(albedo: "grass.png", tiling: 32.0), (albedo: "rock.png")])),
+            ),
+        },
+        terrain: Some((heightmap: "height.png", material: "terrain", height_scale: 20.0)),
+    )"#;
+    let desc = SceneDesc::parse(src, SceneFormat::Ron).unwrap();
+    let splat = desc.materials["terrain"].splat.as_ref().unwrap();
+    assert_eq!(splat.layers.len(), 2);
+    assert_eq!((splat.layers[0].tiling, splat.layers[1].tiling, splat.layers[1].roughness), (32.0, 1.0, 1.0));
+    assert!(splat.layers[1].normal.is_none());
+
+    let terrain = desc.terrain.as_ref().unwrap();
+    let defaults = TerrainSettings::default();
+    assert_eq!(terrain.height_scale, 20.0);
+    assert_eq!((terrain.size, terrain.chunks, terrain.lod_levels), (defaults.size, defaults.chunks, defaults.lod_levels));
+    assert_eq!(terrain.origin, [0.0; 3]);
+    assert!(terrain.cast_shadows);
+}
+
+#[cfg(feature = "scene")]
+#[test]
+fn scene_rejects_mismatched_material_properties() {
+    use bun::scene::scene::Scene;
+    use bun::scene::scene_desc::{SceneDesc, SceneFormat};
+
+    let scene = |albedo: &str, metallic: &str| {
+        let src = format!(
+            r#"(materials: {{ "rock": (shader: "pbr", albedo: {}, metallic: {}, roughness: Value(0.5)) }})"#,
+            albedo, metallic
+        );
+        Scene::from_desc(SceneDesc::parse(&src, SceneFormat::Ron).unwrap(), 1.0)
+    };
+    let err = scene("Color((1.0, 1.0, 1.0))", "Color((1.0, 0.0, 0.0))").err().unwrap();
+    assert_eq!(err, "Material 'rock': metallic must be Value or Texture");
+    let err = scene("Value(1.0)", "Value(0.0)").err().unwrap();
+    assert_eq!(err, "Material 'rock': albedo must be Color or Texture");
+}
@@ -0,0 +1,42 @@
+use bun::renderer::shader::Shader;
+use std::path::Path;
+
+#[test]
+fn includes_are_resolved_relative_to_the_file() {
+    let src = Shader::load_source(Path::new("tests/data/shaders/main.frag")).unwrap();
+    assert!(src.starts_with("#version 460\n"));
+    assert!(src.contains("uniform float shared_value;\nuniform float inner_value;\n"));
+    assert!(!src.contains("#include"));
+    assert!(src.ends_with("void main() {}\n"));
+}
+
+#[test]
+fn include_errors() {
+    assert!(Shader::load_source(Path::new("tests/data/shaders/cycle.glsl")).unwrap_err().contains("nested includes"));
+    assert!(Shader::load_source(Path::new("tests/data/shaders/missing.frag")).is_err());
+}
+
+#[test]
+fn engine_includes() {
+    let src = Shader::resolve_includes("#version 460\n#include \"bun/material.glsl\"\n").unwrap();
+    assert!(src.contains("uniform MaterialUBO"));
+    // without a file there is nothing to resolve other includes against
+    assert!(Shader::resolve_includes("#include \"common.glsl\"").unwrap_err().contains("Unknown shader include"));
+}
+
+#[test]
+fn forward_and_deferred_share_material_and_lighting() {
+    let material = Shader::resolve_includes("#include \"bun/material.glsl\"").unwrap();
+    let lighting = Shader::resolve_includes("#include \"bun/lighting.glsl\"").unwrap();
+    for stage in ["pbr.frag", "pbr_displacement.tesc", "pbr_displacement.tese"] {
+        let src = Shader::load_source(&Path::new("../kadse/res/shaders").join(stage)).unwrap();
+        assert!(src.contains(&material), "{} doesn't include the material block", stage);
+    }
+    let forward = Shader::load_source(Path::new("../kadse/res/shaders/pbr.frag")).unwrap();
+    assert!(forward.contains(&lighting));
+
+    let gbuffer = Shader::resolve_includes(include_str!("../src/res/shaders/gbuffer.frag")).unwrap();
+    assert!(gbuffer.contains(&material));
+    let deferred = Shader::resolve_includes(include_str!("../src/res/shaders/deferred_lighting.frag")).unwrap();
+    assert!(deferred.contains(&lighting));
+}
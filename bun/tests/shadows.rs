@@ -0,0 +1,130 @@
+use bun::glm::{Vec3, Vec4};
+use bun::renderer::light::{Light, PointShadowSettings};
+use bun::renderer::shadow::{
+    cascade_splits, compute_cascades, cube_face_view_projs, ortho, select_point_shadows, ShadowSettings,
+};
+use bun::Camera;
+
+fn camera() -> Camera {
+    Camera::new(Vec3::new(2.0, 3.0, -4.0), Vec3::new(-0.3, 0.8, 0.0), 1.2, 16.0 / 9.0, 0.1, 200.0)
+}
+
+#[test]
+fn splits_are_increasing_and_end_at_far() {
+    for lambda in [0.0, 0.5, 1.0] {
+        let splits = cascade_splits(0.1, 80.0, 4, lambda);
+        assert_eq!(splits.len(), 4);
+        assert!(splits.windows(2).all(|w| w[0] < w[1]), "{:?}", splits);
+        assert!((splits[3] - 80.0).abs() < 1e-3);
+    }
+    // uniform splits are evenly spaced
+    let uniform = cascade_splits(1.0, 101.0, 4, 0.0);
+    assert!((uniform[0] - 26.0).abs() < 1e-4);
+}
+
+#[test]
+fn ortho_maps_box_to_clip_cube() {
+    let m = ortho(-2.0, 4.0, -1.0, 3.0, 0.5, 10.0);
+    let near_corner = m * Vec4::new(-2.0, -1.0, -0.5, 1.0);
+    let far_corner = m * Vec4::new(4.0, 3.0, -10.0, 1.0);
+    for (a, b) in [(near_corner.x, -1.0), (near_corner.y, -1.0), (near_corner.z, -1.0), (far_corner.x, 1.0), (far_corner.y, 1.0), (far_corner.z, 1.0)] {
+        assert!((a - b).abs() < 1e-5, "{} != {}", a, b);
+    }
+}
+
+#[test]
+fn cascades_cover_their_frustum_slice() {
+    let camera = camera();
+    let settings = ShadowSettings::default();
+    let light = Vec3::new(-1.0, -0.4, 0.2);
+    let cascades = compute_cascades(&camera.view(), &camera.projection(), light, &settings);
+    assert_eq!(cascades.len(), settings.cascade_count);
+    assert!((cascades.last().unwrap().split_far - settings.max_distance).abs() < 1e-3);
+
+    let tan_half_fov = (camera.fov() / 2.0).tan();
+    let mut split_near = camera.near_clip();
+    for cascade in &cascades {
+        for distance in [split_near, cascade.split_far] {
+            for (x, y) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0), (0.0, 0.0)] {
+                let p = camera.position()
+                    + camera.forward() * distance
+                    + camera.right() * (x * distance * tan_half_fov * camera.aspect_ratio())
+                    + camera.up() * (y * distance * tan_half_fov);
+                let clip = cascade.view_proj * Vec4::new(p.x, p.y, p.z, 1.0);
+                let eps = 1e-3;
+                assert!(clip.x.abs() <= 1.0 + eps && clip.y.abs() <= 1.0 + eps, "{:?} outside cascade", p);
+                assert!(clip.z >= -1.0 - eps && clip.z <= 1.0 + eps, "{:?} outside cascade depth", p);
+            }
+        }
+        split_near = cascade.split_far;
+    }
+}
+
+#[test]
+fn cascades_are_stable_under_small_camera_moves() {
+    // texel snapping: a moving camera only ever shifts the cascade by whole texels
+    let settings = ShadowSettings::default();
+    let light = Vec3::new(-1.0, -0.4, 0.2);
+    let mut camera = camera();
+    let before = compute_cascades(&camera.view(), &camera.projection(), light, &settings);
+    camera.set_position(camera.position() + Vec3::new(0.0123, 0.0, 0.0071));
+    let after = compute_cascades(&camera.view(), &camera.projection(), light, &settings);
+
+    let texels = settings.resolution as f32 / 2.0;
+    for (a, b) in before.iter().zip(&after) {
+        let origin = Vec4::new(0.0, 0.0, 0.0, 1.0);
+        let shift = (b.view_proj * origin - a.view_proj * origin) * texels;
+        assert!((shift.x - shift.x.round()).abs() < 1e-2, "{}", shift.x);
+        assert!((shift.y - shift.y.round()).abs() < 1e-2, "{}", shift.y);
+    }
+}
+
+#[test]
+fn point_shadows_within_budget() {
+    let white = Vec3::new(1.0, 1.0, 1.0);
+    let shadow = PointShadowSettings::new(256, 0.1, 10.0);
+    let lights = [
+        Light::directional(Vec3::new(0.0, -1.0, 0.0), white, 3.0),
+        Light::point(Vec3::new(0.0, 0.0, 0.0), white, 5.0, 10.0).with_shadow(shadow),
+        // brightest, but no shadow requested
+        Light::point(Vec3::new(1.0, 0.0, 0.0), white, 50.0, 10.0),
+        Light::point(Vec3::new(2.0, 0.0, 0.0), white, 20.0, 10.0).with_shadow(shadow),
+        Light::point(Vec3::new(200.0, 0.0, 0.0), white, 20.0, 10.0).with_shadow(shadow),
+        Light::spot(Vec3::new(0.0, 3.0, 0.0), Vec3::new(0.0, -1.0, 0.0), white, 10.0, 10.0, 0.3, 0.5).with_shadow(shadow),
+    ];
+    let camera_position = Vec3::new(0.0, 1.0, 0.0);
+
+    assert_eq!(select_point_shadows(&lights, camera_position, 2), vec![3, 5]);
+    // the far away light ranks last even though it is brighter than the first one
+    assert_eq!(select_point_shadows(&lights, camera_position, 10), vec![3, 5, 1, 4]);
+    assert!(select_point_shadows(&lights, camera_position, 0).is_empty());
+}
+
+#[test]
+fn cube_faces_look_along_the_axes() {
+    let position = Vec3::new(1.0, 2.0, 3.0);
+    let faces = cube_face_view_projs(position, 0.1, 20.0);
+    let axes = [
+        Vec3::new(1.0, 0.0, 0.0),
+        Vec3::new(-1.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        Vec3::new(0.0, -1.0, 0.0),
+        Vec3::new(0.0, 0.0, 1.0),
+        Vec3::new(0.0, 0.0, -1.0),
+    ];
+    for (face, view_proj) in faces.iter().enumerate() {
+        for (axis_index, axis) in axes.iter().enumerate() {
+            let p = position + *axis * 5.0;
+            let clip = *view_proj * Vec4::new(p.x, p.y, p.z, 1.0);
+            if axis_index == face {
+                // centered in front of the face, inside the depth range
+                assert!(clip.w > 0.0);
+                assert!((clip.x / clip.w).abs() < 1e-4 && (clip.y / clip.w).abs() < 1e-4);
+                assert!((clip.z / clip.w).abs() < 1.0);
+            } else {
+                let outside = clip.w <= 0.0 || (clip.x / clip.w).abs() > 1.0 || (clip.y / clip.w).abs() > 1.0;
+                assert!(outside, "axis {} visible on face {}", axis_index, face);
+            }
+        }
+    }
+}
@@ -0,0 +1,20 @@
+use bun::glm::length;
+use bun::renderer::ssao::{ssao_kernel, MAX_SSAO_SAMPLES};
+
+#[test]
+fn kernel_stays_in_the_normal_hemisphere() {
+    let kernel = ssao_kernel(32);
+    assert_eq!(kernel.len(), 32);
+    for sample in &kernel {
+        assert!(sample.z >= 0.0, "{:?}", sample);
+        assert!(length(*sample) <= 1.0, "{:?}", sample);
+    }
+    // seeded, the uniforms don't change between runs
+    assert_eq!(kernel, ssao_kernel(32));
+}
+
+#[test]
+fn kernel_size_is_clamped() {
+    assert_eq!(ssao_kernel(0).len(), 1);
+    assert_eq!(ssao_kernel(1000).len(), MAX_SSAO_SAMPLES as usize);
+}
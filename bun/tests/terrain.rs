@@ -0,0 +1,145 @@
+use bun::glm::Vec3;
+use bun::renderer::terrain::{balance_lods, CoarserEdges, Heightmap, TerrainData, TerrainSettings};
+
+fn settings(size: f32, chunks: u32, chunk_resolution: u32) -> TerrainSettings {
+    TerrainSettings {
+        size,
+        height_scale: 2.0,
+        chunks,
+        chunk_resolution,
+        lod_levels: 4,
+        lod_distance: 4.0,
+    }
+}
+
+// quadratic residues, no three evenly spaced samples are on a line
+fn noise_heightmap(width: u32) -> Heightmap {
+    let samples = (0..width * width).map(|i| ((i * i) % 101 * 37 % 101) as f32 / 100.0).collect();
+    Heightmap::from_samples(width, width, samples).unwrap()
+}
+
+#[test]
+fn heightmap_sampling() {
+    let heightmap = Heightmap::from_samples(2, 2, vec![0.0, 1.0, 0.0, 1.0]).unwrap();
+    assert_eq!(heightmap.sample(0.5, 0.0), 0.5);
+    assert_eq!(heightmap.sample(0.25, 0.75), 0.25);
+    // clamped to the edges
+    assert_eq!(heightmap.sample(2.0, -1.0), 1.0);
+
+    assert!(Heightmap::from_samples(2, 2, vec![0.0; 3]).is_err());
+    assert!(Heightmap::from_samples(1, 4, vec![0.0; 4]).is_err());
+}
+
+#[test]
+fn height_and_normal_queries() {
+    // rises along +X from 0 to height_scale
+    let heightmap = Heightmap::from_samples(2, 2, vec![0.0, 1.0, 0.0, 1.0]).unwrap();
+    let terrain = TerrainData::new(heightmap, settings(10.0, 1, 4));
+
+    assert!((terrain.height_at(0.0, 0.0).unwrap() - 1.0).abs() < 1e-5);
+    assert!((terrain.height_at(5.0, 3.0).unwrap() - 2.0).abs() < 1e-5);
+    assert_eq!(terrain.height_at(5.5, 0.0), None);
+    assert_eq!(terrain.normal_at(0.0, -6.0), None);
+
+    let normal = terrain.normal_at(0.0, 0.0).unwrap();
+    let expected = bun::glm::normalize(Vec3::new(-0.2, 1.0, 0.0));
+    assert!(bun::glm::length(normal - expected) < 1e-5, "{:?} != {:?}", normal, expected);
+}
+
+#[test]
+fn settings_are_sanitized() {
+    let terrain = TerrainData::new(noise_heightmap(4), settings(8.0, 0, 6));
+    assert_eq!(terrain.settings().chunks, 1);
+    assert_eq!(terrain.settings().chunk_resolution, 8);
+    // 8, 4, 2 and 1 quads
+    assert_eq!(terrain.settings().lod_levels, 4);
+
+    let terrain = TerrainData::new(noise_heightmap(4), settings(8.0, 1, 2));
+    assert_eq!(terrain.settings().lod_levels, 2);
+}
+
+#[test]
+fn chunk_vertex_counts() {
+    let terrain = TerrainData::new(noise_heightmap(33), settings(16.0, 2, 8));
+    for (lod, quads) in [(0, 8), (1, 4), (2, 2), (3, 1)] {
+        let mesh = terrain.chunk_mesh_data(1, 0, lod, CoarserEdges::default());
+        assert_eq!(mesh.vertices().len(), (quads + 1) * (quads + 1));
+        assert_eq!(mesh.indices().len(), quads * quads * 6);
+    }
+
+    // chunk 1, 0 spans x 0..8, z -8..0
+    let mesh = terrain.chunk_mesh_data(1, 0, 0, CoarserEdges::default());
+    assert_eq!(mesh.vertices()[0].v.x, 0.0);
+    assert_eq!(mesh.vertices()[0].v.z, -8.0);
+    assert_eq!(mesh.vertices().last().unwrap().v.x, 8.0);
+    assert_eq!(mesh.vertices().last().unwrap().v.z, 0.0);
+    for vertex in mesh.vertices() {
+        assert_eq!(vertex.v.y, terrain.height_at(vertex.v.x, vertex.v.z).unwrap());
+    }
+}
+
+#[test]
+fn triangles_face_up() {
+    let terrain = TerrainData::new(noise_heightmap(9), settings(8.0, 1, 8));
+    let mesh = terrain.chunk_mesh_data(0, 0, 0, CoarserEdges::default());
+    for triangle in mesh.indices().chunks(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| mesh.vertices()[triangle[i] as usize].v);
+        assert!(bun::glm::cross(b - a, c - a).y > 0.0);
+    }
+}
+
+#[test]
+fn stitched_edge_matches_coarser_neighbour() {
+    let terrain = TerrainData::new(noise_heightmap(33), settings(16.0, 2, 8));
+    // chunk 0 at level 1 on the -X side of chunk 1 at level 0
+    let coarse = terrain.chunk_mesh_data(0, 0, 1, CoarserEdges::default());
+    let fine = terrain.chunk_mesh_data(1, 0, 0, CoarserEdges { neg_x: true, ..Default::default() });
+
+    let coarse_edge: Vec<Vec3> = coarse.vertices().iter().map(|v| v.v).filter(|v| v.x == 0.0).collect();
+    let fine_edge: Vec<Vec3> = fine.vertices().iter().map(|v| v.v).filter(|v| v.x == 0.0).collect();
+    assert_eq!(coarse_edge.len(), 5);
+    assert_eq!(fine_edge.len(), 9);
+    for (i, vertex) in fine_edge.iter().enumerate() {
+        let expected = if i % 2 == 0 {
+            coarse_edge[i / 2].y
+        } else {
+            (coarse_edge[i / 2].y + coarse_edge[i / 2 + 1].y) * 0.5
+        };
+        assert!((vertex.y - expected).abs() < 1e-5, "vertex {}: {} != {}", i, vertex.y, expected);
+    }
+
+    // the other edges keep their own heights
+    let unstitched = terrain.chunk_mesh_data(1, 0, 0, CoarserEdges::default());
+    let inner: Vec<f32> = unstitched.vertices().iter().filter(|v| v.v.x == 0.0).map(|v| v.v.y).collect();
+    assert_ne!(inner[1], fine_edge[1].y);
+    for (a, b) in unstitched.vertices().iter().zip(fine.vertices()).filter(|(a, _)| a.v.x != 0.0) {
+        assert_eq!(a.v.y, b.v.y);
+    }
+}
+
+#[test]
+fn lods_are_balanced() {
+    let mut lods = vec![0, 3, 3, 3];
+    balance_lods(&mut lods, 2);
+    assert_eq!(lods, [0, 1, 1, 2]);
+
+    let mut lods = vec![3, 3, 3, 3, 3, 0];
+    balance_lods(&mut lods, 3);
+    assert_eq!(lods, [3, 2, 1, 2, 1, 0]);
+}
+
+#[test]
+fn lods_grow_with_distance() {
+    let terrain = TerrainData::new(noise_heightmap(65), settings(64.0, 8, 8));
+    // above the -X, -Z corner chunk
+    let lods = terrain.select_lods(Vec3::new(-30.0, 1.0, -30.0));
+    assert_eq!(lods.len(), 64);
+    assert_eq!(lods[0], 0);
+    assert_eq!(lods[63], 3);
+    for z in 0..8 {
+        for x in 1..8 {
+            let (a, b) = (lods[z * 8 + x - 1], lods[z * 8 + x]);
+            assert!(a <= b && b - a <= 1, "{:?}", lods);
+        }
+    }
+}
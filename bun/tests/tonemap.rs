@@ -0,0 +1,61 @@
+use bun::renderer::tonemap::{
+    adapt_luminance, exposure_from_luminance, histogram_average_luminance, luminance_histogram_bin,
+    AutoExposureSettings, Tonemapper, HISTOGRAM_BINS,
+};
+
+#[test]
+fn histogram_bins_cover_the_log_range() {
+    let settings = AutoExposureSettings { min_log_luminance: -4.0, ..Default::default() };
+    assert_eq!(luminance_histogram_bin(0.0, &settings), 0);
+    assert_eq!(luminance_histogram_bin(2f32.powf(settings.min_log_luminance), &settings), 1);
+    assert_eq!(luminance_histogram_bin(1e6, &settings), HISTOGRAM_BINS - 1);
+    assert!(luminance_histogram_bin(0.5, &settings) < luminance_histogram_bin(2.0, &settings));
+}
+
+#[test]
+fn histogram_average_ignores_black_pixels() {
+    let settings = AutoExposureSettings::default();
+    let mut histogram = [0u32; HISTOGRAM_BINS];
+    histogram[luminance_histogram_bin(1.0, &settings)] = 100;
+    histogram[0] = 10_000;
+    let average = histogram_average_luminance(&histogram, &settings);
+    // one bin is ~0.055 stops wide
+    assert!((average.log2()).abs() < 0.06, "average {}", average);
+}
+
+#[test]
+fn adaptation_converges_without_overshooting() {
+    let settings = AutoExposureSettings::default();
+    let mut luminance = 0.1;
+    for _ in 0..600 {
+        luminance = adapt_luminance(luminance, 1.0, 1.0 / 60.0, &settings);
+        assert!(luminance <= 1.0);
+    }
+    assert!((luminance - 1.0).abs() < 1e-3);
+    // darkening is slower than brightening
+    let up = adapt_luminance(1.0, 2.0, 0.1, &settings) - 1.0;
+    let down = 1.0 - adapt_luminance(1.0, 0.0, 0.1, &settings);
+    assert!(up > down);
+}
+
+#[test]
+fn exposure_maps_average_to_middle_grey() {
+    // every scene average ends up at the same exposed value, regardless of how bright it is
+    for luminance in [0.01, 0.18, 5.0, 1000.0] {
+        assert!((luminance * exposure_from_luminance(luminance) - 1.0 / 9.6).abs() < 1e-5);
+    }
+    assert!(exposure_from_luminance(0.0).is_finite());
+}
+
+#[test]
+fn tonemapper_cycle_visits_every_curve() {
+    let mut tonemapper = Tonemapper::default();
+    let mut indices = vec![];
+    for _ in 0..5 {
+        indices.push(tonemapper.shader_index());
+        tonemapper = tonemapper.next();
+    }
+    assert_eq!(tonemapper, Tonemapper::default());
+    indices.sort();
+    assert_eq!(indices, vec![0, 1, 2, 3, 4]);
+}
@@ -0,0 +1,49 @@
+use bun::glm::Vec3;
+use bun::Transform;
+
+fn assert_vec3_near(a: Vec3, b: Vec3) {
+    assert!(bun::glm::length(a - b) < 1e-4, "{:?} != {:?}", a, b);
+}
+
+#[test]
+fn from_matrix_round_trip() {
+    for rotation in [
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.3, -1.2, 2.5),
+        Vec3::new(-2.0, 0.7, -0.4),
+    ] {
+        let transform = Transform::new(Vec3::new(1.0, -2.0, 3.0), Vec3::new(2.0, 0.5, 1.5), rotation);
+        let back = Transform::from_matrix(&transform.model_matrix());
+        assert_vec3_near(back.pos(), transform.pos());
+        assert_vec3_near(back.scale(), transform.scale());
+        assert_vec3_near(back.rotation(), rotation);
+    }
+}
+
+#[test]
+fn from_matrix_gimbal_lock() {
+    // a quarter turn around y puts x and z on the same axis, the matrix still has to match
+    let transform = Transform::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 1.0), Vec3::new(0.4, std::f32::consts::FRAC_PI_2, 0.9));
+    let back = Transform::from_matrix(&transform.model_matrix());
+    let (a, b) = (transform.model_matrix(), back.model_matrix());
+    for column in 0..4 {
+        assert!(bun::glm::length(a[column] - b[column]) < 1e-4, "{:?} != {:?}", a, b);
+    }
+}
+
+#[test]
+fn from_matrix_keeps_mirroring() {
+    let transform = Transform::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(-1.0, 1.0, 1.0), Vec3::new(0.0, 0.0, 0.0));
+    let back = Transform::from_matrix(&transform.model_matrix());
+    assert_vec3_near(back.scale(), Vec3::new(-1.0, 1.0, 1.0));
+}
+
+#[test]
+fn euler_from_quaternion() {
+    // half angle sines and cosines of a single axis rotation
+    let (s, c) = (0.25_f32.sin(), 0.25_f32.cos());
+    assert_vec3_near(Transform::euler_from_quaternion([s, 0.0, 0.0, c]), Vec3::new(0.5, 0.0, 0.0));
+    assert_vec3_near(Transform::euler_from_quaternion([0.0, s, 0.0, c]), Vec3::new(0.0, 0.5, 0.0));
+    assert_vec3_near(Transform::euler_from_quaternion([0.0, 0.0, s, c]), Vec3::new(0.0, 0.0, 0.5));
+    assert_vec3_near(Transform::euler_from_quaternion([0.0, 0.0, 0.0, 1.0]), Vec3::new(0.0, 0.0, 0.0));
+}
@@ -0,0 +1,27 @@
+#version 460
+
+layout (location = 0) in vec3 in_pos;
+layout (location = 1) in vec3 in_normal;
+layout (location = 2) in vec2 in_uv;
+layout (location = 3) in vec4 in_tangent;
+layout (location = 4) in mat4 in_instance_model;
+
+uniform mat4 model_mat;
+// set by the renderer for instanced draws, the model matrix then comes per instance
+uniform bool instanced;
+
+// world space, projected after the displacement in pbr_displacement.tese
+out vec3 control_pos;
+out vec3 control_normal;
+out vec4 control_tangent;
+out vec2 control_uv;
+
+void main() {
+    mat4 model = instanced ? in_instance_model : model_mat;
+    mat3 normal_mat = transpose(inverse(mat3(model)));
+
+    control_pos = vec3(model * vec4(in_pos, 1.0));
+    control_normal = normalize(normal_mat * in_normal);
+    control_tangent = vec4(normalize(normal_mat * in_tangent.xyz), in_tangent.w);
+    control_uv = in_uv;
+}
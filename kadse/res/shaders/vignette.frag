@@ -0,0 +1,17 @@
+#version 460 core
+
+in vec2 frag_uv;
+
+layout(binding = 0) uniform sampler2D source;
+uniform float strength = 0.4;
+uniform float radius = 0.75;
+
+out vec4 out_color;
+
+// darkens the corners, runs on the HDR frame so it is a plain multiply
+void main() {
+    vec3 color = texture(source, frag_uv).rgb;
+    float dist = length(frag_uv - 0.5) * 1.41421356;
+    float vignette = 1.0 - strength * smoothstep(radius * 0.5, radius * 1.25, dist);
+    out_color = vec4(color * vignette, 1.0);
+}
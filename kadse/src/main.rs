@@ -1,201 +1,108 @@
 use bun::engine::engine::Engine;
 use bun::engine::runtime::{run, App, AppConfig, AppControl, Time};
-use bun::glm::{Vec2, Vec4};
-use bun::renderer::material::{Material, MaterialProperty, NormalMap};
-use bun::renderer::render_object::RenderObject;
-use bun::{glm, glm::Vec3, Camera, Event, Keycode, Mesh, One, Shader, Texture, Transform, Zero};
+use bun::glm::Vec4;
+use bun::renderer::deferred::RenderPath;
+use bun::renderer::light::{Light, PointShadowSettings};
+use bun::renderer::post_process::PostProcessPass;
+use bun::renderer::tonemap::AutoExposureSettings;
+use bun::scene::scene::Scene;
+use bun::sdl3::mouse::MouseButton;
+use bun::{glm, glm::Vec3, Camera, Event, Keycode};
 use std::f32::consts::PI;
 use std::path::PathBuf;
-use std::sync::Arc;
-use bun::renderer::mesh_data::MeshData;
-use bun::renderer::texture::TextureSpec;
 
 const DEG_TO_RAD: f32 = PI / 180.0;
+const DEFAULT_SCENE: &str = "kadse/res/scenes/default.ron";
 
 
 struct GameState {
-    camera: Camera,
-    
-    bunny: RenderObject,
-    cube: RenderObject,
-    sphere: RenderObject,
-    ground: RenderObject,
+    scene: Scene,
+    scene_path: PathBuf,
+    selected: Option<String>,
     
     speed: f32,
     rot_speed: f32,
     t: f32,
+    // hours per second, 0 stops the sun
+    day_speed: f32,
 }
 
 impl GameState {
-    fn new(engine: &Engine) -> Result<Self, String> {
-        let default_shader = Arc::new(Shader::new(
-            &PathBuf::from("kadse/res/shaders/default.vert"),
-            &PathBuf::from("kadse/res/shaders/default.frag"),
-        )?);
-        
-        let pbr_shader = Arc::new(Shader::new(
-            &PathBuf::from("kadse/res/shaders/pbr.vert"),
-            &PathBuf::from("kadse/res/shaders/pbr.frag"),
-        )?);
-        
-        let bunny_texture = Arc::new(Texture::new("kadse/res/textures/gltf_embedded_0.png", TextureSpec::albedo())?);
-        let bunny_mat = Arc::new(Material {
-            shader: pbr_shader.clone(),
-            albedo: MaterialProperty::Texture(bunny_texture.clone()),
-            metallic: MaterialProperty::Value(0.0),
-            roughness: MaterialProperty::Value(1.0),
-            normal: NormalMap::None,
-            ..Default::default()
-        });
-        
-        let bunny_mesh = Arc::new(Mesh::from_model(PathBuf::from(
-            "kadse/res/models/rabbit.obj",
-        ))?);
-        
-        let bunny_transform = Transform::new(
-            Vec3::new(0.0, 3.0, 12.5),
-            Vec3::new(40.0, 40.0, 40.0),
-            // Vec3::new(0.0, -90f32 * DEG_TO_RAD, 0.0),
-            Vec3::new(0.0, 0.0, 0.0),
-        );
-        
-        let bunny = RenderObject::new(bunny_transform, bunny_mesh.clone(), bunny_mat.clone());
-        
-        
-        let cube_mesh = Arc::new(Mesh::from_model(PathBuf::from(
-            "kadse/res/models/TestCube/TestCube.obj"
-        ))?);
-        let sphere_mesh = Arc::new(Mesh::from_model(PathBuf::from(
-            "kadse/res/models/sphere.obj"
-        ))?);
-        // let cube_albedo = Arc::new(Texture::new("kadse/res/models/TestCube/Mat_Terracotta/D_Terracotta.jpg", TextureSpec::albedo())?);
-        // let cube_metallic = Arc::new(Texture::new("kadse/res/models/TestCube/Mat_Terracotta/M_Terracotta.png", TextureSpec::data())?);
-        // let cube_normal = Arc::new(Texture::new("kadse/res/models/TestCube/Mat_Terracotta/N_Terracotta.jpg", TextureSpec::normal())?);
-        // let cube_roughness = Arc::new(Texture::new("kadse/res/models/TestCube/Mat_Terracotta/R_Terracotta.png", TextureSpec::data())?);
-        
-        // let cube_albedo = Arc::new(Texture::new("kadse/res/models/TestCube/Mat_PinkGlass/D_PinkGlass.jpg", TextureSpec::albedo())?);
-        // let cube_metallic = Arc::new(Texture::new("kadse/res/models/TestCube/Mat_PinkGlass/M_PinkGlass.jpg", TextureSpec::data())?);
-        // let cube_normal = Arc::new(Texture::new("kadse/res/models/TestCube/Mat_PinkGlass/N_PinkGlass.png", TextureSpec::normal())?);
-        // let cube_roughness = Arc::new(Texture::new("kadse/res/models/TestCube/Mat_PinkGlass/R_PinkGlass.jpg", TextureSpec::data())?);
-        
-        // let cube_albedo = Arc::new(Texture::new("kadse/res/models/TestCube/Mat_MetalBubbles/D_MetalBubbles.png", TextureSpec::albedo())?);
-        // let cube_metallic = Arc::new(Texture::new("kadse/res/models/TestCube/Mat_MetalBubbles/M_MetalBubbles.png", TextureSpec::data())?);
-        // let cube_normal = Arc::new(Texture::new("kadse/res/models/TestCube/Mat_MetalBubbles/N_MetalBubbles.png", TextureSpec::normal())?);
-        // let cube_roughness = Arc::new(Texture::new("kadse/res/models/TestCube/Mat_MetalBubbles/R_MetalBubbles.png", TextureSpec::data())?);
-        
-        let cube_albedo = Arc::new(Texture::new("kadse/res/models/TestCube/Mat_Wooden/D_Wooden.png", TextureSpec::albedo())?);
-        // let cube_metallic = Arc::new(Texture::new("kadse/res/models/TestCube/Mat_Wooden/M_", TextureSpec::data())?);
-        let cube_normal = Arc::new(Texture::new("kadse/res/models/TestCube/Mat_Wooden/N_Wooden.png", TextureSpec::normal())?);
-        let cube_roughness = Arc::new(Texture::new("kadse/res/models/TestCube/Mat_Wooden/R_Wooden.png", TextureSpec::data())?);
-        
-        let cube_mat = Arc::new(Material {
-            shader: pbr_shader.clone(),
-            albedo: MaterialProperty::Texture(cube_albedo.clone()),
-            // albedo: MaterialProperty::Color(Vec3::new(0.8, 0.589, 0.006)),
-            // metallic: MaterialProperty::Texture(cube_metallic.clone()),
-            metallic: MaterialProperty::Value(0.0),
-            normal: NormalMap::Texture {texture: cube_normal.clone(), scale: 1.0},
-            // normal: NormalMap::None,
-            roughness: MaterialProperty::Texture(cube_roughness.clone()),
-            // roughness: MaterialProperty::Value(0.705),
-            ..Default::default()
-        });
-        let cube = RenderObject::new(
-            Transform::new(
-                Vec3::new(0.0, 0.0, 5.0),
-                Vec3::one() * 5.0,
-                Vec3::zero()
-            ),
-            cube_mesh.clone(),
-            cube_mat.clone(),
-        );
-        
-        let sphere_mat = Arc::new(Material {
-            shader: pbr_shader.clone(),
-            albedo: MaterialProperty::Color(Vec3::one()),
-            metallic: MaterialProperty::Value(1.0),
-            normal: NormalMap::None,
-            roughness: MaterialProperty::Value(0.13),
-            ..Default::default()
-        });
-        let sphere = RenderObject::new(
-            Transform::new(
-                Vec3::new(2.5, 0.5, 5.0),
-                Vec3::one() * 0.75,
-                Vec3::zero()
-            ),
-            sphere_mesh.clone(),
-            sphere_mat.clone(),
-        );
-        
-        let ground_albedo = Arc::new(Texture::new("kadse/res/textures/rocky_ground/rocky_terrain_diff_4k.jpg", TextureSpec::albedo())?);
-        let ground_normal = Arc::new(Texture::new("kadse/res/textures/rocky_ground/rocky_terrain_nor_gl_4k.png", TextureSpec::normal())?);
-        let ground_roughness = Arc::new(Texture::new("kadse/res/textures/rocky_ground/rocky_terrain_rough_4k.png", TextureSpec::data())?);
-        let ground_mat = Arc::new(Material {
-            shader: pbr_shader.clone(),
-            albedo: MaterialProperty::Texture(ground_albedo.clone()),
-            normal: NormalMap::Texture {texture: ground_normal.clone(), scale: 1.0},
-            roughness: MaterialProperty::Texture(ground_roughness.clone()),
-            metallic: MaterialProperty::Value(0.0),
-            texture_scale: Vec2::new(10.0, 10.0),
-            ..Default::default()
-        });
-        let ground_mesh = Arc::new(Mesh::from_mesh_data(&MeshData::subdiv_quad(1)));
-        let ground = RenderObject::new(
-            Transform::new(
-                Vec3::new(0.0, -0.5, 0.0),
-                Vec3::new(50.0, 1.0, 50.0),
-                Vec3::zero()
-            ),
-            ground_mesh.clone(),
-            ground_mat.clone(),
-        );
-        
-        let camera = Camera::new(
-            Vec3::new(0.0, 1.0, 0.0),
-            Vec3::new(0.0, 0.0, 0.0),
-            70.0,
-            engine.aspect_ratio(),
-            0.01,
-            100.0,
-        );
+    fn new(engine: &Engine, scene_path: PathBuf) -> Result<Self, String> {
+        let scene = Scene::load(&scene_path, engine.aspect_ratio())?;
+        if scene.cameras().is_empty() {
+            return Err(format!("Scene {:?} has no camera", scene_path));
+        }
 
         Ok(Self {
-            camera,
-            bunny,
-            cube,
-            sphere,
-            ground,
+            scene,
+            scene_path,
+            selected: None,
             speed: 7.0,
             rot_speed: 2.0,
             t: 0.0,
+            day_speed: 0.0,
         })
     }
+    
+    fn camera(&self) -> &Camera {
+        &self.scene.cameras()[0].camera
+    }
+    
+    fn camera_mut(&mut self) -> &mut Camera {
+        &mut self.scene.cameras_mut()[0].camera
+    }
+
+    fn save_scene(&self) {
+        match self.scene.save(&self.scene_path) {
+            Ok(_) => println!("scene saved to {:?}", self.scene_path),
+            Err(e) => eprintln!("saving scene failed: {}", e),
+        }
+    }
+
+    fn select_at_cursor(&mut self, engine: &Engine) {
+        let ray = self.camera().screen_ray(engine.input().mouse_state().pos());
+        self.selected = match self.scene.pick(&ray) {
+            Some((object, hit)) => {
+                println!(
+                    "selected {} at {:?}, normal {:?}, barycentric {:?}",
+                    object.name, hit.point, hit.normal, hit.barycentric
+                );
+                Some(object.name.clone())
+            }
+            None => None,
+        };
+    }
 
     fn reload_shaders(&mut self) {
         println!("Reloading shaders");
         
-        match self.cube.material().shader.reload() {
-            Ok(_) => println!("pbr shader reloaded!"),
-            Err(e) => eprintln!("pbr shader compilation failed: {}", e),
+        for (name, shader) in self.scene.shaders() {
+            match shader.reload() {
+                Ok(_) => println!("{} shader reloaded!", name),
+                Err(e) => eprintln!("{} shader compilation failed: {}", name, e),
+            }
         }
     }
 
     fn handle_movement(&mut self, engine: &Engine, dt: f32) {
+        let speed = self.speed;
+        let rot_speed = self.rot_speed;
+        let camera = self.camera_mut();
         let mut direction = Vec3::new(0.0, 0.0, 0.0);
         let input = engine.input();
 
         if input.is_down(Keycode::W) {
-            direction = direction + self.camera.forward();
+            direction = direction + camera.forward();
         }
         if input.is_down(Keycode::S) {
-            direction = direction + self.camera.backward();
+            direction = direction + camera.backward();
         }
         if input.is_down(Keycode::A) {
-            direction = direction + self.camera.left();
+            direction = direction + camera.left();
         }
         if input.is_down(Keycode::D) {
-            direction = direction + self.camera.right();
+            direction = direction + camera.right();
         }
         if input.is_down(Keycode::E) {
             direction = direction + Vec3::new(0.0, 1.0, 0.0);
@@ -205,39 +112,39 @@ impl GameState {
         }
 
         if input.is_down(Keycode::Left) {
-            let mut rot = self.camera.rotation();
-            rot.y += self.rot_speed * dt;
-            self.camera.set_rotation(rot);
+            let mut rot = camera.rotation();
+            rot.y += rot_speed * dt;
+            camera.set_rotation(rot);
         }
         if input.is_down(Keycode::Right) {
-            let mut rot = self.camera.rotation();
-            rot.y -= self.rot_speed * dt;
-            self.camera.set_rotation(rot);
+            let mut rot = camera.rotation();
+            rot.y -= rot_speed * dt;
+            camera.set_rotation(rot);
         }
         if input.is_down(Keycode::Up) {
-            let mut rot = self.camera.rotation();
-            rot.x = (rot.x - self.rot_speed * dt).max(-DEG_TO_RAD * 89.0);
-            self.camera.set_rotation(rot);
+            let mut rot = camera.rotation();
+            rot.x = (rot.x - rot_speed * dt).max(-DEG_TO_RAD * 89.0);
+            camera.set_rotation(rot);
         }
         if input.is_down(Keycode::Down) {
-            let mut rot = self.camera.rotation();
-            rot.x = (rot.x + self.rot_speed * dt).min(DEG_TO_RAD * 89.0);
-            self.camera.set_rotation(rot);
+            let mut rot = camera.rotation();
+            rot.x = (rot.x + rot_speed * dt).min(DEG_TO_RAD * 89.0);
+            camera.set_rotation(rot);
         }
 
         if glm::length(direction) > 0.0 {
-            self.camera
-                .set_position(self.camera.position() + glm::normalize(direction) * self.speed * dt);
+            camera
+                .set_position(camera.position() + glm::normalize(direction) * speed * dt);
         }
         
         // shitty mouse movement
         // {
         //     let mouse_state = input.mouse_state();
-        //     let mut rot = self.camera.rotation();
-        //     rot.y -= mouse_state.pos().x * self.rot_speed * 15.0 * dt;
-        //     rot.x -= mouse_state.pos().y * self.rot_speed * 15.0 * dt;
+        //     let mut rot = camera.rotation();
+        //     rot.y -= mouse_state.pos().x * rot_speed * 15.0 * dt;
+        //     rot.x -= mouse_state.pos().y * rot_speed * 15.0 * dt;
         //     rot.x = rot.x.min(DEG_TO_RAD * 89.0).max(-DEG_TO_RAD * 89.0);
-        //     self.camera.set_rotation(rot);
+        //     camera.set_rotation(rot);
         // }
         // println!("mouse_state: pos {:?}, prev_pos {:?}", input.mouse_state().pos(), input.mouse_state().prev_pos());
     }
@@ -261,12 +168,24 @@ impl KadseApp {
 
 impl App for KadseApp {
     fn init(&mut self, engine: &mut Engine) -> Result<(), String> {
-        let state = GameState::new(engine)?;
+        let scene_path = std::env::args()
+            .nth(1)
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_SCENE));
+        let state = GameState::new(engine, scene_path)?;
         self.state = Some(state);
+
+        let vignette = PostProcessPass::from_fragment_source(
+            "vignette",
+            include_str!("../res/shaders/vignette.frag").to_string(),
+        )?
+        .with_param("strength", 0.4)
+        .with_param("radius", 0.75);
+        engine.post_process().push(vignette);
         Ok(())
     }
 
-    fn handle_event(&mut self, _engine: &mut Engine, event: &Event) -> AppControl {
+    fn handle_event(&mut self, engine: &mut Engine, event: &Event) -> AppControl {
         match event {
             Event::Quit { .. } => AppControl::Exit,
             Event::KeyDown {
@@ -280,6 +199,141 @@ impl App for KadseApp {
                 self.state_mut().reload_shaders();
                 AppControl::Continue
             }
+            Event::KeyDown {
+                keycode: Some(Keycode::F5),
+                ..
+            } => {
+                self.state_mut().save_scene();
+                AppControl::Continue
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::F3),
+                ..
+            } => {
+                let stats = engine.renderer().stats();
+                println!(
+                    "drawn: {}, culled: {}, draw calls: {}, instanced: {}, shader binds: {}, material binds: {}",
+                    stats.drawn, stats.culled, stats.draw_calls, stats.instanced, stats.shader_binds, stats.material_binds
+                );
+                AppControl::Continue
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::F4),
+                ..
+            } => {
+                let renderer = engine.renderer();
+                renderer.set_frustum_culling(!renderer.frustum_culling());
+                println!("frustum culling: {}", renderer.frustum_culling());
+                AppControl::Continue
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::F6),
+                ..
+            } => {
+                let renderer = engine.renderer();
+                renderer.set_shadows_enabled(!renderer.shadows_enabled());
+                println!("shadows: {}", renderer.shadows_enabled());
+                AppControl::Continue
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::F7),
+                ..
+            } => {
+                let renderer = engine.renderer();
+                let mut bloom = *renderer.bloom_settings();
+                bloom.enabled = !bloom.enabled;
+                renderer.set_bloom_settings(bloom);
+                println!("bloom: {}", bloom.enabled);
+                AppControl::Continue
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::F8),
+                ..
+            } => {
+                let renderer = engine.renderer();
+                let mut tonemap = *renderer.tonemap_settings();
+                tonemap.tonemapper = tonemap.tonemapper.next();
+                renderer.set_tonemap_settings(tonemap);
+                println!("tonemapper: {:?}", tonemap.tonemapper);
+                AppControl::Continue
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::F9),
+                ..
+            } => {
+                let renderer = engine.renderer();
+                let mut tonemap = *renderer.tonemap_settings();
+                tonemap.auto_exposure = match tonemap.auto_exposure {
+                    Some(_) => None,
+                    None => Some(AutoExposureSettings::default()),
+                };
+                renderer.set_tonemap_settings(tonemap);
+                println!("auto exposure: {}", tonemap.auto_exposure.is_some());
+                AppControl::Continue
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::F10),
+                ..
+            } => {
+                let post_process = engine.post_process();
+                let enabled = post_process.pass("vignette").is_some_and(|p| p.enabled());
+                post_process.set_enabled("vignette", !enabled);
+                println!("vignette: {}", !enabled);
+                AppControl::Continue
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::F11),
+                ..
+            } => {
+                let renderer = engine.renderer();
+                let mut ssao = *renderer.ssao_settings();
+                ssao.enabled = !ssao.enabled;
+                renderer.set_ssao_settings(ssao);
+                println!("ssao: {}", ssao.enabled);
+                AppControl::Continue
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::F12),
+                ..
+            } => {
+                let renderer = engine.renderer();
+                let mut ssao = *renderer.ssao_settings();
+                ssao.debug_view = !ssao.debug_view;
+                renderer.set_ssao_settings(ssao);
+                println!("ssao debug view: {}", ssao.debug_view);
+                AppControl::Continue
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::G),
+                ..
+            } => {
+                let renderer = engine.renderer();
+                let path = match renderer.render_path() {
+                    RenderPath::Forward => RenderPath::Deferred,
+                    RenderPath::Deferred => RenderPath::Forward,
+                };
+                match renderer.set_render_path(path) {
+                    Ok(()) => println!("render path: {:?}", path),
+                    Err(e) => eprintln!("Could not switch to the {:?} render path: {}", path, e),
+                }
+                AppControl::Continue
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::T),
+                ..
+            } => {
+                let state = self.state_mut();
+                state.day_speed = if state.day_speed == 0.0 { 1.0 } else { 0.0 };
+                println!("time of day animation: {}", state.day_speed != 0.0);
+                AppControl::Continue
+            }
+            Event::MouseButtonDown {
+                mouse_btn: MouseButton::Left,
+                ..
+            } => {
+                self.state_mut().select_at_cursor(engine);
+                AppControl::Continue
+            }
             Event::MouseWheel { y, .. } => {
                 let state = self.state_mut();
                 state.speed = (state.speed + (*y * 10.0 * (1.0 / 60.0))).max(0.1);
@@ -292,26 +346,30 @@ impl App for KadseApp {
 
     fn update(&mut self, engine: &mut Engine, time: Time) {
         let state = self.state_mut();
-        state.camera.set_aspect_ratio(engine.aspect_ratio());
+        state.camera_mut().set_aspect_ratio(engine.aspect_ratio());
         state.handle_movement(engine, time.dt());
+
+        let camera_position = state.camera().position();
+        if let Some(terrain) = state.scene.terrain_mut() {
+            terrain.update(camera_position);
+        }
         
-        let pos = state.bunny.transform().pos();
-        let rot = state.bunny.transform().rotation();
-        state
-            .bunny
-            .transform_mut()
-            .set_rotation(Vec3::new(rot.x, rot.y + 0.02, rot.z));
-        state
-            .bunny
-            .transform_mut()
-            .set_pos(Vec3::new(pos.x, glm::sin(time.elapsed_secs() * 2.0) + 2.0 + 1.0, pos.z));
-        
-        // state
-        //     .cube
-        //     .transform_mut()
-        //     .set_rotation(Vec3::new(rot.x, rot.y + 0.02, rot.z));
+        if let Some(bunny) = state.scene.object_mut("bunny") {
+            let pos = bunny.transform().pos();
+            let rot = bunny.transform().rotation();
+            bunny
+                .transform_mut()
+                .set_rotation(Vec3::new(rot.x, rot.y + 0.02, rot.z));
+            bunny
+                .transform_mut()
+                .set_pos(Vec3::new(pos.x, glm::sin(time.elapsed_secs() * 2.0) + 2.0 + 1.0, pos.z));
+        }
         
         state.t += time.dt();
+        let day_speed = state.day_speed;
+        if let Some(atmosphere) = state.scene.atmosphere_mut() {
+            atmosphere.time_of_day = (atmosphere.time_of_day + day_speed * time.dt()).rem_euclid(24.0);
+        }
     }
 
     fn render(&mut self, engine: &mut Engine) {
@@ -319,33 +377,37 @@ impl App for KadseApp {
         renderer.begin_frame(Vec4::new(189.0 / 255.0, 220.0 / 255.0, 237.0 / 255.0, 1.0));
         
         let state = self.state_mut();
-        let camera = &state.camera;
+        let camera = state.camera();
 
-        
-        renderer.render(
-            &state.bunny,
-            camera
-        );
-        
-        renderer.render(
-            &state.cube,
-            camera
-        );
-        
-         renderer.render(
-            &state.sphere,
-            camera
-        );
-        
-        renderer.render(
-            &state.ground,
-            camera
+        renderer.set_environment(state.scene.environment().cloned());
+        renderer.set_sky(state.scene.sky().cloned());
+        if let Some(atmosphere) = state.scene.atmosphere() {
+            renderer.add_light(atmosphere.sun_light());
+        }
+        for light in state.scene.lights() {
+            renderer.add_light(*light);
+        }
+        // warm light circling the bunny
+        let orbit = Vec3::new(glm::cos(state.t) * 4.0, 2.5, glm::sin(state.t) * 4.0);
+        renderer.add_light(
+            Light::point(orbit, Vec3::new(1.0, 0.6, 0.3), 40.0, 12.0)
+                .with_shadow(PointShadowSettings::new(512, 0.1, 12.0)),
         );
+
+        for object in state.scene.objects() {
+            renderer.render(
+                &object.object,
+                camera
+            );
+        }
+        if let Some(terrain) = state.scene.terrain() {
+            terrain.render(renderer, camera);
+        }
         
         renderer.end_frame();
         
         if engine.aspect_ratio() > 0.0 {
-            state.camera.set_aspect_ratio(engine.aspect_ratio());
+            state.camera_mut().set_aspect_ratio(engine.aspect_ratio());
         }
     }
 }
@@ -355,7 +417,7 @@ fn main() -> Result<(), String> {
     let config = AppConfig {
         width: 1920,
         height: 1080,
-        title: "Hellowo Katse",
+        title: "Hellowo Katse".to_string(),
         max_fps: Some(60),
     };
 